@@ -0,0 +1,90 @@
+use crate::anomaly::anomaly_mode::AnomalyMode;
+use crate::anomaly::word_cutoff::OutlierCutoffs;
+use crate::anomaly::Anomaly;
+use crate::count_overflow::CountOverflow;
+use crate::site_summary::SiteSummary;
+use crate::tag_summary::TagSummary;
+use std::collections::HashMap;
+
+/// Finds the outliers of a single file. If they're excluded, also adds them up by site, so
+/// they can be left out of the rankings
+pub struct AnomalyDetector<'a> {
+    mode: AnomalyMode,
+    cutoffs: Option<&'a OutlierCutoffs>,
+    file: &'a str,
+    found: Vec<(String, Anomaly)>,
+    excluded: HashMap<String, SiteSummary>,
+}
+
+impl<'a> AnomalyDetector<'a> {
+    /// Creates the detector of the given file. Without cutoffs, nothing is an outlier
+    pub fn new(
+        mode: AnomalyMode,
+        cutoffs: Option<&'a OutlierCutoffs>,
+        file: &'a str,
+    ) -> AnomalyDetector<'a> {
+        AnomalyDetector {
+            mode,
+            cutoffs,
+            file,
+            found: Vec::new(),
+            excluded: HashMap::new(),
+        }
+    }
+
+    /// Checks the question at the given line (starting at 1), recording it if it's an outlier.
+    /// The question is counted either way. Fails if a count of the excluded questions overflows
+    pub fn check(
+        &mut self,
+        line: usize,
+        site: &str,
+        words: u32,
+        tags: &[String],
+    ) -> Result<(), CountOverflow> {
+        let cutoffs = match (self.mode.is_enabled(), self.cutoffs) {
+            (true, Some(cutoffs)) => cutoffs,
+            _ => return Ok(()),
+        };
+        let site_cutoff = cutoffs.sites.get(site);
+        let mut outlier_tags: Vec<String> = tags
+            .iter()
+            .filter(|tag| cutoffs.tags.get(*tag).is_some_and(|c| c.is_outlier(words)))
+            .cloned()
+            .collect();
+        let site_outlier = site_cutoff.is_some_and(|c| c.is_outlier(words));
+        if !site_outlier && outlier_tags.is_empty() {
+            return Ok(());
+        }
+
+        outlier_tags.sort();
+        outlier_tags.dedup();
+        let anomaly = Anomaly {
+            file: self.file.to_string(),
+            line,
+            words,
+            score: site_cutoff
+                .and_then(|c| c.score(words))
+                .map(|score| score as f32),
+            tags: outlier_tags,
+        };
+        self.found.push((site.to_string(), anomaly));
+
+        if self.mode == AnomalyMode::Exclude {
+            let excluded = self
+                .excluded
+                .entry(site.to_string())
+                .or_insert_with(|| SiteSummary::new(site, Vec::new()));
+            excluded.add_question(words)?;
+            for tag in tags {
+                excluded.add_tag(TagSummary::from_question(tag.clone(), words))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the outliers found, as `(site, anomaly)` pairs, and the summary of the excluded
+    /// ones by site (empty unless excluding)
+    pub fn into_anomalies(self) -> (Vec<(String, Anomaly)>, HashMap<String, SiteSummary>) {
+        (self.found, self.excluded)
+    }
+}
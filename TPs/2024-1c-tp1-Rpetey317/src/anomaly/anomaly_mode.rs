@@ -0,0 +1,31 @@
+/// What is done with questions whose word count is an extreme outlier
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AnomalyMode {
+    /// Outliers are not looked for
+    #[default]
+    Off,
+    /// Outliers are reported, but still counted
+    Report,
+    /// Outliers are reported, still counted, but left out of the chattiness rankings
+    Exclude,
+}
+
+impl AnomalyMode {
+    /// Parses a mode from its command line name
+    pub fn parse(name: &str) -> Result<AnomalyMode, String> {
+        match name {
+            "off" => Ok(AnomalyMode::Off),
+            "report" => Ok(AnomalyMode::Report),
+            "exclude" => Ok(AnomalyMode::Exclude),
+            _ => Err(format!(
+                "Unknown anomalies mode: {}. Use one of [off, report, exclude]",
+                name
+            )),
+        }
+    }
+
+    /// Whether outliers are looked for at all
+    pub fn is_enabled(&self) -> bool {
+        *self != AnomalyMode::Off
+    }
+}
@@ -0,0 +1,66 @@
+use crate::anomaly::word_cutoff::{OutlierCutoffs, WordCutoff};
+use crate::anomaly::Anomaly;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Questions whose word count is an extreme outlier for their site or tag, by site
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AnomalyReport {
+    /// Whether the outliers were left out of the chattiness rankings. They are counted either way
+    pub excluded: bool,
+    /// Statistics and outliers of every site with questions
+    pub sites: BTreeMap<String, SiteAnomalies>,
+}
+
+impl AnomalyReport {
+    /// Groups the outliers found, as `(site, anomaly)` pairs, by site
+    pub(crate) fn new(
+        cutoffs: &OutlierCutoffs,
+        found: Vec<(String, Anomaly)>,
+        excluded: bool,
+    ) -> AnomalyReport {
+        let mut sites: BTreeMap<String, SiteAnomalies> = cutoffs
+            .sites
+            .iter()
+            .map(|(name, cutoff)| (name.clone(), SiteAnomalies::new(cutoff)))
+            .collect();
+        for (site, anomaly) in found {
+            if let Some(report) = sites.get_mut(&site) {
+                report.questions.push(anomaly);
+            }
+        }
+        for report in sites.values_mut() {
+            report
+                .questions
+                .sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+        }
+        AnomalyReport { excluded, sites }
+    }
+}
+
+/// Outliers found in a site, along with the statistics they were found with
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SiteAnomalies {
+    /// Median word count of the questions of the site
+    pub median_words: f32,
+    /// Median absolute deviation of their word counts
+    pub mad: f32,
+    /// Word count above which questions are outliers for the site. Not present if the site is
+    /// too small or its word counts too uniform to tell
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_words: Option<f32>,
+    /// Outliers of the site, by file and line
+    pub questions: Vec<Anomaly>,
+}
+
+impl SiteAnomalies {
+    /// Creates the report of a site, with no outliers yet
+    pub(crate) fn new(cutoff: &WordCutoff) -> SiteAnomalies {
+        SiteAnomalies {
+            median_words: cutoff.median_words as f32,
+            mad: cutoff.mad as f32,
+            max_words: cutoff.max_words().map(|max| max as f32),
+            questions: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+pub(crate) mod anomaly_detector;
+pub mod anomaly_mode;
+pub mod anomaly_report;
+pub(crate) mod word_cutoff;
+pub(crate) mod word_histogram;
+
+use serde::{Deserialize, Serialize};
+
+/// A question whose word count is an extreme outlier for its site or for some of its tags
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Anomaly {
+    /// File the question was read from
+    pub file: String,
+    /// Line of the question in its file, starting at 1
+    pub line: usize,
+    /// Word count of the question
+    pub words: u32,
+    /// Modified z-score of the word count among the questions of its site, if it could be computed
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub score: Option<f32>,
+    /// Tags the question is an outlier for, sorted by name
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub tags: Vec<String>,
+}
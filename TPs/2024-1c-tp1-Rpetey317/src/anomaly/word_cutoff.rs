@@ -0,0 +1,112 @@
+use crate::anomaly::word_histogram::WordHistogram;
+use std::collections::HashMap;
+
+/// Modified z-score above which a word count is an extreme outlier (Iglewicz and Hoaglin)
+pub const OUTLIER_SCORE: f64 = 3.5;
+
+/// Distributions with fewer questions than this are too small to tell outliers apart
+pub const MIN_QUESTIONS: u64 = 10;
+
+/// Scales the MAD so it estimates the standard deviation of a normal distribution
+const MAD_SCALE: f64 = 1.4826;
+
+/// Same, for the mean absolute deviation. Used when most word counts are equal and the MAD is 0
+const MEAN_AD_SCALE: f64 = 1.2533;
+
+/// Robust statistics of a word count distribution, used to score how far a question is from it
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordCutoff {
+    /// Median word count
+    pub median_words: f64,
+    /// Median absolute deviation of the word counts
+    pub mad: f64,
+    /// Estimated standard deviation. None if outliers can't be told apart
+    scale: Option<f64>,
+}
+
+impl WordCutoff {
+    /// Computes the statistics of the given distribution. None if it has no questions
+    pub fn from_histogram(histogram: &WordHistogram) -> Option<WordCutoff> {
+        let median_words = histogram.median()?;
+        let mad = histogram.median_absolute_deviation(median_words)?;
+        let scale = if mad > 0.0 {
+            mad * MAD_SCALE
+        } else {
+            histogram.mean_absolute_deviation(median_words)? * MEAN_AD_SCALE
+        };
+        let usable = histogram.len() >= MIN_QUESTIONS && scale > 0.0;
+        Some(WordCutoff {
+            median_words,
+            mad,
+            scale: usable.then_some(scale),
+        })
+    }
+
+    /// Modified z-score of a word count: how many estimated standard deviations it is above
+    /// the median. None if the distribution is too small or has no spread
+    pub fn score(&self, words: u32) -> Option<f64> {
+        self.scale
+            .map(|scale| (words as f64 - self.median_words) / scale)
+    }
+
+    /// Whether the word count is an extreme outlier. Only long questions are
+    pub fn is_outlier(&self, words: u32) -> bool {
+        self.score(words).is_some_and(|score| score > OUTLIER_SCORE)
+    }
+
+    /// Word count above which questions are outliers, if there is one
+    pub fn max_words(&self) -> Option<f64> {
+        self.scale
+            .map(|scale| self.median_words + OUTLIER_SCORE * scale)
+    }
+}
+
+/// Word count statistics of every site and every tag (over all sites), by name
+#[derive(Debug, Default)]
+pub struct OutlierCutoffs {
+    /// Statistics of the questions of each site
+    pub sites: HashMap<String, WordCutoff>,
+    /// Statistics of the questions of each tag
+    pub tags: HashMap<String, WordCutoff>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::WordCutoff;
+    use crate::anomaly::word_histogram::WordHistogram;
+
+    fn cutoff(words: &[u32]) -> WordCutoff {
+        let mut histogram = WordHistogram::default();
+        for w in words {
+            histogram.add(*w);
+        }
+        WordCutoff::from_histogram(&histogram).unwrap()
+    }
+
+    #[test]
+    fn extreme_long_questions_are_outliers() {
+        let c = cutoff(&[10, 12, 8, 11, 9, 10, 13, 10, 9, 11, 5000]);
+        assert_eq!(c.median_words, 10.0);
+        assert_eq!(c.mad, 1.0);
+        assert!(c.is_outlier(5000));
+        assert!(!c.is_outlier(14));
+        assert!(!c.is_outlier(1));
+    }
+
+    #[test]
+    fn mean_deviation_is_used_without_mad() {
+        let c = cutoff(&[10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 2000]);
+        assert_eq!(c.mad, 0.0);
+        assert!(c.is_outlier(2000));
+        assert!(!c.is_outlier(10));
+    }
+
+    #[test]
+    fn small_or_flat_distributions_have_no_outliers() {
+        let c = cutoff(&[10, 10, 5000]);
+        assert_eq!(c.score(5000), None);
+        assert!(!c.is_outlier(5000));
+        let c = cutoff(&[7; 20]);
+        assert_eq!(c.max_words(), None);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::anomaly::word_cutoff::{OutlierCutoffs, WordCutoff};
+use std::collections::{BTreeMap, HashMap};
+
+/// Number of questions with each word count.
+///
+/// Enough to get the exact median and MAD of a distribution without keeping every question.
+#[derive(Debug, Clone, Default)]
+pub struct WordHistogram {
+    counts: BTreeMap<u32, u64>,
+    total: u64,
+}
+
+impl WordHistogram {
+    /// Adds a question with the given word count
+    pub fn add(&mut self, words: u32) {
+        *self.counts.entry(words).or_insert(0) += 1;
+        self.total += 1;
+    }
+
+    /// Adds every question of another histogram
+    pub fn merge(&mut self, other: &WordHistogram) {
+        for (words, count) in &other.counts {
+            *self.counts.entry(*words).or_insert(0) += count;
+        }
+        self.total += other.total;
+    }
+
+    /// N° of questions
+    pub fn len(&self) -> u64 {
+        self.total
+    }
+
+    /// Median word count. None if there are no questions
+    pub fn median(&self) -> Option<f64> {
+        let values: Vec<(f64, u64)> = self
+            .counts
+            .iter()
+            .map(|(words, count)| (*words as f64, *count))
+            .collect();
+        weighted_median(&values, self.total)
+    }
+
+    /// Median absolute deviation of the word counts from the given median
+    pub fn median_absolute_deviation(&self, median: f64) -> Option<f64> {
+        let mut deviations: Vec<(f64, u64)> = self
+            .counts
+            .iter()
+            .map(|(words, count)| ((*words as f64 - median).abs(), *count))
+            .collect();
+        deviations.sort_by(|a, b| a.0.total_cmp(&b.0));
+        weighted_median(&deviations, self.total)
+    }
+
+    /// Mean absolute deviation of the word counts from the given median
+    pub fn mean_absolute_deviation(&self, median: f64) -> Option<f64> {
+        if self.total == 0 {
+            return None;
+        }
+        let sum: f64 = self
+            .counts
+            .iter()
+            .map(|(words, count)| (*words as f64 - median).abs() * *count as f64)
+            .sum();
+        Some(sum / self.total as f64)
+    }
+}
+
+/// Word count distribution of the questions of each site and of each tag, by name
+#[derive(Debug, Default)]
+pub struct WordDistributions {
+    sites: HashMap<String, WordHistogram>,
+    tags: HashMap<String, WordHistogram>,
+}
+
+impl WordDistributions {
+    /// Adds a question of the given site, with the given tags
+    pub fn add(&mut self, site: &str, words: u32, tags: &[String]) {
+        if !self.sites.contains_key(site) {
+            self.sites
+                .insert(site.to_string(), WordHistogram::default());
+        }
+        if let Some(histogram) = self.sites.get_mut(site) {
+            histogram.add(words);
+        }
+        for tag in tags {
+            self.tags.entry(tag.clone()).or_default().add(words);
+        }
+    }
+
+    /// Adds every question of another set of distributions
+    pub fn merge(mut self, other: WordDistributions) -> WordDistributions {
+        for (site, histogram) in other.sites {
+            self.sites.entry(site).or_default().merge(&histogram);
+        }
+        for (tag, histogram) in other.tags {
+            self.tags.entry(tag).or_default().merge(&histogram);
+        }
+        self
+    }
+
+    /// Computes the statistics outliers are found with
+    pub fn cutoffs(&self) -> OutlierCutoffs {
+        let cutoffs = |histograms: &HashMap<String, WordHistogram>| {
+            histograms
+                .iter()
+                .filter_map(|(name, h)| WordCutoff::from_histogram(h).map(|c| (name.clone(), c)))
+                .collect()
+        };
+        OutlierCutoffs {
+            sites: cutoffs(&self.sites),
+            tags: cutoffs(&self.tags),
+        }
+    }
+}
+
+/// Median of sorted `(value, count)` pairs whose counts add up to `total`
+fn weighted_median(values: &[(f64, u64)], total: u64) -> Option<f64> {
+    if total == 0 {
+        return None;
+    }
+    let value_at = |position: u64| {
+        let mut seen = 0;
+        for (value, count) in values {
+            seen += count;
+            if seen > position {
+                return *value;
+            }
+        }
+        values.last().map(|(value, _)| *value).unwrap_or_default()
+    };
+    Some((value_at((total - 1) / 2) + value_at(total / 2)) / 2.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::WordHistogram;
+
+    fn histogram(words: &[u32]) -> WordHistogram {
+        let mut histogram = WordHistogram::default();
+        for w in words {
+            histogram.add(*w);
+        }
+        histogram
+    }
+
+    #[test]
+    fn median_of_odd_and_even_counts() {
+        assert_eq!(histogram(&[]).median(), None);
+        assert_eq!(histogram(&[5, 1, 3]).median(), Some(3.0));
+        assert_eq!(histogram(&[1, 2, 3, 10]).median(), Some(2.5));
+        assert_eq!(histogram(&[4, 4, 4, 9]).median(), Some(4.0));
+    }
+
+    #[test]
+    fn deviations_from_the_median() {
+        let h = histogram(&[1, 1, 2, 2, 4, 6, 9]);
+        assert_eq!(h.median(), Some(2.0));
+        assert_eq!(h.median_absolute_deviation(2.0), Some(1.0));
+        assert_eq!(h.mean_absolute_deviation(2.0), Some(15.0 / 7.0));
+    }
+
+    #[test]
+    fn merged_histograms_count_everything() {
+        let mut h = histogram(&[1, 2]);
+        h.merge(&histogram(&[2, 3, 100]));
+        assert_eq!(h.len(), 5);
+        assert_eq!(h.median(), Some(2.0));
+    }
+}
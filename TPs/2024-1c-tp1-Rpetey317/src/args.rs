@@ -0,0 +1,439 @@
+use crate::glob_pattern::GlobPattern;
+use crate::output::output_format::OutputFormat;
+use crate::site_name_pattern::SiteNamePattern;
+use std::time::Duration;
+use tp1::{AnomalyMode, DedupMode, Sampling};
+
+/// What the program does with the summary
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Print the summary to stdout (the default)
+    Summarize,
+    /// Answer HTTP queries about the summary on localhost
+    Serve,
+}
+
+/// Command line arguments of the summarizer
+#[derive(Debug)]
+pub struct Args {
+    /// What to do with the summary
+    pub command: Command,
+    /// N° of worker threads
+    pub n_threads: usize,
+    /// Path to a schema file mapping input fields. Uses the StackExchange schema if not given
+    pub schema_file: Option<String>,
+    /// Whether subdirectories of the data directory are looked into too
+    pub recursive: bool,
+    /// Patterns input files must match one of
+    pub include: Vec<GlobPattern>,
+    /// Patterns input files must not match
+    pub exclude: Vec<GlobPattern>,
+    /// Whether the input files are listed in stdin instead of found in the data directory
+    pub from_stdin: bool,
+    /// How the site of each input file is named
+    pub site_name: SiteNamePattern,
+    /// Format of the summary printed to stdout
+    pub format: OutputFormat,
+    /// Directory the summary is written to, split by site, instead of printed to stdout
+    pub out_dir: Option<String>,
+    /// Whether global tags include the sites they appear in
+    pub tag_sites: bool,
+    /// Which repeated questions are dropped before counting
+    pub dedup: DedupMode,
+    /// What is done with questions whose word count is an extreme outlier
+    pub anomalies: AnomalyMode,
+    /// Fraction of the input lines read and its seed, if estimating from a sample
+    pub sample: Option<Sampling>,
+    /// Whether live progress and a timing report are printed to stderr
+    pub progress: bool,
+    /// Path to a file where the timing report is written
+    pub timings_file: Option<String>,
+    /// Time after which the run stops and prints a partial summary
+    pub time_budget: Option<Duration>,
+    /// Path to a SQLite database where the summary is also exported
+    pub sqlite_file: Option<String>,
+    /// Whether the tables already in the SQLite database are replaced
+    pub overwrite: bool,
+    /// Port the query server listens on
+    pub port: u16,
+    /// Summary JSON the query server loads instead of processing the data directory
+    pub load_file: Option<String>,
+}
+
+impl Args {
+    /// Parses command line arguments (without the program name).
+    ///
+    /// The arguments may start with the `serve` subcommand. The first positional argument is
+    /// the number of threads, which defaults to 4 if it's not given or is not a valid number.
+    /// Every other option is a `--name value` pair.
+    pub fn parse_args(args: &[String]) -> Result<Args, String> {
+        let (command, args) = match args.first().map(String::as_str) {
+            Some("serve") => (Command::Serve, &args[1..]),
+            _ => (Command::Summarize, args),
+        };
+        let mut n_threads = None;
+        let mut schema_file = None;
+        let mut recursive = false;
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        let mut from_stdin = false;
+        let mut site_name = SiteNamePattern::default();
+        let mut format = None;
+        let mut out_dir = None;
+        let mut sqlite_file = None;
+        let mut overwrite = false;
+        let mut tag_sites = false;
+        let mut dedup = DedupMode::Off;
+        let mut anomalies = AnomalyMode::Off;
+        let mut fraction = None;
+        let mut seed = 0;
+        let mut progress = false;
+        let mut timings_file = None;
+        let mut time_budget = None;
+        let mut port = 8080;
+        let mut load_file = None;
+
+        let mut i = 0;
+        while i < args.len() {
+            match args[i].as_str() {
+                "-s" | "--schema" => {
+                    schema_file = Some(value_of(args, i)?.to_string());
+                    i += 1;
+                }
+                "-r" | "--recursive" => recursive = true,
+                "--include" => {
+                    include.push(GlobPattern::parse(value_of(args, i)?)?);
+                    i += 1;
+                }
+                "--exclude" => {
+                    exclude.push(GlobPattern::parse(value_of(args, i)?)?);
+                    i += 1;
+                }
+                "--stdin" => from_stdin = true,
+                "--site-name" => {
+                    site_name = SiteNamePattern::parse(value_of(args, i)?)?;
+                    i += 1;
+                }
+                "-f" | "--format" => {
+                    format = Some(OutputFormat::parse(value_of(args, i)?)?);
+                    i += 1;
+                }
+                "-o" | "--out-dir" => {
+                    out_dir = Some(value_of(args, i)?.to_string());
+                    i += 1;
+                }
+                "--tag-sites" => tag_sites = true,
+                "--dedup" => {
+                    dedup = DedupMode::parse(value_of(args, i)?)?;
+                    i += 1;
+                }
+                "--anomalies" => {
+                    anomalies = AnomalyMode::parse(value_of(args, i)?)?;
+                    i += 1;
+                }
+                "--sample" => {
+                    let value = value_of(args, i)?;
+                    let parsed = value
+                        .parse::<f64>()
+                        .map_err(|_| format!("Invalid sample fraction: {}", value))?;
+                    fraction = Some(parsed);
+                    i += 1;
+                }
+                "--seed" => {
+                    let value = value_of(args, i)?;
+                    seed = value
+                        .parse()
+                        .map_err(|_| format!("Invalid seed: {}", value))?;
+                    i += 1;
+                }
+                "--progress" => progress = true,
+                "--timings" => {
+                    timings_file = Some(value_of(args, i)?.to_string());
+                    i += 1;
+                }
+                "--time-budget" => {
+                    time_budget = Some(parse_duration(value_of(args, i)?)?);
+                    i += 1;
+                }
+                "--sqlite" => {
+                    sqlite_file = Some(value_of(args, i)?.to_string());
+                    i += 1;
+                }
+                "--overwrite" => overwrite = true,
+                "-p" | "--port" => {
+                    let value = value_of(args, i)?;
+                    port = value
+                        .parse()
+                        .map_err(|_| format!("Invalid port: {}", value))?;
+                    i += 1;
+                }
+                "-l" | "--load" => {
+                    load_file = Some(value_of(args, i)?.to_string());
+                    i += 1;
+                }
+                "-h" | "--help" => return Err(help()),
+                arg if arg.starts_with('-') => {
+                    return Err(format!(
+                        "Unknown option: {}. Use -h | --help for possible arguments",
+                        arg
+                    ))
+                }
+                arg => {
+                    if n_threads.is_none() {
+                        n_threads = Some(arg.parse::<usize>().unwrap_or(4));
+                    }
+                }
+            }
+            i += 1;
+        }
+        if format.is_some() && out_dir.is_some() {
+            return Err(
+                "--format can't be used with --out-dir, which always writes JSON".to_string(),
+            );
+        }
+
+        Ok(Args {
+            command,
+            n_threads: n_threads.unwrap_or(4),
+            schema_file,
+            recursive,
+            include,
+            exclude,
+            from_stdin,
+            site_name,
+            format: format.unwrap_or(OutputFormat::Json),
+            out_dir,
+            tag_sites,
+            dedup,
+            anomalies,
+            sample: fraction.map(|f| Sampling::new(f, seed)).transpose()?,
+            progress,
+            timings_file,
+            time_budget,
+            sqlite_file,
+            overwrite,
+            port,
+            load_file,
+        })
+    }
+}
+
+/// Returns the value following the option at position `i`
+fn value_of(args: &[String], i: usize) -> Result<&str, String> {
+    args.get(i + 1)
+        .map(String::as_str)
+        .ok_or_else(|| format!("Missing value for {}", args[i]))
+}
+
+/// Parses a duration in seconds, or in minutes or hours with an `m` or `h` suffix (`90`,
+/// `90s`, `1.5m`, `2h`)
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let (number, unit) = match value.char_indices().last() {
+        Some((i, 's')) => (&value[..i], 1.0),
+        Some((i, 'm')) => (&value[..i], 60.0),
+        Some((i, 'h')) => (&value[..i], 3600.0),
+        _ => (value, 1.0),
+    };
+    match number.parse::<f64>() {
+        Ok(n) if n.is_finite() && n >= 0.0 => Ok(Duration::from_secs_f64(n * unit)),
+        _ => Err(format!("Invalid duration: {}", value)),
+    }
+}
+
+/// Usage message
+fn help() -> String {
+    [
+        "Usage: cargo run -- [serve] [threads] [options]",
+        "",
+        "Prints a summary of the jsonl files in the data directory. With `serve`, answers HTTP",
+        "queries about it on localhost instead (/sites, /sites/NAME, /tags/NAME, /top, /totals).",
+        "",
+        "Options:",
+        "  -s, --schema FILE   JSON file mapping input fields (default: StackExchange schema)",
+        "  -r, --recursive     Also look for input files in subdirectories of data",
+        "      --include GLOB  Only read files matching GLOB (default: *.jsonl). Repeatable",
+        "      --exclude GLOB  Don't read files matching GLOB. Repeatable",
+        "      --stdin         Read the input files as a newline-separated list from stdin",
+        "      --site-name PATTERN",
+        "                      Site name of each file, using {file}, {stem}, {parent} and",
+        "                      {path} (default: {file})",
+        "  -f, --format FMT    Output format, one of [json, csv-sites, csv-tags, csv-site-tags,",
+        "                      ndjson, markdown] (default: json)",
+        "  -o, --out-dir DIR   Write the summary to DIR instead of stdout, as JSON files:",
+        "                      sites/<site>.json (each written as soon as its site is done),",
+        "                      tags.json and index.json (totals and rankings). Old site",
+        "                      files in DIR are removed first. Can't be used with --format",
+        "      --tag-sites     Break each global tag down by site, with its dominant site,",
+        "                      and rank the tags used by the most sites",
+        "      --dedup MODE    Count repeated questions once, one of [off, site, global]",
+        "                      (default: off)",
+        "      --anomalies MODE",
+        "                      Report questions with an extreme word count for their site or",
+        "                      tag, one of [off, report, exclude] (default: off). `exclude`",
+        "                      also leaves them out of the chattiness rankings",
+        "      --sample FRACTION",
+        "                      Only read that fraction of the lines, in (0, 1], scaling the",
+        "                      counts up and adding confidence intervals to the chattiness",
+        "      --seed N        Seed of the lines read with --sample (default: 0)",
+        "      --progress      Print live progress and a final JSON timing report to stderr",
+        "      --timings FILE  Write the JSON timing report to a file",
+        "      --time-budget DURATION",
+        "                      Stop after DURATION (seconds, or with an s, m or h suffix)",
+        "                      and print a partial summary. Ctrl-C does the same",
+        "      --sqlite FILE   Also export the summary to a SQLite database",
+        "                      (requires building with --features sqlite)",
+        "      --overwrite     Replace the tables already in the --sqlite database",
+        "  -p, --port PORT     Port of the query server (default: 8080)",
+        "  -l, --load FILE     Serve a previously printed JSON summary instead of the data",
+        "  -h, --help          Print this help message",
+    ]
+    .join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_duration, Args, Command};
+    use crate::output::output_format::OutputFormat;
+    use std::time::Duration;
+    use tp1::AnomalyMode;
+    use tp1::DedupMode;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn threads_default_to_four() {
+        assert_eq!(Args::parse_args(&args("")).unwrap().n_threads, 4);
+        assert_eq!(Args::parse_args(&args("many")).unwrap().n_threads, 4);
+    }
+
+    #[test]
+    fn threads_and_options_are_parsed() {
+        let parsed = Args::parse_args(&args("8 --schema s.json")).unwrap();
+        assert_eq!(parsed.n_threads, 8);
+        assert_eq!(parsed.schema_file, Some("s.json".to_string()));
+        assert_eq!(parsed.format, OutputFormat::Json);
+        assert_eq!(parsed.sqlite_file, None);
+    }
+
+    #[test]
+    fn serve_subcommand_is_parsed() {
+        let parsed = Args::parse_args(&args("serve 2 --port 9000 --load out.json")).unwrap();
+        assert_eq!(parsed.command, Command::Serve);
+        assert_eq!(parsed.n_threads, 2);
+        assert_eq!(parsed.port, 9000);
+        assert_eq!(parsed.load_file, Some("out.json".to_string()));
+        let parsed = Args::parse_args(&args("1")).unwrap();
+        assert_eq!(parsed.command, Command::Summarize);
+        assert_eq!(parsed.port, 8080);
+        assert!(Args::parse_args(&args("serve --port http")).is_err());
+    }
+
+    #[test]
+    fn format_is_parsed() {
+        let parsed = Args::parse_args(&args("--format csv-sites 2")).unwrap();
+        assert_eq!(parsed.n_threads, 2);
+        assert_eq!(parsed.format, OutputFormat::CsvSites);
+        assert!(!parsed.tag_sites);
+        assert!(Args::parse_args(&args("--tag-sites")).unwrap().tag_sites);
+    }
+
+    #[test]
+    fn telemetry_options_are_parsed() {
+        let parsed = Args::parse_args(&args("--progress --timings t.json 3")).unwrap();
+        assert!(parsed.progress);
+        assert_eq!(parsed.timings_file, Some("t.json".to_string()));
+        assert_eq!(parsed.n_threads, 3);
+        assert!(Args::parse_args(&args("--format yaml")).is_err());
+    }
+
+    #[test]
+    fn dedup_is_parsed() {
+        assert_eq!(Args::parse_args(&args("")).unwrap().dedup, DedupMode::Off);
+        let parsed = Args::parse_args(&args("2 --dedup global")).unwrap();
+        assert_eq!(parsed.dedup, DedupMode::Global);
+        assert!(Args::parse_args(&args("--dedup everything")).is_err());
+    }
+
+    #[test]
+    fn anomalies_are_parsed() {
+        assert_eq!(
+            Args::parse_args(&args("")).unwrap().anomalies,
+            AnomalyMode::Off
+        );
+        let parsed = Args::parse_args(&args("--anomalies exclude 3")).unwrap();
+        assert_eq!(parsed.anomalies, AnomalyMode::Exclude);
+        assert_eq!(parsed.n_threads, 3);
+        assert!(Args::parse_args(&args("--anomalies")).is_err());
+    }
+
+    #[test]
+    fn discovery_options_are_parsed() {
+        let parsed = Args::parse_args(&args(
+            "-r --include *.jsonl --include meta/* --exclude *.bak --site-name {parent} 2",
+        ))
+        .unwrap();
+        assert!(parsed.recursive);
+        assert!(!parsed.from_stdin);
+        assert_eq!(parsed.include.len(), 2);
+        assert!(parsed.exclude[0].matches("x/y.bak"));
+        assert_eq!(
+            parsed.site_name.site_name("data/so/1.jsonl", "so/1.jsonl"),
+            "so"
+        );
+        assert_eq!(parsed.n_threads, 2);
+        assert!(Args::parse_args(&args("--stdin")).unwrap().from_stdin);
+        assert!(Args::parse_args(&args("--site-name {dir}")).is_err());
+    }
+
+    #[test]
+    fn time_budget_is_parsed() {
+        assert_eq!(Args::parse_args(&args("")).unwrap().time_budget, None);
+        let parsed = Args::parse_args(&args("--time-budget 1.5m")).unwrap();
+        assert_eq!(parsed.time_budget, Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("2s"), Ok(Duration::from_secs(2)));
+        assert_eq!(parse_duration("1h"), Ok(Duration::from_secs(3600)));
+        assert!(parse_duration("-1").is_err());
+        assert!(parse_duration("soon").is_err());
+    }
+
+    #[test]
+    fn out_dir_is_parsed() {
+        assert_eq!(Args::parse_args(&args("")).unwrap().out_dir, None);
+        let parsed = Args::parse_args(&args("2 -o out")).unwrap();
+        assert_eq!(parsed.out_dir.as_deref(), Some("out"));
+        assert_eq!(parsed.n_threads, 2);
+        assert!(Args::parse_args(&args("--out-dir")).is_err());
+        assert!(Args::parse_args(&args("-o out --format json")).is_err());
+    }
+
+    #[test]
+    fn sampling_is_parsed() {
+        assert_eq!(Args::parse_args(&args("")).unwrap().sample, None);
+        let sample = Args::parse_args(&args("--seed 7 --sample 0.25"))
+            .unwrap()
+            .sample
+            .unwrap();
+        assert_eq!(sample.fraction, 0.25);
+        assert_eq!(sample.seed, 7);
+        assert_eq!(
+            Args::parse_args(&args("--sample 1"))
+                .unwrap()
+                .sample
+                .unwrap()
+                .seed,
+            0
+        );
+        assert!(Args::parse_args(&args("--sample 0")).is_err());
+        assert!(Args::parse_args(&args("--sample half")).is_err());
+        assert!(Args::parse_args(&args("--sample 0.5 --seed -1")).is_err());
+    }
+
+    #[test]
+    fn unknown_and_incomplete_options_fail() {
+        assert!(Args::parse_args(&args("--nope")).is_err());
+        assert!(Args::parse_args(&args("1 --schema")).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+const N_CHATTY: usize = 10;
+
+/// Question and word counts of a tag, as printed by the summarizer
+#[derive(Serialize, Default, Clone)]
+pub struct ExpectedTag {
+    /// N° of questions this tag appears in
+    pub questions: u64,
+    /// Total word count for every question this tag appears in
+    pub words: u64,
+}
+
+/// Question, word and tag counts of a site, as printed by the summarizer
+#[derive(Serialize, Default)]
+pub struct ExpectedSite {
+    /// N° of questions in this site
+    pub questions: u64,
+    /// Total word count for every question in this site
+    pub words: u64,
+    /// Counts of each tag in this site
+    pub tags: BTreeMap<String, ExpectedTag>,
+}
+
+/// Global rankings, as printed by the summarizer
+#[derive(Serialize)]
+struct ExpectedTotals {
+    chatty_sites: Vec<String>,
+    chatty_tags: Vec<String>,
+}
+
+/// The exact summary the summarizer must output for a generated dataset.
+///
+/// Computed independently from the summarizer while the data is generated, so it can be used
+/// as an oracle. Maps are ordered so the file is reproducible.
+#[derive(Serialize)]
+pub struct ExpectedSummary {
+    padron: u32,
+    sites: BTreeMap<String, ExpectedSite>,
+    tags: BTreeMap<String, ExpectedTag>,
+    totals: ExpectedTotals,
+}
+
+impl ExpectedSite {
+    /// Accounts for a new question with the given word count and tags
+    pub fn add_question(&mut self, words: u32, tags: &[String]) {
+        self.questions += 1;
+        self.words += words as u64;
+        for tag in tags {
+            let entry = self.tags.entry(tag.clone()).or_default();
+            entry.questions += 1;
+            entry.words += words as u64;
+        }
+    }
+}
+
+impl ExpectedSummary {
+    /// Builds the full expected summary from the expected summary of each site
+    pub fn new(padron: u32, sites: BTreeMap<String, ExpectedSite>) -> ExpectedSummary {
+        let mut tags: BTreeMap<String, ExpectedTag> = BTreeMap::new();
+        for site in sites.values() {
+            for (name, tag) in &site.tags {
+                let entry = tags.entry(name.clone()).or_default();
+                entry.questions += tag.questions;
+                entry.words += tag.words;
+            }
+        }
+
+        let chatty_sites = chattiest(sites.iter().map(|(n, s)| (n, s.questions, s.words)));
+        let chatty_tags = chattiest(tags.iter().map(|(n, t)| (n, t.questions, t.words)));
+
+        ExpectedSummary {
+            padron,
+            sites,
+            tags,
+            totals: ExpectedTotals {
+                chatty_sites,
+                chatty_tags,
+            },
+        }
+    }
+}
+
+/// Returns the names of the `N_CHATTY` entries with the highest words/questions ratio.
+///
+/// Ties are broken by name, the same way the summarizer does.
+fn chattiest<'a>(entries: impl Iterator<Item = (&'a String, u64, u64)>) -> Vec<String> {
+    let mut ranked: Vec<(&String, f32)> = entries
+        .map(|(name, questions, words)| {
+            let chattiness = if questions == 0 {
+                0.0
+            } else {
+                words as f32 / questions as f32
+            };
+            (name, chattiness)
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(b.0))
+    });
+    ranked
+        .into_iter()
+        .take(N_CHATTY)
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ExpectedSite, ExpectedSummary};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn aggregates_tags_across_sites() {
+        let mut site1 = ExpectedSite::default();
+        site1.add_question(10, &["a".to_string(), "b".to_string()]);
+        let mut site2 = ExpectedSite::default();
+        site2.add_question(4, &["a".to_string()]);
+        let mut sites = BTreeMap::new();
+        sites.insert("s1".to_string(), site1);
+        sites.insert("s2".to_string(), site2);
+
+        let summary = ExpectedSummary::new(1, sites);
+        assert_eq!(summary.tags["a"].questions, 2);
+        assert_eq!(summary.tags["a"].words, 14);
+        assert_eq!(summary.totals.chatty_sites, vec!["s1", "s2"]);
+        assert_eq!(summary.totals.chatty_tags, vec!["b", "a"]);
+    }
+
+    #[test]
+    fn ties_are_broken_by_name() {
+        let mut sites = BTreeMap::new();
+        for name in ["z", "m", "a"] {
+            let mut site = ExpectedSite::default();
+            site.add_question(5, &[]);
+            sites.insert(name.to_string(), site);
+        }
+        let summary = ExpectedSummary::new(1, sites);
+        assert_eq!(summary.totals.chatty_sites, vec!["a", "m", "z"]);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::length_distribution::LengthDistribution;
+
+/// Configuration of a synthetic dataset, parsed from the command line
+pub struct GeneratorConfig {
+    /// Directory where the `.jsonl` files and `expected.json` are written
+    pub out_dir: String,
+    /// N° of sites (one `.jsonl` file each)
+    pub sites: usize,
+    /// N° of questions per site
+    pub questions: usize,
+    /// N° of distinct tags shared by all sites
+    pub tags: usize,
+    /// Exponent of the Zipf distribution used to pick tags
+    pub zipf_exponent: f64,
+    /// N° of tags of each question
+    pub tags_per_question: LengthDistribution,
+    /// N° of words in each title
+    pub title_words: LengthDistribution,
+    /// N° of words in each body
+    pub body_words: LengthDistribution,
+    /// N° of characters of each word
+    pub word_chars: LengthDistribution,
+    /// Seed of the random number generator
+    pub seed: u64,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig {
+            out_dir: "data".to_string(),
+            sites: 8,
+            questions: 10_000,
+            tags: 500,
+            zipf_exponent: 1.1,
+            tags_per_question: LengthDistribution::Uniform(1, 5),
+            title_words: LengthDistribution::Uniform(3, 15),
+            body_words: LengthDistribution::Geometric(80.0),
+            word_chars: LengthDistribution::Uniform(1, 10),
+            seed: 0,
+        }
+    }
+}
+
+impl GeneratorConfig {
+    /// Parses the configuration from command line arguments (without the program name).
+    ///
+    /// Every option is a `--name value` pair; options not given keep their default value.
+    pub fn from_args(args: &[String]) -> Result<GeneratorConfig, String> {
+        let mut config = GeneratorConfig::default();
+        let mut iter = args.iter();
+        while let Some(flag) = iter.next() {
+            let value = iter
+                .next()
+                .ok_or_else(|| format!("Missing value for {}", flag))?;
+            match flag.as_str() {
+                "--out" => config.out_dir = value.clone(),
+                "--sites" => config.sites = parse_number(flag, value)?,
+                "--questions" => config.questions = parse_number(flag, value)?,
+                "--tags" => config.tags = parse_number(flag, value)?,
+                "--zipf" => config.zipf_exponent = parse_number(flag, value)?,
+                "--tags-per-question" => {
+                    config.tags_per_question = LengthDistribution::parse(value)?
+                }
+                "--title-words" => config.title_words = LengthDistribution::parse(value)?,
+                "--body-words" => config.body_words = LengthDistribution::parse(value)?,
+                "--word-chars" => config.word_chars = LengthDistribution::parse(value)?,
+                "--seed" => config.seed = parse_number(flag, value)?,
+                _ => return Err(format!("Unknown option: {}", flag)),
+            }
+        }
+        if config.tags == 0 {
+            return Err("There must be at least one tag".to_string());
+        }
+        Ok(config)
+    }
+}
+
+/// Parses a numeric option value, naming the option on error
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
+
+#[cfg(test)]
+mod test {
+    use super::GeneratorConfig;
+    use crate::length_distribution::LengthDistribution;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn defaults_are_kept() {
+        let config = GeneratorConfig::from_args(&args("--sites 3")).unwrap();
+        assert_eq!(config.sites, 3);
+        assert_eq!(config.out_dir, "data");
+        assert_eq!(config.tags, 500);
+    }
+
+    #[test]
+    fn parses_distributions() {
+        let config = GeneratorConfig::from_args(&args("--body-words geo:20 --seed 9")).unwrap();
+        assert_eq!(config.body_words, LengthDistribution::Geometric(20.0));
+        assert_eq!(config.seed, 9);
+    }
+
+    #[test]
+    fn rejects_bad_options() {
+        assert!(GeneratorConfig::from_args(&args("--sites")).is_err());
+        assert!(GeneratorConfig::from_args(&args("--colour blue")).is_err());
+        assert!(GeneratorConfig::from_args(&args("--tags 0")).is_err());
+    }
+}
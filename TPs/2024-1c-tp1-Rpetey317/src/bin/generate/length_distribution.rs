@@ -0,0 +1,95 @@
+use crate::rng::Rng;
+
+/// Distribution used to pick word counts and word lengths of generated questions.
+///
+/// Parsed from the command line as `N` (fixed), `MIN-MAX` (uniform) or `geo:MEAN` (geometric).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LengthDistribution {
+    /// Always the same value
+    Fixed(u32),
+    /// Uniformly distributed between both values (inclusive)
+    Uniform(u32, u32),
+    /// Geometrically distributed with the given mean. Gives a long tail, like real question bodies
+    Geometric(f64),
+}
+
+impl LengthDistribution {
+    /// Parses a distribution from its command line representation
+    pub fn parse(s: &str) -> Result<LengthDistribution, String> {
+        if let Some(mean) = s.strip_prefix("geo:") {
+            let mean = mean
+                .parse::<f64>()
+                .map_err(|_| format!("Invalid geometric mean: {}", mean))?;
+            if mean < 0.0 {
+                return Err(format!("Geometric mean must not be negative: {}", mean));
+            }
+            return Ok(LengthDistribution::Geometric(mean));
+        }
+        if let Some((min, max)) = s.split_once('-') {
+            let min = min
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid range: {}", s))?;
+            let max = max
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid range: {}", s))?;
+            if min > max {
+                return Err(format!("Invalid range: {}", s));
+            }
+            return Ok(LengthDistribution::Uniform(min, max));
+        }
+        s.parse::<u32>()
+            .map(LengthDistribution::Fixed)
+            .map_err(|_| format!("Invalid distribution: {}", s))
+    }
+
+    /// Samples a value from the distribution
+    pub fn sample(&self, rng: &mut Rng) -> u32 {
+        match *self {
+            LengthDistribution::Fixed(n) => n,
+            LengthDistribution::Uniform(min, max) => rng.range(min, max),
+            LengthDistribution::Geometric(mean) => {
+                if mean <= 0.0 {
+                    return 0;
+                }
+                let p = 1.0 / (mean + 1.0);
+                // 1 - u is in (0, 1], so the logarithm is always finite
+                let u = 1.0 - rng.next_f64();
+                (u.ln() / (1.0 - p).ln()).floor() as u32
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LengthDistribution;
+    use crate::rng::Rng;
+
+    #[test]
+    fn parses_all_variants() {
+        assert_eq!(
+            LengthDistribution::parse("5"),
+            Ok(LengthDistribution::Fixed(5))
+        );
+        assert_eq!(
+            LengthDistribution::parse("2-9"),
+            Ok(LengthDistribution::Uniform(2, 9))
+        );
+        assert_eq!(
+            LengthDistribution::parse("geo:80"),
+            Ok(LengthDistribution::Geometric(80.0))
+        );
+        assert!(LengthDistribution::parse("9-2").is_err());
+        assert!(LengthDistribution::parse("words").is_err());
+    }
+
+    #[test]
+    fn geometric_mean_is_close() {
+        let dist = LengthDistribution::Geometric(50.0);
+        let mut rng = Rng::new(11);
+        let n = 20000;
+        let total: u64 = (0..n).map(|_| dist.sample(&mut rng) as u64).sum();
+        let mean = total as f64 / n as f64;
+        assert!((mean - 50.0).abs() < 2.5, "mean was {}", mean);
+    }
+}
@@ -0,0 +1,84 @@
+use expected_summary::{ExpectedSite, ExpectedSummary};
+use generator_config::GeneratorConfig;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::process;
+use zipf::Zipf;
+
+mod expected_summary;
+mod generator_config;
+mod length_distribution;
+mod rng;
+mod site_generator;
+mod zipf;
+
+/// Student ID, printed in the expected summary just like the summarizer does
+const PADRON: u32 = 109442;
+
+/// Generates a synthetic StackExchange-like dataset.
+///
+/// Writes one `.jsonl` file per site to the output directory (`data` by default), plus an
+/// `expected.json` file with the exact summary `tp1` must print for that data.
+/// Sites are generated in parallel; the result only depends on the options and the seed.
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let config = match GeneratorConfig::from_args(&args) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    if let Err(e) = fs::create_dir_all(&config.out_dir) {
+        eprintln!("Failed to create {}: {}", config.out_dir, e);
+        process::exit(1);
+    }
+
+    let zipf = Zipf::new(config.tags, config.zipf_exponent);
+    let width = config.sites.saturating_sub(1).to_string().len();
+    let results: Vec<(String, std::io::Result<ExpectedSite>)> = (0..config.sites)
+        .into_par_iter()
+        .map(|i| {
+            let site = format!("site{:0width$}.jsonl", i, width = width);
+            eprintln!("Generating {}", site);
+            let path = format!("{}/{}", config.out_dir, site);
+            let result = File::create(&path).and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                let expected = site_generator::generate_site(&config, &zipf, i, &mut writer)?;
+                writer.flush()?;
+                Ok(expected)
+            });
+            (site, result)
+        })
+        .collect();
+
+    let mut sites = BTreeMap::new();
+    for (name, result) in results {
+        match result {
+            Ok(site) => {
+                sites.insert(name, site);
+            }
+            Err(e) => {
+                eprintln!("Failed to write {}: {}", name, e);
+                process::exit(1);
+            }
+        }
+    }
+
+    let expected = ExpectedSummary::new(PADRON, sites);
+    let path = format!("{}/expected.json", config.out_dir);
+    let result = File::create(&path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, &expected)?;
+        writer.write_all(b"\n")?;
+        writer.flush()
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to write {}: {}", path, e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,70 @@
+/// Small, seedable pseudo random number generator (SplitMix64).
+///
+/// Used instead of an external crate so generated datasets are reproducible
+/// byte-for-byte given the same seed, on any platform.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a new generator from the given seed
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    /// Returns the next pseudo random 64 bit number
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a uniformly distributed float in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a uniformly distributed integer in [min, max] (both inclusive)
+    pub fn range(&mut self, min: u32, max: u32) -> u32 {
+        if max <= min {
+            return min;
+        }
+        let span = (max - min) as u64 + 1;
+        min + (self.next_u64() % span) as u32
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Rng;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn range_stays_in_bounds() {
+        let mut rng = Rng::new(7);
+        for _ in 0..1000 {
+            let n = rng.range(3, 5);
+            assert!((3..=5).contains(&n));
+        }
+        assert_eq!(rng.range(4, 4), 4);
+    }
+
+    #[test]
+    fn floats_are_in_unit_interval() {
+        let mut rng = Rng::new(1);
+        for _ in 0..1000 {
+            let f = rng.next_f64();
+            assert!((0.0..1.0).contains(&f));
+        }
+    }
+}
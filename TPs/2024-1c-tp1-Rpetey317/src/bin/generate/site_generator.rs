@@ -0,0 +1,138 @@
+use crate::expected_summary::ExpectedSite;
+use crate::generator_config::GeneratorConfig;
+use crate::rng::Rng;
+use crate::zipf::Zipf;
+use serde::Serialize;
+use std::io::{self, Write};
+
+/// A generated question, serialized with the same schema as the real dataset
+#[derive(Serialize)]
+struct Line<'a> {
+    texts: [&'a str; 2],
+    tags: &'a [String],
+}
+
+/// Writes the questions of the `index`-th site to `writer` and returns its expected summary.
+///
+/// Each site has its own random number generator derived from the seed and its index, so
+/// sites can be generated in any order (or in parallel) and still be reproducible.
+/// Each site also ranks the tags differently, so the most common tags vary between sites.
+pub fn generate_site<W: Write>(
+    config: &GeneratorConfig,
+    zipf: &Zipf,
+    index: usize,
+    writer: &mut W,
+) -> io::Result<ExpectedSite> {
+    let mut rng = Rng::new(config.seed ^ (index as u64).wrapping_mul(0xA24B_AED4_963E_E407));
+    let ranking = shuffled_tags(config.tags, &mut rng);
+    let mut site = ExpectedSite::default();
+    let mut title = String::new();
+    let mut body = String::new();
+    let mut tags: Vec<String> = Vec::new();
+
+    for _ in 0..config.questions {
+        let title_words = config.title_words.sample(&mut rng);
+        let body_words = config.body_words.sample(&mut rng);
+        fill_text(&mut title, title_words, config, &mut rng);
+        fill_text(&mut body, body_words, config, &mut rng);
+
+        let n_tags = (config.tags_per_question.sample(&mut rng) as usize).min(config.tags);
+        tags.clear();
+        while tags.len() < n_tags {
+            let tag = format!("tag{}", ranking[zipf.sample(&mut rng)]);
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+
+        let line = Line {
+            texts: [&title, &body],
+            tags: &tags,
+        };
+        serde_json::to_writer(&mut *writer, &line)?;
+        writer.write_all(b"\n")?;
+
+        site.add_question(title_words + body_words, &tags);
+    }
+
+    Ok(site)
+}
+
+/// Returns a random permutation of the tag ids. Position `k` holds the tag with Zipf rank `k`
+fn shuffled_tags(n: usize, rng: &mut Rng) -> Vec<usize> {
+    let mut ids: Vec<usize> = (0..n).collect();
+    for i in (1..n).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        ids.swap(i, j);
+    }
+    ids
+}
+
+/// Replaces the contents of `text` with exactly `words` random words.
+///
+/// Words are mostly separated by a single space, but sometimes by several spaces, tabs or
+/// newlines, so the summarizer's whitespace handling is exercised too.
+fn fill_text(text: &mut String, words: u32, config: &GeneratorConfig, rng: &mut Rng) {
+    text.clear();
+    for i in 0..words {
+        if i > 0 {
+            let separator = match rng.range(0, 19) {
+                0 => "  ",
+                1 => "\n",
+                2 => " \t ",
+                _ => " ",
+            };
+            text.push_str(separator);
+        }
+        let chars = config.word_chars.sample(rng).max(1);
+        for _ in 0..chars {
+            text.push((b'a' + rng.range(0, 25) as u8) as char);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::generate_site;
+    use crate::generator_config::GeneratorConfig;
+    use crate::zipf::Zipf;
+
+    fn small_config() -> GeneratorConfig {
+        GeneratorConfig {
+            questions: 50,
+            tags: 20,
+            ..GeneratorConfig::default()
+        }
+    }
+
+    #[test]
+    fn expected_counts_match_written_lines() {
+        let config = small_config();
+        let zipf = Zipf::new(config.tags, config.zipf_exponent);
+        let mut out = Vec::new();
+        let site = generate_site(&config, &zipf, 0, &mut out).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        let mut words = 0;
+        for line in text.lines() {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            for t in value["texts"].as_array().unwrap() {
+                words += t.as_str().unwrap().split_whitespace().count() as u64;
+            }
+        }
+        assert_eq!(text.lines().count(), 50);
+        assert_eq!(site.questions, 50);
+        assert_eq!(site.words, words);
+    }
+
+    #[test]
+    fn generation_is_reproducible() {
+        let config = small_config();
+        let zipf = Zipf::new(config.tags, config.zipf_exponent);
+        let mut a = Vec::new();
+        let mut b = Vec::new();
+        generate_site(&config, &zipf, 1, &mut a).unwrap();
+        generate_site(&config, &zipf, 1, &mut b).unwrap();
+        assert_eq!(a, b);
+    }
+}
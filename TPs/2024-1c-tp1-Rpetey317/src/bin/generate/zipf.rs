@@ -0,0 +1,58 @@
+use crate::rng::Rng;
+
+/// Zipf distribution over ranks `0..n`, where rank `k` has weight `1 / (k + 1)^exponent`
+pub struct Zipf {
+    /// Cumulative (normalized) weights of each rank
+    cdf: Vec<f64>,
+}
+
+impl Zipf {
+    /// Creates a Zipf distribution over `n` ranks with the given exponent
+    pub fn new(n: usize, exponent: f64) -> Zipf {
+        let mut cdf = Vec::with_capacity(n);
+        let mut acc = 0.0;
+        for k in 0..n {
+            acc += 1.0 / ((k + 1) as f64).powf(exponent);
+            cdf.push(acc);
+        }
+        for c in cdf.iter_mut() {
+            *c /= acc;
+        }
+        Zipf { cdf }
+    }
+
+    /// Samples a rank
+    pub fn sample(&self, rng: &mut Rng) -> usize {
+        let u = rng.next_f64();
+        self.cdf
+            .partition_point(|&c| c <= u)
+            .min(self.cdf.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Zipf;
+    use crate::rng::Rng;
+
+    #[test]
+    fn first_rank_is_most_common() {
+        let zipf = Zipf::new(50, 1.2);
+        let mut rng = Rng::new(3);
+        let mut counts = [0u32; 50];
+        for _ in 0..10000 {
+            counts[zipf.sample(&mut rng)] += 1;
+        }
+        assert!(counts[0] > counts[1]);
+        assert!(counts[1] > counts[10]);
+    }
+
+    #[test]
+    fn single_rank_always_sampled() {
+        let zipf = Zipf::new(1, 1.0);
+        let mut rng = Rng::new(3);
+        for _ in 0..100 {
+            assert_eq!(zipf.sample(&mut rng), 0);
+        }
+    }
+}
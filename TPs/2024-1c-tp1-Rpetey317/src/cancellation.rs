@@ -0,0 +1,83 @@
+use crate::stop_reason::StopReason;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+/// Cooperative cancellation of a run, checked by the workers before reading each line.
+///
+/// A run is cancelled once it's interrupted or once its deadline passes. Cancellation is
+/// sticky: the first reason found is kept. Each run gets its own token; clones share it, so one
+/// can be handed to whatever interrupts the run (such as a Ctrl-C handler).
+#[derive(Debug, Default, Clone)]
+pub struct Cancellation {
+    deadline: Option<Instant>,
+    reason: Arc<OnceLock<StopReason>>,
+}
+
+impl Cancellation {
+    /// Creates the cancellation of a run that must stop at the given deadline, if any
+    pub fn new(deadline: Option<Instant>) -> Cancellation {
+        Cancellation {
+            deadline,
+            reason: Arc::new(OnceLock::new()),
+        }
+    }
+
+    /// Cancels the run as interrupted. Returns false if it was already cancelled
+    pub fn interrupt(&self) -> bool {
+        self.reason.set(StopReason::Interrupted).is_ok()
+    }
+
+    /// Whether the run must stop
+    pub fn is_cancelled(&self) -> bool {
+        if self.reason.get().is_some() {
+            return true;
+        }
+        if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            let _ = self.reason.set(StopReason::TimeBudget);
+            return true;
+        }
+        false
+    }
+
+    /// Why the run was cancelled, if it was
+    pub fn reason(&self) -> Option<StopReason> {
+        self.reason.get().copied()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Cancellation;
+    use crate::stop_reason::StopReason;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn runs_without_deadline_are_not_cancelled() {
+        let cancellation = Cancellation::default();
+        assert!(!cancellation.is_cancelled());
+        assert_eq!(cancellation.reason(), None);
+    }
+
+    #[test]
+    fn expired_deadlines_cancel_for_good() {
+        let cancellation = Cancellation::new(Some(Instant::now()));
+        assert!(cancellation.is_cancelled());
+        assert_eq!(cancellation.reason(), Some(StopReason::TimeBudget));
+
+        let later = Cancellation::new(Some(Instant::now() + Duration::from_secs(3600)));
+        assert!(!later.is_cancelled());
+    }
+
+    #[test]
+    fn interrupts_only_cancel_their_own_run() {
+        let cancellation = Cancellation::default();
+        let handle = cancellation.clone();
+        assert!(handle.interrupt());
+        assert!(!handle.interrupt());
+        assert!(cancellation.is_cancelled());
+        assert_eq!(cancellation.reason(), Some(StopReason::Interrupted));
+
+        let next_run = Cancellation::default();
+        assert!(!next_run.is_cancelled());
+    }
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+/// z-score of a two-sided 95% confidence interval
+pub const Z_95: f64 = 1.96;
+
+/// Chattiness of a site or tag estimated from a sample, with its 95% confidence interval.
+///
+/// Uses the normal approximation of the mean word count of the sampled questions, with the
+/// finite population correction for the sampled fraction.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChattinessEstimate {
+    /// N° of questions in the sample
+    pub sampled_questions: u64,
+    /// Chattiness of the sampled questions
+    pub chattiness: f32,
+    /// Lower bound of the interval. Not present if there are too few questions to tell
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub low: Option<f32>,
+    /// Upper bound of the interval. Not present if there are too few questions to tell
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub high: Option<f32>,
+}
+
+impl ChattinessEstimate {
+    /// Estimates the chattiness from the questions, words and sum of squared word counts of a
+    /// sample of the given fraction
+    pub fn new(
+        questions: u64,
+        words: u64,
+        words_squared: u128,
+        fraction: f64,
+    ) -> ChattinessEstimate {
+        let chattiness = if questions == 0 {
+            0.0
+        } else {
+            words as f32 / questions as f32
+        };
+        let mut estimate = ChattinessEstimate {
+            sampled_questions: questions,
+            chattiness,
+            low: None,
+            high: None,
+        };
+        if questions < 2 {
+            return estimate;
+        }
+
+        let n = questions as f64;
+        let mean = words as f64 / n;
+        let variance = ((words_squared as f64 - n * mean * mean) / (n - 1.0)).max(0.0);
+        let margin = Z_95 * (variance / n * (1.0 - fraction)).sqrt();
+        estimate.low = Some((mean - margin).max(0.0) as f32);
+        estimate.high = Some((mean + margin) as f32);
+        estimate
+    }
+
+    /// Whether the intervals of both estimates overlap, so their order may be due to chance.
+    /// Estimates without an interval overlap with everything
+    pub fn overlaps(&self, other: &ChattinessEstimate) -> bool {
+        match (self.low, self.high, other.low, other.high) {
+            (Some(low), Some(high), Some(other_low), Some(other_high)) => {
+                low <= other_high && other_low <= high
+            }
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ChattinessEstimate;
+
+    #[test]
+    fn interval_surrounds_the_mean() {
+        // word counts 8, 10, 12
+        let estimate = ChattinessEstimate::new(3, 30, 64 + 100 + 144, 0.1);
+        assert_eq!(estimate.chattiness, 10.0);
+        let (low, high) = (estimate.low.unwrap(), estimate.high.unwrap());
+        assert!(low < 10.0 && high > 10.0);
+        assert!((high - 10.0 - (10.0 - low)).abs() < 1e-4);
+        // sd 2, n 3: margin = 1.96 * 2 / sqrt(3) * sqrt(0.9)
+        assert!((high - 10.0 - 2.1468).abs() < 1e-3);
+    }
+
+    #[test]
+    fn full_samples_are_exact() {
+        let estimate = ChattinessEstimate::new(3, 30, 308, 1.0);
+        assert_eq!(estimate.low, Some(10.0));
+        assert_eq!(estimate.high, Some(10.0));
+    }
+
+    #[test]
+    fn overlap_of_intervals() {
+        let a = ChattinessEstimate::new(3, 30, 308, 0.1);
+        // word counts 9, 11, 13
+        let b = ChattinessEstimate::new(3, 33, 81 + 121 + 169, 0.1);
+        let far = ChattinessEstimate::new(2, 200, 20_000, 0.1);
+        assert!(a.overlaps(&b));
+        assert!(!a.overlaps(&far));
+        assert!(ChattinessEstimate::new(1, 5, 25, 0.1).overlaps(&far));
+    }
+}
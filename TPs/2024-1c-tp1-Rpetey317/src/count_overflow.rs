@@ -0,0 +1,54 @@
+use std::error::Error;
+use std::fmt;
+
+/// A count of a site or tag that went past the largest value it can hold
+#[derive(Debug, Clone, PartialEq)]
+pub struct CountOverflow {
+    /// What was being counted (`questions`, `words`, ...)
+    pub count: String,
+    /// Site or tag whose count overflowed
+    pub of: String,
+}
+
+impl CountOverflow {
+    /// Creates the overflow of the given count of a site or tag
+    pub fn new(count: &str, of: &str) -> CountOverflow {
+        CountOverflow {
+            count: count.to_string(),
+            of: of.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for CountOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "count of {} of {} overflowed", self.count, self.of)
+    }
+}
+
+impl Error for CountOverflow {}
+
+/// Adds two counts of a site or tag, failing if the sum overflows
+pub(crate) fn add(a: u64, b: u64, count: &str, of: &str) -> Result<u64, CountOverflow> {
+    a.checked_add(b)
+        .ok_or_else(|| CountOverflow::new(count, of))
+}
+
+/// Adds two sums of squared word counts of a site or tag, failing if the sum overflows
+pub(crate) fn add_squares(a: u128, b: u128, of: &str) -> Result<u128, CountOverflow> {
+    a.checked_add(b)
+        .ok_or_else(|| CountOverflow::new("squared words", of))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{add, CountOverflow};
+
+    #[test]
+    fn overflows_are_errors() {
+        assert_eq!(add(1, 2, "words", "rust"), Ok(3));
+        let overflow = add(u64::MAX, 1, "words", "rust").unwrap_err();
+        assert_eq!(overflow, CountOverflow::new("words", "rust"));
+        assert_eq!(overflow.to_string(), "count of words of rust overflowed");
+    }
+}
@@ -0,0 +1,49 @@
+use std::process;
+use std::sync::{Arc, Mutex};
+use tp1::Cancellation;
+
+/// Exit status of a process killed by Ctrl-C
+const INTERRUPTED_STATUS: i32 = 130;
+
+/// Ctrl-C handling of the binary. While a run is being summarized, the first Ctrl-C cancels it
+/// instead of killing the process. Otherwise, or on a second Ctrl-C, the process exits as usual
+pub struct CtrlC {
+    run: Arc<Mutex<Option<Cancellation>>>,
+}
+
+impl CtrlC {
+    /// Installs the Ctrl-C handler, which can only be done once per process. If it can't be
+    /// installed, Ctrl-C keeps killing the process
+    pub fn install() -> CtrlC {
+        let run: Arc<Mutex<Option<Cancellation>>> = Arc::default();
+        let current = Arc::clone(&run);
+        let result = ctrlc::set_handler(move || {
+            let cancelled = match current.lock() {
+                Ok(run) => run.as_ref().is_some_and(Cancellation::interrupt),
+                Err(_) => false,
+            };
+            if !cancelled {
+                process::exit(INTERRUPTED_STATUS);
+            }
+        });
+        if let Err(e) = result {
+            eprintln!("Failed to catch Ctrl-C: {}", e);
+        }
+        CtrlC { run }
+    }
+
+    /// Runs `f`, with Ctrl-C cancelling the given run meanwhile
+    pub fn cancelling<T>(&self, cancellation: &Cancellation, f: impl FnOnce() -> T) -> T {
+        self.set(Some(cancellation.clone()));
+        let result = f();
+        self.set(None);
+        result
+    }
+
+    /// Sets the run Ctrl-C cancels, if any
+    fn set(&self, run: Option<Cancellation>) {
+        if let Ok(mut current) = self.run.lock() {
+            *current = run;
+        }
+    }
+}
@@ -0,0 +1,38 @@
+/// Which repeated questions are dropped before counting
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DedupMode {
+    /// Every question is counted
+    #[default]
+    Off,
+    /// A question repeated within the same site is counted once
+    Site,
+    /// A question repeated anywhere is counted once, in the first site it appears in
+    Global,
+}
+
+impl DedupMode {
+    /// Parses a mode from its command line name
+    pub fn parse(name: &str) -> Result<DedupMode, String> {
+        match name {
+            "off" => Ok(DedupMode::Off),
+            "site" => Ok(DedupMode::Site),
+            "global" => Ok(DedupMode::Global),
+            _ => Err(format!(
+                "Unknown dedup mode: {}. Use one of [off, site, global]",
+                name
+            )),
+        }
+    }
+
+    /// Whether a question may be repeated in a different file than its first occurrence.
+    ///
+    /// If so, files must be scanned before parsing to decide which file keeps each question.
+    /// Within a site that's only possible if some site is spread over several files.
+    pub fn spans_files(&self, sites_span_files: bool) -> bool {
+        match self {
+            DedupMode::Off => false,
+            DedupMode::Site => sites_span_files,
+            DedupMode::Global => true,
+        }
+    }
+}
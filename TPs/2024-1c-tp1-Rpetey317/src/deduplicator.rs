@@ -0,0 +1,182 @@
+use crate::dedup_mode::DedupMode;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// Fingerprint of a question's normalized contents
+pub type Fingerprint = u128;
+
+/// Decides which questions of a single file are counted, according to the dedup mode.
+///
+/// A question is counted only the first time it appears in the file. If questions may be
+/// repeated across files (see `DedupMode::spans_files`), it's also only counted in the file
+/// that owns it: the first file, in file order, where it appears. As ownership depends only on
+/// the contents and order of the files, the result is the same regardless of how files are
+/// split among threads.
+pub struct Deduplicator<'a> {
+    mode: DedupMode,
+    seen: HashSet<Fingerprint>,
+    owners: Option<&'a HashMap<Fingerprint, usize>>,
+    file_index: usize,
+}
+
+impl<'a> Deduplicator<'a> {
+    /// Creates the deduplicator of the `file_index`-th file. `owners` maps each question
+    /// repeated across files to the index of the file that owns it
+    pub fn new(
+        mode: DedupMode,
+        owners: Option<&'a HashMap<Fingerprint, usize>>,
+        file_index: usize,
+    ) -> Deduplicator<'a> {
+        Deduplicator {
+            mode,
+            seen: HashSet::new(),
+            owners,
+            file_index,
+        }
+    }
+
+    /// Whether questions need to be fingerprinted at all
+    pub fn is_enabled(&self) -> bool {
+        self.mode != DedupMode::Off
+    }
+
+    /// Returns the key a question is deduplicated by: its fingerprint, scoped to its site
+    /// unless deduplicating globally
+    pub fn key(&self, site: &str, fingerprint: Fingerprint) -> Fingerprint {
+        match self.mode {
+            DedupMode::Global => fingerprint,
+            _ => {
+                let mut hasher = DefaultHasher::new();
+                site.hash(&mut hasher);
+                fingerprint.hash(&mut hasher);
+                let site_hash = hasher.finish() as u128;
+                fingerprint ^ (site_hash << 64 | site_hash)
+            }
+        }
+    }
+
+    /// Whether a question with the given key must be counted. Always true if dedup is off
+    pub fn keep(&mut self, key: Fingerprint) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+        if !self.seen.insert(key) {
+            return false;
+        }
+        match self.owners.and_then(|owners| owners.get(&key)) {
+            Some(owner) => *owner == self.file_index,
+            None => true,
+        }
+    }
+}
+
+/// Fingerprints a question by its title and body, ignoring case and differences in whitespace
+pub fn fingerprint(title: &str, body: &str) -> Fingerprint {
+    let mut normalized = String::with_capacity(title.len() + body.len() + 1);
+    for text in [title, body] {
+        let mut first = true;
+        for word in text.split_whitespace() {
+            if !first {
+                normalized.push(' ');
+            }
+            first = false;
+            normalized.extend(word.chars().flat_map(char::to_lowercase));
+        }
+        normalized.push('\0');
+    }
+
+    let mut low = DefaultHasher::new();
+    0u8.hash(&mut low);
+    normalized.hash(&mut low);
+    let mut high = DefaultHasher::new();
+    1u8.hash(&mut high);
+    normalized.hash(&mut high);
+    (high.finish() as u128) << 64 | low.finish() as u128
+}
+
+/// Decides which file owns each question that appears in more than one file: the first one,
+/// in the order the key sets are given.
+///
+/// Questions that appear in a single file are left out, as there's nothing to decide.
+pub fn owners(keys_per_file: &[HashSet<Fingerprint>]) -> HashMap<Fingerprint, usize> {
+    let mut first: HashMap<Fingerprint, (usize, bool)> = HashMap::new();
+    for (index, keys) in keys_per_file.iter().enumerate() {
+        for key in keys {
+            first
+                .entry(*key)
+                .and_modify(|(_, repeated)| *repeated = true)
+                .or_insert((index, false));
+        }
+    }
+    first
+        .into_iter()
+        .filter(|(_, (_, repeated))| *repeated)
+        .map(|(key, (index, _))| (key, index))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{fingerprint, owners, Deduplicator};
+    use crate::dedup_mode::DedupMode;
+    use std::collections::HashSet;
+
+    #[test]
+    fn fingerprint_ignores_case_and_spacing() {
+        assert_eq!(
+            fingerprint("How to  Sort", "a list\nin Rust"),
+            fingerprint("how to sort", " a LIST in rust ")
+        );
+        assert_ne!(
+            fingerprint("How to sort", "a list"),
+            fingerprint("How to", "sort a list")
+        );
+        assert_ne!(fingerprint("a", "b"), fingerprint("a", "c"));
+    }
+
+    #[test]
+    fn repeats_within_a_file_are_dropped() {
+        let mut dedup = Deduplicator::new(DedupMode::Site, None, 0);
+        let key = dedup.key("s", fingerprint("a", "b"));
+        assert!(dedup.keep(key));
+        assert!(!dedup.keep(key));
+    }
+
+    #[test]
+    fn site_keys_differ_between_sites() {
+        let dedup = Deduplicator::new(DedupMode::Site, None, 0);
+        let fp = fingerprint("a", "b");
+        assert_ne!(dedup.key("s1", fp), dedup.key("s2", fp));
+        let global = Deduplicator::new(DedupMode::Global, None, 0);
+        assert_eq!(global.key("s1", fp), global.key("s2", fp));
+    }
+
+    #[test]
+    fn off_keeps_everything() {
+        let mut dedup = Deduplicator::new(DedupMode::Off, None, 0);
+        assert!(dedup.keep(1));
+        assert!(dedup.keep(1));
+    }
+
+    #[test]
+    fn first_file_owns_repeated_questions() {
+        let files = vec![
+            HashSet::from([1, 2]),
+            HashSet::from([2, 3]),
+            HashSet::from([1, 3, 4]),
+        ];
+        let owners = owners(&files);
+        assert_eq!(owners.len(), 3);
+        assert_eq!(owners[&1], 0);
+        assert_eq!(owners[&2], 0);
+        assert_eq!(owners[&3], 1);
+
+        let mut second = Deduplicator::new(DedupMode::Global, Some(&owners), 1);
+        assert!(!second.keep(2));
+        assert!(second.keep(3));
+        let mut third = Deduplicator::new(DedupMode::Global, Some(&owners), 2);
+        assert!(third.keep(4));
+        assert!(!third.keep(1));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::discovery_options::DiscoveryOptions;
+use crate::glob_pattern::GlobPattern;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::Path;
+use tp1::InputFile;
+
+/// Returns the files of the data directory (and of its subdirectories, if recursive) that match
+/// the include and exclude patterns, sorted by path. Without include patterns, every `.jsonl`
+/// file is included.
+///
+/// May fail if the directory cannot be read. Unreadable subdirectories are reported on stderr
+/// and skipped
+pub fn find_files(options: &DiscoveryOptions) -> io::Result<Vec<InputFile>> {
+    let mut relative_paths = Vec::new();
+    walk(
+        Path::new(&options.dir),
+        "",
+        options.recursive,
+        &mut relative_paths,
+    )?;
+
+    let mut files: Vec<InputFile> = relative_paths
+        .into_iter()
+        .filter(|relative| is_selected(relative, options, relative.ends_with(".jsonl")))
+        .map(|relative| {
+            let path = format!("{}/{}", options.dir, relative);
+            input_file(path, relative, options)
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Returns the files listed one per line in `reader` that match the include and exclude
+/// patterns, sorted by path. Blank lines are skipped.
+///
+/// May fail if the list cannot be read
+pub fn read_file_list<R: BufRead>(
+    reader: R,
+    options: &DiscoveryOptions,
+) -> io::Result<Vec<InputFile>> {
+    let mut files = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let path = line.trim();
+        if path.is_empty() || !is_selected(path, options, true) {
+            continue;
+        }
+        files.push(input_file(path.to_string(), path.to_string(), options));
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files.dedup_by(|a, b| a.path == b.path);
+    Ok(files)
+}
+
+/// Adds to `found` the paths (relative to the walked directory) of the files in `dir`.
+/// Subdirectories that can't be read are reported on stderr and skipped
+fn walk(dir: &Path, prefix: &str, recursive: bool, found: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let file_name = entry.file_name();
+        let file_name_str = match file_name.to_str() {
+            Some(name) => name,
+            None => continue,
+        };
+        let relative = format!("{}{}", prefix, file_name_str);
+        let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+        if is_dir {
+            if recursive {
+                // unreadable subdirectories are skipped rather than failing the whole run
+                if let Err(e) = walk(&entry.path(), &format!("{}/", relative), true, found) {
+                    eprintln!("Skipping {}: {}", entry.path().display(), e);
+                }
+            }
+        } else if entry.path().is_file() {
+            found.push(relative);
+        }
+    }
+    Ok(())
+}
+
+/// Whether a path matches some include pattern and no exclude pattern. Without include
+/// patterns, whether it's included is up to `by_default`
+fn is_selected(path: &str, options: &DiscoveryOptions, by_default: bool) -> bool {
+    let included = if options.include.is_empty() {
+        by_default
+    } else {
+        matches_any(&options.include, path)
+    };
+    included && !matches_any(&options.exclude, path)
+}
+
+/// Whether the path matches any of the patterns
+fn matches_any(patterns: &[GlobPattern], path: &str) -> bool {
+    patterns.iter().any(|glob| glob.matches(path))
+}
+
+/// Names the file and its site
+fn input_file(path: String, name: String, options: &DiscoveryOptions) -> InputFile {
+    let site = options.site_name.site_name(&path, &name);
+    InputFile { path, name, site }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_files, read_file_list};
+    use crate::discovery_options::DiscoveryOptions;
+    use crate::glob_pattern::GlobPattern;
+    use crate::site_name_pattern::SiteNamePattern;
+
+    fn options() -> DiscoveryOptions {
+        DiscoveryOptions {
+            dir: "test_data/discovery".to_string(),
+            ..DiscoveryOptions::default()
+        }
+    }
+
+    fn names(options: &DiscoveryOptions) -> Vec<String> {
+        find_files(options)
+            .unwrap()
+            .into_iter()
+            .map(|file| file.name)
+            .collect()
+    }
+
+    #[test]
+    fn only_top_level_jsonl_files_by_default() {
+        let files = find_files(&options()).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "test_data/discovery/a.jsonl");
+        assert_eq!(files[0].name, "a.jsonl");
+        assert_eq!(files[0].site, "a.jsonl");
+    }
+
+    #[test]
+    fn recursive_discovery_with_globs() {
+        let mut options = options();
+        options.recursive = true;
+        assert_eq!(
+            names(&options),
+            vec!["a.jsonl", "nested/b.jsonl", "nested/deeper/c.jsonl"]
+        );
+
+        options.exclude = vec![GlobPattern::parse("**/deeper/**").unwrap()];
+        assert_eq!(names(&options), vec!["a.jsonl", "nested/b.jsonl"]);
+
+        options.include = vec![GlobPattern::parse("*.txt").unwrap()];
+        options.exclude.clear();
+        assert_eq!(names(&options), vec!["nested/notes.txt"]);
+    }
+
+    #[test]
+    fn site_names_follow_the_pattern() {
+        let mut options = options();
+        options.recursive = true;
+        options.site_name = SiteNamePattern::parse("{parent}").unwrap();
+        let sites: Vec<String> = find_files(&options)
+            .unwrap()
+            .into_iter()
+            .map(|file| file.site)
+            .collect();
+        assert_eq!(sites, vec!["discovery", "nested", "deeper"]);
+    }
+
+    #[test]
+    fn file_lists_are_filtered_and_sorted() {
+        let mut options = options();
+        options.exclude = vec![GlobPattern::parse("*.txt").unwrap()];
+        options.site_name = SiteNamePattern::parse("{stem}").unwrap();
+        let list = "b/y.jsonl\n\n  a/x.jsonl  \nnotes.txt\nb/y.jsonl\n";
+        let files = read_file_list(list.as_bytes(), &options).unwrap();
+        let listed: Vec<(&str, &str)> = files
+            .iter()
+            .map(|file| (file.path.as_str(), file.site.as_str()))
+            .collect();
+        assert_eq!(listed, vec![("a/x.jsonl", "x"), ("b/y.jsonl", "y")]);
+    }
+}
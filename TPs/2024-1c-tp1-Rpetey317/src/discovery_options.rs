@@ -0,0 +1,33 @@
+use crate::glob_pattern::GlobPattern;
+use crate::site_name_pattern::SiteNamePattern;
+
+/// Options that change which files are summarized and which site each one is
+#[derive(Debug, Clone)]
+pub struct DiscoveryOptions {
+    /// Directory the input files are looked for in
+    pub dir: String,
+    /// Whether subdirectories are looked into too
+    pub recursive: bool,
+    /// Files must match one of these to be summarized. If empty, every `.jsonl` file found in
+    /// the directory is (and every file read from stdin)
+    pub include: Vec<GlobPattern>,
+    /// Files matching any of these are not summarized
+    pub exclude: Vec<GlobPattern>,
+    /// Whether the files are read as a newline-separated list from stdin instead
+    pub from_stdin: bool,
+    /// How the site name of each file is derived from its path
+    pub site_name: SiteNamePattern,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        DiscoveryOptions {
+            dir: "data".to_string(),
+            recursive: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            from_stdin: false,
+            site_name: SiteNamePattern::default(),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+/// Summary statistics of a set of values, such as the chattiness of every site.
+///
+/// Percentiles interpolate linearly between the closest ranks, so the median of an even number
+/// of values is the mean of the middle two
+#[derive(Debug, PartialEq)]
+pub struct Distribution {
+    /// N° of values
+    pub count: usize,
+    /// Smallest value
+    pub min: f64,
+    /// Largest value
+    pub max: f64,
+    /// Arithmetic mean
+    pub mean: f64,
+    /// 50th percentile
+    pub median: f64,
+    /// 90th percentile
+    pub p90: f64,
+    /// 99th percentile
+    pub p99: f64,
+}
+
+impl Distribution {
+    /// Returns the distribution of the given values, or None if there are none
+    pub fn of(values: impl IntoIterator<Item = f64>) -> Option<Distribution> {
+        let mut sorted: Vec<f64> = values.into_iter().collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_by(f64::total_cmp);
+        Some(Distribution {
+            count: sorted.len(),
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            mean: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            median: percentile(&sorted, 0.5),
+            p90: percentile(&sorted, 0.9),
+            p99: percentile(&sorted, 0.99),
+        })
+    }
+}
+
+/// Value at the given fraction (between 0 and 1) of non-empty sorted values
+fn percentile(sorted: &[f64], fraction: f64) -> f64 {
+    let rank = fraction * (sorted.len() - 1) as f64;
+    let below = rank.floor() as usize;
+    let above = rank.ceil() as usize;
+    sorted[below] + (sorted[above] - sorted[below]) * (rank - below as f64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::Distribution;
+
+    #[test]
+    fn empty_values_have_no_distribution() {
+        assert_eq!(Distribution::of(Vec::new()), None);
+    }
+
+    #[test]
+    fn percentiles_interpolate_between_ranks() {
+        let distribution = Distribution::of([4.0, 1.0, 3.0, 2.0]).unwrap();
+        assert_eq!(distribution.count, 4);
+        assert_eq!(distribution.min, 1.0);
+        assert_eq!(distribution.max, 4.0);
+        assert_eq!(distribution.mean, 2.5);
+        assert_eq!(distribution.median, 2.5);
+        assert!((distribution.p90 - 3.7).abs() < 1e-9);
+        assert!((distribution.p99 - 3.97).abs() < 1e-9);
+
+        let single = Distribution::of([7.0]).unwrap();
+        assert_eq!(single.median, 7.0);
+        assert_eq!(single.p99, 7.0);
+    }
+}
@@ -0,0 +1,137 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+/// Live progress of a single input file, updated by the worker parsing it.
+///
+/// Every counter is atomic so the progress reporter can read it while the file is parsed.
+/// Relaxed ordering is enough: counters are only read for display and in the final report,
+/// after every worker has joined.
+pub struct FileProgress {
+    /// File name, as listed in the data directory
+    pub name: String,
+    /// File size in bytes (0 if it couldn't be read)
+    pub size: u64,
+    bytes: AtomicU64,
+    lines: AtomicU64,
+    parse_nanos: AtomicU64,
+    worker: AtomicUsize,
+    started: AtomicBool,
+    done: AtomicBool,
+}
+
+impl FileProgress {
+    /// Creates the progress of a file that hasn't started being parsed
+    pub fn new(name: &str, size: u64) -> FileProgress {
+        FileProgress {
+            name: name.to_string(),
+            size,
+            bytes: AtomicU64::new(0),
+            lines: AtomicU64::new(0),
+            parse_nanos: AtomicU64::new(0),
+            worker: AtomicUsize::new(usize::MAX),
+            started: AtomicBool::new(false),
+            done: AtomicBool::new(false),
+        }
+    }
+
+    /// Accounts for a line of the given length read from the file (not counting the newline)
+    pub fn add_line(&self, len: usize) {
+        self.bytes.fetch_add(len as u64 + 1, Ordering::Relaxed);
+        self.lines.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Marks the file as being parsed by the given worker thread
+    pub fn start(&self, worker: Option<usize>) {
+        if let Some(w) = worker {
+            self.worker.store(w, Ordering::Relaxed);
+        }
+        self.started.store(true, Ordering::Relaxed);
+    }
+
+    /// Marks the file as parsed, taking the given time
+    pub fn finish(&self, nanos: u64) {
+        self.parse_nanos.store(nanos, Ordering::Relaxed);
+        self.done.store(true, Ordering::Relaxed);
+    }
+
+    /// Marks the file as no longer being parsed, after the given time, without having been
+    /// completely read
+    pub fn stop(&self, nanos: u64) {
+        self.parse_nanos.store(nanos, Ordering::Relaxed);
+    }
+
+    /// Bytes read so far
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// Lines read so far
+    pub fn lines(&self) -> u64 {
+        self.lines.load(Ordering::Relaxed)
+    }
+
+    /// Time it took to parse the file, in nanoseconds (0 if not finished)
+    pub fn parse_nanos(&self) -> u64 {
+        self.parse_nanos.load(Ordering::Relaxed)
+    }
+
+    /// Index of the worker thread that parsed the file, if known
+    pub fn worker(&self) -> Option<usize> {
+        match self.worker.load(Ordering::Relaxed) {
+            usize::MAX => None,
+            w => Some(w),
+        }
+    }
+
+    /// Whether a worker started parsing the file
+    pub fn is_started(&self) -> bool {
+        self.started.load(Ordering::Relaxed)
+    }
+
+    /// Whether the file was completely parsed
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    /// Fraction of the file read so far, between 0 and 1
+    pub fn completion(&self) -> f64 {
+        if self.is_done() {
+            return 1.0;
+        }
+        if self.size == 0 {
+            return 0.0;
+        }
+        (self.bytes() as f64 / self.size as f64).min(1.0)
+    }
+}
+
+impl Default for FileProgress {
+    fn default() -> Self {
+        FileProgress::new("", 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FileProgress;
+
+    #[test]
+    fn counts_lines_and_bytes() {
+        let progress = FileProgress::new("f", 20);
+        progress.add_line(4);
+        progress.add_line(5);
+        assert_eq!(progress.lines(), 2);
+        assert_eq!(progress.bytes(), 11);
+        assert_eq!(progress.completion(), 11.0 / 20.0);
+    }
+
+    #[test]
+    fn finished_files_are_complete() {
+        let progress = FileProgress::new("f", 0);
+        assert!(!progress.is_started());
+        progress.start(Some(2));
+        progress.finish(10);
+        assert_eq!(progress.worker(), Some(2));
+        assert!(progress.is_done());
+        assert_eq!(progress.completion(), 1.0);
+    }
+}
@@ -1,31 +1,50 @@
+use crate::anomaly::anomaly_report::AnomalyReport;
+use crate::count_overflow::CountOverflow;
+use crate::global_tag_summary::GlobalTagSummary;
+use crate::partial_run::PartialRun;
+use crate::sampling_report::SamplingReport;
 use crate::site_summary::SiteSummary;
-use crate::tag_summary::TagSummary;
-use serde::Serialize;
-use std::collections::HashMap;
+use crate::tag_site_share::TagSiteShare;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
 
-const N_CHATTY: usize = 10;
+pub const N_CHATTY: usize = 10;
 
 /// A struct containing the two global summaries (chattiest sites and tags).
 /// Made only to make use of serde api for formatting and printing
-#[derive(Serialize)]
-struct Totals {
+#[derive(Serialize, Deserialize)]
+pub struct Totals {
     /// 10 chattiest (word count / question count) sites
-    chatty_sites: Vec<String>,
+    pub chatty_sites: Vec<String>,
     /// 10 chattiest (word count / question count) tags (by aggregate word & question count over all sites)
-    chatty_tags: Vec<String>,
+    pub chatty_tags: Vec<String>,
+    /// 10 tags used by the most sites. Only present if the per-site breakdown of tags was computed
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub widespread_tags: Option<Vec<String>>,
 }
 
 /// A struct containing the full summary of the data as asked in the assignment specifications.
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct FullSummary {
     /// Student ID
-    padron: u32,
+    pub padron: u32,
     /// Summary of each site
-    sites: HashMap<String, SiteSummary>,
+    pub sites: HashMap<String, SiteSummary>,
     /// Summary of each tag aggregated through all sites
-    tags: HashMap<String, TagSummary>,
+    pub tags: HashMap<String, GlobalTagSummary>,
     /// Global summaries (chattiest sites and tags)
-    totals: Totals,
+    pub totals: Totals,
+    /// Questions whose word count is an extreme outlier, by site. Only present if looked for
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub anomalies: Option<AnomalyReport>,
+    /// Present only if the run stopped before reading every file
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub partial: Option<PartialRun>,
+    /// Present only if the counts were estimated from a sample of the data
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sampling: Option<SamplingReport>,
 }
 
 impl FullSummary {
@@ -40,25 +59,21 @@ impl FullSummary {
         sites: HashMap<String, SiteSummary>,
         total: &SiteSummary,
     ) -> FullSummary {
-        let tags = total.tags.clone();
+        let tags = total
+            .tags
+            .iter()
+            .map(|(name, tag)| (name.clone(), GlobalTagSummary::new(tag.clone())))
+            .collect();
 
         let _chatty_tags = total.n_chattiest(N_CHATTY);
         let chatty_tags = _chatty_tags.iter().map(|tag| tag.name.clone()).collect();
 
-        let mut sites_vec = sites
-            .iter()
-            .map(|(name, site)| (name.clone(), site.chattiness()))
-            .collect::<Vec<_>>();
-        sites_vec.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-        let chatty_sites = sites_vec
-            .iter()
-            .take(N_CHATTY)
-            .map(|(name, _)| name.clone())
-            .collect();
+        let chatty_sites = chattiest(sites.iter().map(|(name, site)| (name, site.chattiness())));
 
         let totals = Totals {
             chatty_sites,
             chatty_tags,
+            widespread_tags: None,
         };
 
         FullSummary {
@@ -66,8 +81,263 @@ impl FullSummary {
             sites,
             tags,
             totals,
+            anomalies: None,
+            partial: None,
+            sampling: None,
+        }
+    }
+
+    /// Creates a summary of the given sites, by name, aggregating them to get the global tags.
+    /// Fails if a count of the aggregate overflows
+    pub fn from_sites(
+        padron: u32,
+        sites: HashMap<String, SiteSummary>,
+    ) -> Result<FullSummary, CountOverflow> {
+        let mut total = SiteSummary::empty();
+        for site in sites.values() {
+            total.merge(site)?;
+        }
+        Ok(FullSummary::new(padron, sites, &total))
+    }
+
+    /// Ranks the chattiest sites and tags again, leaving out the given questions, by site.
+    /// Counts are left as they are. Fails if a count of the left out questions overflows
+    pub fn ranked_without(
+        mut self,
+        excluded: &HashMap<String, SiteSummary>,
+    ) -> Result<FullSummary, CountOverflow> {
+        let mut excluded_total = SiteSummary::empty();
+        for site in excluded.values() {
+            excluded_total.merge(site)?;
+        }
+
+        let sites = self.sites.iter().map(|(name, site)| {
+            let (questions, words) = match excluded.get(name) {
+                Some(out) => (
+                    site.questions.saturating_sub(out.questions),
+                    site.words.saturating_sub(out.words),
+                ),
+                None => (site.questions, site.words),
+            };
+            (name, chattiness(questions, words))
+        });
+        let tags = self.tags.iter().map(|(name, tag)| {
+            let (questions, words) = match excluded_total.tags.get(name) {
+                Some(out) => (
+                    tag.summary.questions.saturating_sub(out.questions),
+                    tag.summary.words.saturating_sub(out.words),
+                ),
+                None => (tag.summary.questions, tag.summary.words),
+            };
+            (name, chattiness(questions, words))
+        });
+        self.totals.chatty_sites = chattiest(sites);
+        self.totals.chatty_tags = chattiest(tags);
+        Ok(self)
+    }
+
+    /// Adds the report of the questions with an extreme word count
+    pub fn with_anomalies(mut self, anomalies: AnomalyReport) -> FullSummary {
+        self.anomalies = Some(anomalies);
+        self
+    }
+
+    /// Flags the summary as covering only part of the data
+    pub fn with_partial(mut self, partial: PartialRun) -> FullSummary {
+        self.partial = Some(partial);
+        self
+    }
+
+    /// Adds how the data was sampled and the chattiness estimates
+    pub fn with_sampling(mut self, sampling: SamplingReport) -> FullSummary {
+        self.sampling = Some(sampling);
+        self
+    }
+
+    /// Multiplies the word and question counts of every site and tag by the given factor,
+    /// rounding them. Rankings are left as they are
+    pub fn scaled(mut self, factor: f64) -> FullSummary {
+        for site in self.sites.values_mut() {
+            site.scale(factor);
         }
+        for tag in self.tags.values_mut() {
+            tag.summary.scale(factor);
+        }
+        self
+    }
+
+    /// Reads a summary previously printed as JSON.
+    ///
+    /// Names are not part of the JSON entries, so they are restored from the map keys.
+    pub fn from_reader<R: Read>(reader: R) -> serde_json::Result<FullSummary> {
+        let mut summary: FullSummary = serde_json::from_reader(reader)?;
+        for (name, site) in summary.sites.iter_mut() {
+            site.name = name.clone();
+            for (tag_name, tag) in site.tags.iter_mut() {
+                tag.name = tag_name.clone();
+            }
+        }
+        for (name, tag) in summary.tags.iter_mut() {
+            tag.summary.name = name.clone();
+        }
+        Ok(summary)
+    }
+
+    /// Adds to every global tag the sites it appears in, with their share of the tag's totals
+    /// and the dominant site, and ranks the tags used by the most sites.
+    ///
+    /// Computed from the per-site summaries, so the data doesn't need to be parsed again.
+    pub fn with_tag_sites(mut self) -> FullSummary {
+        let mut shares: HashMap<&String, BTreeMap<String, TagSiteShare>> = HashMap::new();
+        for (site_name, site) in &self.sites {
+            for (tag_name, tag) in &site.tags {
+                if let Some(total) = self.tags.get(tag_name) {
+                    let share = TagSiteShare::new(
+                        tag.questions,
+                        tag.words,
+                        total.summary.questions,
+                        total.summary.words,
+                    );
+                    shares
+                        .entry(tag_name)
+                        .or_default()
+                        .insert(site_name.clone(), share);
+                }
+            }
+        }
+
+        let mut by_site_count: Vec<(&String, usize, u64)> = shares
+            .iter()
+            .map(|(name, sites)| (*name, sites.len(), self.tags[*name].summary.questions))
+            .collect();
+        by_site_count.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then_with(|| b.2.cmp(&a.2))
+                .then_with(|| a.0.cmp(b.0))
+        });
+        let widespread_tags = by_site_count
+            .iter()
+            .take(N_CHATTY)
+            .map(|(name, _, _)| (*name).clone())
+            .collect();
+
+        let mut tags = HashMap::with_capacity(self.tags.len());
+        for (name, sites) in shares {
+            let mut tag = self.tags[name].clone();
+            tag.set_sites(sites);
+            tags.insert(name.clone(), tag);
+        }
+        self.tags = tags;
+        self.totals.widespread_tags = Some(widespread_tags);
+        self
+    }
+
+    /// Returns the share of the given tag in every site it appears in, by site name
+    pub fn site_shares(&self, tag: &str) -> BTreeMap<String, TagSiteShare> {
+        let total = match self.tags.get(tag) {
+            Some(t) => &t.summary,
+            None => return BTreeMap::new(),
+        };
+        self.sites
+            .iter()
+            .filter_map(|(name, site)| {
+                site.tags.get(tag).map(|t| {
+                    let share =
+                        TagSiteShare::new(t.questions, t.words, total.questions, total.words);
+                    (name.clone(), share)
+                })
+            })
+            .collect()
     }
 }
 
-// No tests as this is just for output formatting
+/// Words per question, or 0 if there are no questions
+fn chattiness(questions: u64, words: u64) -> f32 {
+    if questions == 0 {
+        return 0.0;
+    }
+    words as f32 / questions as f32
+}
+
+/// Names of the `N_CHATTY` entries with the highest chattiness, ties broken by name
+fn chattiest<'a>(entries: impl Iterator<Item = (&'a String, f32)>) -> Vec<String> {
+    let mut entries: Vec<_> = entries.collect();
+    entries.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.0.cmp(b.0))
+    });
+    entries
+        .into_iter()
+        .take(N_CHATTY)
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::FullSummary;
+    use crate::site_summary::SiteSummary;
+    use crate::tag_summary::TagSummary;
+    use std::collections::HashMap;
+
+    fn test_summary() -> FullSummary {
+        let site1 = SiteSummary::new("site1", vec![TagSummary::new("a".to_string(), 2, 100)]);
+        let site2 = SiteSummary::new(
+            "site2",
+            vec![
+                TagSummary::new("a".to_string(), 1, 10),
+                TagSummary::new("b".to_string(), 1, 10),
+            ],
+        );
+        let total = site1.combine(&site2).unwrap();
+        let sites = HashMap::from([("site1".to_string(), site1), ("site2".to_string(), site2)]);
+        FullSummary::new(1, sites, &total)
+    }
+
+    #[test]
+    fn json_round_trip_keeps_names() {
+        let json = serde_json::to_string(&test_summary()).unwrap();
+        let summary = FullSummary::from_reader(json.as_bytes()).unwrap();
+        assert_eq!(summary.sites["site2"].name, "site2");
+        assert_eq!(summary.sites["site2"].tags["b"].name, "b");
+        assert_eq!(summary.tags["a"].summary.name, "a");
+        assert_eq!(summary.tags["a"].summary.words, 110);
+        assert_eq!(summary.totals.chatty_sites, vec!["site1", "site2"]);
+    }
+
+    #[test]
+    fn site_shares_lists_every_site() {
+        let summary = test_summary();
+        let shares = summary.site_shares("a");
+        let sites: Vec<&String> = shares.keys().collect();
+        assert_eq!(sites, vec!["site1", "site2"]);
+        assert_eq!(shares["site1"].question_share, 2.0 / 3.0);
+        assert_eq!(summary.site_shares("b").len(), 1);
+        assert!(summary.site_shares("c").is_empty());
+    }
+
+    #[test]
+    fn tag_sites_are_only_serialized_when_asked_for() {
+        let plain = serde_json::to_value(test_summary()).unwrap();
+        assert!(plain["tags"]["a"].get("sites").is_none());
+        assert!(plain["totals"].get("widespread_tags").is_none());
+
+        let detailed = serde_json::to_value(test_summary().with_tag_sites()).unwrap();
+        assert_eq!(detailed["tags"]["a"]["sites"]["site2"]["questions"], 1);
+        assert_eq!(detailed["tags"]["a"]["dominant_site"], "site1");
+        assert_eq!(detailed["tags"]["b"]["dominant_site"], "site2");
+        assert_eq!(detailed["totals"]["widespread_tags"][0], "a");
+        assert_eq!(detailed["totals"]["widespread_tags"][1], "b");
+    }
+
+    #[test]
+    fn from_sites_aggregates_tags() {
+        let built = test_summary();
+        let summary = FullSummary::from_sites(1, built.sites).unwrap();
+        assert_eq!(summary.tags["a"].summary.questions, 3);
+        assert_eq!(summary.tags["a"].summary.name, "a");
+        assert_eq!(summary.totals.chatty_tags, built.totals.chatty_tags);
+        assert_eq!(summary.totals.chatty_sites, vec!["site1", "site2"]);
+    }
+}
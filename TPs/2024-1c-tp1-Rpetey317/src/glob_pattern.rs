@@ -0,0 +1,124 @@
+/// A glob pattern matched against file paths.
+///
+/// `*` matches any characters but `/`, `?` matches a single one and a `**` segment matches any
+/// number of directories. Patterns without a `/` are matched against the file name only, at any
+/// depth.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlobPattern {
+    segments: Vec<String>,
+    file_name_only: bool,
+}
+
+impl GlobPattern {
+    /// Parses a pattern. Fails if it's empty
+    pub fn parse(pattern: &str) -> Result<GlobPattern, String> {
+        let segments: Vec<String> = split_path(pattern).map(String::from).collect();
+        if segments.is_empty() {
+            return Err(format!("Invalid glob pattern: '{}'", pattern));
+        }
+        Ok(GlobPattern {
+            file_name_only: !pattern.contains('/'),
+            segments,
+        })
+    }
+
+    /// Whether the given path matches the pattern
+    pub fn matches(&self, path: &str) -> bool {
+        let components: Vec<&str> = split_path(path).collect();
+        if self.file_name_only {
+            return match components.last() {
+                Some(name) => matches_segment(&self.segments[0], name),
+                None => false,
+            };
+        }
+        matches_segments(&self.segments, &components)
+    }
+}
+
+/// Splits a path into its components, ignoring empty and `.` ones
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split(['/', '\\'])
+        .filter(|component| !component.is_empty() && *component != ".")
+}
+
+/// Whether the path components match the pattern segments
+fn matches_segments(segments: &[String], components: &[&str]) -> bool {
+    match segments.split_first() {
+        None => components.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=components.len()).any(|skip| matches_segments(rest, &components[skip..]))
+        }
+        Some((first, rest)) => match components.split_first() {
+            Some((component, others)) => {
+                matches_segment(first, component) && matches_segments(rest, others)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Whether a single path component matches a pattern segment with `*` and `?` wildcards
+fn matches_segment(segment: &str, component: &str) -> bool {
+    let pattern: Vec<char> = segment.chars().collect();
+    let text: Vec<char> = component.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // position of the last `*` seen and of the text it was matched up to
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod test {
+    use super::GlobPattern;
+
+    fn glob(pattern: &str) -> GlobPattern {
+        GlobPattern::parse(pattern).unwrap()
+    }
+
+    #[test]
+    fn wildcards_match_file_names_at_any_depth() {
+        assert!(glob("*.jsonl").matches("site.jsonl"));
+        assert!(glob("*.jsonl").matches("nested/deeper/site.jsonl"));
+        assert!(!glob("*.jsonl").matches("site.json"));
+        assert!(glob("site?.jsonl").matches("a/site1.jsonl"));
+        assert!(!glob("site?.jsonl").matches("site10.jsonl"));
+        assert!(glob("*meta*").matches("x.meta.stackexchange.jsonl"));
+    }
+
+    #[test]
+    fn patterns_with_directories_match_whole_paths() {
+        assert!(glob("nested/*.jsonl").matches("nested/a.jsonl"));
+        assert!(glob("./nested/*.jsonl").matches("nested/a.jsonl"));
+        assert!(!glob("nested/*.jsonl").matches("nested/deeper/a.jsonl"));
+        assert!(glob("nested/**/*.jsonl").matches("nested/deeper/a.jsonl"));
+        assert!(glob("nested/**/*.jsonl").matches("nested/a.jsonl"));
+        assert!(glob("**/deeper/*").matches("nested/deeper/a.jsonl"));
+        assert!(!glob("**/deeper/*").matches("nested/a.jsonl"));
+    }
+
+    #[test]
+    fn empty_patterns_fail() {
+        assert!(GlobPattern::parse("").is_err());
+        assert!(GlobPattern::parse("/").is_err());
+    }
+}
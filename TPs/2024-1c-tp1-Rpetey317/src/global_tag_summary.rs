@@ -0,0 +1,89 @@
+use crate::tag_site_share::TagSiteShare;
+use crate::tag_summary::TagSummary;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Summary of a tag aggregated over all sites.
+///
+/// Serializes exactly like a `TagSummary`, unless the per-site breakdown was computed
+/// (see `FullSummary::with_tag_sites`)
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GlobalTagSummary {
+    /// Aggregated question and word counts
+    #[serde(flatten)]
+    pub summary: TagSummary,
+    /// Contribution of each site this tag appears in, by site name
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sites: Option<BTreeMap<String, TagSiteShare>>,
+    /// Site with the most questions with this tag (ties broken by site name)
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub dominant_site: Option<String>,
+}
+
+impl GlobalTagSummary {
+    /// Creates a global summary without the per-site breakdown
+    pub fn new(summary: TagSummary) -> GlobalTagSummary {
+        GlobalTagSummary {
+            summary,
+            sites: None,
+            dominant_site: None,
+        }
+    }
+
+    /// Sets the per-site breakdown and picks the dominant site from it
+    pub fn set_sites(&mut self, sites: BTreeMap<String, TagSiteShare>) {
+        // BTreeMap iterates by name, so keeping the first maximum breaks ties by name
+        let mut dominant: Option<(&String, u64)> = None;
+        for (name, share) in &sites {
+            let more_questions = match dominant {
+                Some((_, questions)) => share.questions > questions,
+                None => true,
+            };
+            if more_questions {
+                dominant = Some((name, share.questions));
+            }
+        }
+        self.dominant_site = dominant.map(|(name, _)| name.clone());
+        self.sites = Some(sites);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::GlobalTagSummary;
+    use crate::tag_site_share::TagSiteShare;
+    use crate::tag_summary::TagSummary;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn serializes_like_a_tag_summary_without_sites() {
+        let tag = GlobalTagSummary::new(TagSummary::new("a".to_string(), 2, 10));
+        let json = serde_json::to_string(&tag).unwrap();
+        assert_eq!(json, serde_json::to_string(&tag.summary).unwrap());
+    }
+
+    #[test]
+    fn dominant_site_has_most_questions() {
+        let mut tag = GlobalTagSummary::new(TagSummary::new("a".to_string(), 7, 70));
+        let sites = BTreeMap::from([
+            ("s1".to_string(), TagSiteShare::new(2, 20, 7, 70)),
+            ("s2".to_string(), TagSiteShare::new(3, 10, 7, 70)),
+            ("s3".to_string(), TagSiteShare::new(2, 40, 7, 70)),
+        ]);
+        tag.set_sites(sites);
+        assert_eq!(tag.dominant_site, Some("s2".to_string()));
+        let json: serde_json::Value = serde_json::to_value(&tag).unwrap();
+        assert_eq!(json["sites"]["s3"]["word_share"], 40.0 / 70.0);
+    }
+
+    #[test]
+    fn dominant_site_ties_are_broken_by_name() {
+        let mut tag = GlobalTagSummary::new(TagSummary::new("a".to_string(), 2, 2));
+        let sites = BTreeMap::from([
+            ("b".to_string(), TagSiteShare::new(1, 1, 2, 2)),
+            ("a".to_string(), TagSiteShare::new(1, 1, 2, 2)),
+        ]);
+        tag.set_sites(sites);
+        assert_eq!(tag.dominant_site, Some("a".to_string()));
+    }
+}
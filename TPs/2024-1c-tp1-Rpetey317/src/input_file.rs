@@ -0,0 +1,11 @@
+/// A file to summarize and the site its questions belong to
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputFile {
+    /// Path the file is opened with
+    pub path: String,
+    /// Name shown in progress, timings and anomalies: the path relative to the data directory,
+    /// or as listed if read from stdin
+    pub name: String,
+    /// Site of the questions that don't name their own
+    pub site: String,
+}
@@ -0,0 +1,54 @@
+//! Summarizes Q&A datasets in the StackExchange jsonl format (or any other, given a `Schema`):
+//! questions, words and chattiest tags of each site, and the chattiest sites and tags overall.
+//!
+//! `summarize_dir` summarizes a whole directory in parallel. `parse_reader` summarizes a single
+//! stream of lines, and `SiteSummary::merge` puts together summaries read separately, to be
+//! turned into a `FullSummary` with `FullSummary::from_sites`.
+//!
+//! For more control over which files are summarized as which site, progress and cancellation,
+//! `summarize_sites` takes the files as `InputFile`s, a `RunProgress` and a `Cancellation`.
+
+mod anomaly;
+mod cancellation;
+mod chattiness_estimate;
+mod count_overflow;
+mod dedup_mode;
+mod deduplicator;
+mod file_progress;
+mod full_summary;
+mod global_tag_summary;
+mod input_file;
+mod parse_options;
+mod parser;
+mod partial_run;
+mod question;
+mod run_progress;
+mod sampling;
+mod sampling_report;
+mod schema;
+mod site_assembler;
+mod site_summary;
+mod stop_reason;
+mod summarize;
+mod tag_site_share;
+mod tag_summary;
+
+pub use anomaly::anomaly_mode::AnomalyMode;
+pub use anomaly::anomaly_report::AnomalyReport;
+pub use cancellation::Cancellation;
+pub use count_overflow::CountOverflow;
+pub use dedup_mode::DedupMode;
+pub use file_progress::FileProgress;
+pub use full_summary::{FullSummary, Totals, N_CHATTY};
+pub use input_file::InputFile;
+pub use parse_options::ParseOptions;
+pub use parser::parse_reader;
+pub use partial_run::PartialRun;
+pub use run_progress::RunProgress;
+pub use sampling::Sampling;
+pub use sampling_report::SamplingReport;
+pub use schema::Schema;
+pub use site_summary::SiteSummary;
+pub use summarize::{summarize_dir, summarize_sites, summarize_sites_with, PADRON};
+pub use tag_site_share::TagSiteShare;
+pub use tag_summary::TagSummary;
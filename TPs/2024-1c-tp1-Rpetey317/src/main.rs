@@ -1,28 +1,112 @@
+use args::{Args, Command};
 use core::panic;
-use rayon::prelude::*;
+use ctrl_c::CtrlC;
+use discovery_options::DiscoveryOptions;
+use output::sharded_output::ShardedOutput;
+use progress_reporter::{report_progress, report_started_files};
 use rayon::ThreadPoolBuilder;
-use site_summary::SiteSummary;
-use std::collections::HashMap;
+#[cfg(feature = "sqlite")]
+use run_metadata::RunMetadata;
 use std::env;
-use std::fs;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Instant;
+#[cfg(feature = "sqlite")]
+use std::time::SystemTime;
+use timing_report::TimingReport;
+use tp1::{Cancellation, FullSummary, InputFile, ParseOptions, RunProgress, Schema, SiteSummary};
 
-mod full_summary;
-mod parser;
-mod site_summary;
-mod tag_summary;
+mod args;
+mod ctrl_c;
+mod discovery;
+mod discovery_options;
+#[cfg(feature = "sqlite")]
+mod distribution;
+mod glob_pattern;
+mod output;
+mod progress_reporter;
+#[cfg(feature = "sqlite")]
+mod run_metadata;
+mod server;
+mod site_name_pattern;
+#[cfg(feature = "sqlite")]
+mod sqlite_export;
+mod timing_report;
 
 /// Main function. Reads all jsonl files in the data directory, parses them and outputs a full summary to stdout.
 ///
+/// `--recursive` looks into subdirectories too, `--include GLOB` and `--exclude GLOB` select the
+/// files, and `--stdin` reads the list of files from stdin instead. `--site-name PATTERN` derives
+/// the site of each file from its path.
+///
 /// Takes an optional argument for the number of threads to use. Defaults to 4 on error or if not provided.
+/// Takes an optional `--schema FILE` to read files with a different input schema (see `Schema`),
+/// an optional `--format FMT` to print the summary as something other than JSON, and an optional
+/// `--sqlite FILE` to also export it to a SQLite database. `--out-dir DIR` writes it to a
+/// directory instead, split into a file per site (written as soon as each site is done), one
+/// for the tags and an index. `--tag-sites` adds the per-site
+/// breakdown of each global tag. `--dedup MODE` counts repeated questions only once.
+/// `--anomalies MODE` reports the questions with an extreme word count, optionally excluding them.
+/// `--sample FRACTION` (with `--seed N`) estimates the summary from a fraction of the lines.
+///
+/// Ctrl-C, or running out of the time given with `--time-budget DURATION`, stops the workers
+/// and prints a summary of what was read so far, flagged as partial.
+///
+/// `--progress` prints live progress and, at the end, a JSON timing report to stderr.
+/// `--timings FILE` writes that report to a file.
+///
+/// With the `serve` subcommand, computes the summary (or loads it with `--load FILE`) once and
+/// answers HTTP queries about it on localhost instead of printing it.
 ///
 /// Prints info messages to stderr for the sake of leaving just the final summary in stdout.
 fn main() {
-    // get number of threads from command line
-    let args: Vec<String> = env::args().collect();
-    let n_threads = args
-        .get(1)
-        .and_then(|arg| arg.parse::<usize>().ok())
-        .unwrap_or(4);
+    let started = Instant::now();
+    #[cfg(feature = "sqlite")]
+    let started_at = SystemTime::now();
+
+    // get number of threads and options from command line
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args = match Args::parse_args(&args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    if args.overwrite && args.sqlite_file.is_none() {
+        eprintln!("--overwrite only applies to the --sqlite database");
+        process::exit(1);
+    }
+    let n_threads = args.n_threads;
+    let ctrl_c = CtrlC::install();
+    let cancellation = Cancellation::new(args.time_budget.map(|budget| started + budget));
+    let schema = match &args.schema_file {
+        Some(path) => match Schema::from_file(path) {
+            Ok(schema) => schema,
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        },
+        None => Schema::default(),
+    };
+    let options = ParseOptions {
+        schema,
+        dedup: args.dedup,
+        anomalies: args.anomalies,
+        sample: args.sample,
+    };
+    let discovery = DiscoveryOptions {
+        recursive: args.recursive,
+        include: args.include,
+        exclude: args.exclude,
+        from_stdin: args.from_stdin,
+        site_name: args.site_name,
+        ..DiscoveryOptions::default()
+    };
 
     // set number of threads
     let thread_pool_result = ThreadPoolBuilder::new()
@@ -32,47 +116,232 @@ fn main() {
         panic!("Failed to set number of threads: {}", e);
     }
 
-    // get list of jsonl files in data directory
-    let mut sites: Vec<String> = Vec::new();
-    if let Ok(entries) = fs::read_dir("data") {
-        for entry in entries.flatten() {
-            let file_name = entry.file_name();
-            if let Some(file_name_str) = file_name.to_str() {
-                if file_name_str.ends_with(".jsonl") {
-                    sites.push(file_name_str.to_string());
-                }
+    if args.command == Command::Serve {
+        let summary = match &args.load_file {
+            Some(path) => load_summary(path),
+            None => {
+                let files = find_files(&discovery);
+                let progress = RunProgress::new(started, &files);
+                summarize_with_progress(
+                    &files,
+                    &options,
+                    &progress,
+                    &cancellation,
+                    &ctrl_c,
+                    args.progress,
+                    None,
+                )
             }
+        };
+        if let Err(e) = server::query_server::serve(summary, args.port, n_threads) {
+            eprintln!("Failed to start server: {}", e);
+            process::exit(1);
         }
-    } else {
-        panic!("Failed to read data directory. Perhaps you forgot to run download_data.sh?");
-    }
-
-    // process files
-    let summaries: HashMap<String, SiteSummary> = sites
-        .par_iter()
-        .map(|site| {
-            eprintln!("Processing {}", site);
-            (
-                site.clone(),
-                parser::parse_file(&format!("data/{}", site), site).unwrap_or(SiteSummary::empty()),
-            )
-        })
-        .collect();
-
-    // get aggregated summary
-    let total = summaries.clone().into_par_iter().reduce(
-        || (String::new(), SiteSummary::empty()),
-        |a: (String, SiteSummary), b: (String, SiteSummary)| {
-            let combined = a.1.combine(&b.1);
-            (combined.name.clone(), combined)
-        },
+        return;
+    }
+
+    // get list of input files
+    let discovery_start = Instant::now();
+    let files = find_files(&discovery);
+    let progress = RunProgress::new(started, &files);
+    progress.record_phase("discovery", discovery_start.elapsed());
+
+    // process files and generate output
+    let output = args.out_dir.as_deref().map(create_output);
+    let mut ans = summarize_with_progress(
+        &files,
+        &options,
+        &progress,
+        &cancellation,
+        &ctrl_c,
+        args.progress,
+        output.as_ref(),
+    );
+    if args.tag_sites {
+        ans = ans.with_tag_sites();
+    }
+    if let Some(path) = &args.sqlite_file {
+        #[cfg(feature = "sqlite")]
+        {
+            let metadata = RunMetadata {
+                n_threads,
+                input_files: files.iter().map(|file| file.path.clone()).collect(),
+                started_at,
+            };
+            progress.time_phase("export", || {
+                export_sqlite(&ans, path, &metadata, args.overwrite)
+            });
+        }
+        #[cfg(not(feature = "sqlite"))]
+        {
+            eprintln!(
+                "Cannot export to {}: built without SQLite support. Rebuild with --features sqlite",
+                path
+            );
+            process::exit(1);
+        }
+    }
+
+    let write_result = progress.time_phase("serialize", || match &output {
+        Some(output) => output.write_index(&ans),
+        None => {
+            let mut out = BufWriter::new(io::stdout().lock());
+            args.format.write(&ans, &mut out).and_then(|_| out.flush())
+        }
+    });
+    if let Err(e) = write_result {
+        panic!("Failed to serialize output: {}", e);
+    }
+
+    write_timings(
+        &progress,
+        n_threads,
+        args.progress,
+        args.timings_file.as_deref(),
     );
+}
+
+/// Sets a flag when dropped, so the progress reporter stops even if summarizing panics
+struct StopOnDrop<'a>(&'a AtomicBool);
+
+impl Drop for StopOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
 
-    // generate output
-    let ans = full_summary::FullSummary::new(109442, summaries, &total.1);
-    let json_result = serde_json::to_string_pretty(&ans);
-    match json_result {
-        Ok(j) => println!("{}", j),
-        Err(e) => panic!("Failed to serialize output: {}", e),
+/// Summarizes the given files, printing live progress to stderr while doing so if `progress`
+/// is set, or else the name of each file as it starts being parsed. Ctrl-C cancels the run while
+/// summarizing, warning that the summary is partial.
+///
+/// If an output directory is given, writes each site to it as soon as it's done, exiting if
+/// some site can't be written. Also exits if a count overflows
+fn summarize_with_progress(
+    files: &[InputFile],
+    options: &ParseOptions,
+    run_progress: &RunProgress,
+    cancellation: &Cancellation,
+    ctrl_c: &CtrlC,
+    progress: bool,
+    output: Option<&ShardedOutput>,
+) -> FullSummary {
+    let stop = AtomicBool::new(false);
+    let write_failed = AtomicBool::new(false);
+    let summary = thread::scope(|s| {
+        if progress {
+            s.spawn(|| report_progress(run_progress, &stop));
+        } else {
+            s.spawn(|| report_started_files(run_progress, &stop));
+        }
+        let _stop = StopOnDrop(&stop);
+        ctrl_c.cancelling(cancellation, || match output {
+            Some(output) => {
+                let write_site = |name: &str, site: &SiteSummary| {
+                    if let Err(e) = output.write_site(name, site) {
+                        eprintln!("Failed to write site {}: {}", name, e);
+                        write_failed.store(true, Ordering::Relaxed);
+                    }
+                };
+                tp1::summarize_sites_with(files, options, run_progress, cancellation, &write_site)
+            }
+            None => tp1::summarize_sites(files, options, run_progress, cancellation),
+        })
+    });
+    if write_failed.load(Ordering::Relaxed) {
+        process::exit(1);
+    }
+    let summary = match summary {
+        Ok(summary) => summary,
+        Err(e) => {
+            eprintln!("Failed to summarize: {}", e);
+            process::exit(1);
+        }
     };
+    if let Some(partial) = &summary.partial {
+        eprintln!(
+            "Stopped early ({}); the summary only covers the data read so far",
+            partial.reason
+        );
+    }
+    summary
+}
+
+/// Writes the timing report as JSON to stderr (if `to_stderr`) and to the given file
+fn write_timings(progress: &RunProgress, n_threads: usize, to_stderr: bool, file: Option<&str>) {
+    if !to_stderr && file.is_none() {
+        return;
+    }
+    let report = TimingReport::new(progress, n_threads);
+    if to_stderr {
+        match serde_json::to_string(&report) {
+            Ok(json) => eprintln!("{}", json),
+            Err(e) => eprintln!("Failed to serialize timing report: {}", e),
+        }
+    }
+    if let Some(path) = file {
+        let result = File::create(path).and_then(|f| {
+            let mut writer = BufWriter::new(f);
+            serde_json::to_writer_pretty(&mut writer, &report)?;
+            writer.write_all(b"\n")?;
+            writer.flush()
+        });
+        if let Err(e) = result {
+            eprintln!("Failed to write timing report to {}: {}", path, e);
+        }
+    }
+}
+
+/// Returns the input files, from the data directory or listed in stdin. Panics if the data
+/// directory doesn't exist, and exits if stdin can't be read
+fn find_files(options: &DiscoveryOptions) -> Vec<InputFile> {
+    if options.from_stdin {
+        return match discovery::read_file_list(io::stdin().lock(), options) {
+            Ok(files) => files,
+            Err(e) => {
+                eprintln!("Failed to read file list from stdin: {}", e);
+                process::exit(1);
+            }
+        };
+    }
+    match discovery::find_files(options) {
+        Ok(files) => files,
+        Err(_) => {
+            panic!("Failed to read data directory. Perhaps you forgot to run download_data.sh?")
+        }
+    }
+}
+
+/// Creates the output directory, exiting on failure
+fn create_output(dir: &str) -> ShardedOutput {
+    match ShardedOutput::create(dir) {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("Failed to create output directory {}: {}", dir, e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Reads a summary previously printed as JSON, exiting on failure
+fn load_summary(path: &str) -> FullSummary {
+    let result = File::open(path)
+        .map_err(|e| e.to_string())
+        .and_then(|file| FullSummary::from_reader(BufReader::new(file)).map_err(|e| e.to_string()));
+    match result {
+        Ok(summary) => summary,
+        Err(e) => {
+            eprintln!("Failed to load {}: {}", path, e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Exports the summary to a SQLite database, exiting on failure
+#[cfg(feature = "sqlite")]
+fn export_sqlite(summary: &FullSummary, path: &str, metadata: &RunMetadata, overwrite: bool) {
+    eprintln!("Exporting to {}", path);
+    if let Err(e) = sqlite_export::export(summary, path, metadata, overwrite) {
+        eprintln!("Failed to export to {}: {}", path, e);
+        process::exit(1);
+    }
 }
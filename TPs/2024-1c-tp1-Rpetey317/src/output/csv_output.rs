@@ -0,0 +1,126 @@
+use std::io::{self, Write};
+use tp1::FullSummary;
+
+/// Writes a table with one row per site: `site,questions,words,chattiness`. Sorted by site
+pub fn write_sites<W: Write>(summary: &FullSummary, out: &mut W) -> io::Result<()> {
+    writeln!(out, "site,questions,words,chattiness")?;
+    let mut sites: Vec<_> = summary.sites.iter().collect();
+    sites.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, site) in sites {
+        writeln!(
+            out,
+            "{},{},{},{}",
+            escape(name),
+            site.questions,
+            site.words,
+            site.chattiness()
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes a table with one row per tag over all sites: `tag,questions,words,chattiness`.
+/// Sorted by tag
+pub fn write_tags<W: Write>(summary: &FullSummary, out: &mut W) -> io::Result<()> {
+    writeln!(out, "tag,questions,words,chattiness")?;
+    let mut tags: Vec<_> = summary.tags.iter().collect();
+    tags.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, tag) in tags {
+        writeln!(
+            out,
+            "{},{},{},{}",
+            escape(name),
+            tag.summary.questions,
+            tag.summary.words,
+            tag.summary.chattiness()
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes a table with one row per tag of each site: `site,tag,questions,words,chattiness`.
+/// Sorted by site, then by tag
+pub fn write_site_tags<W: Write>(summary: &FullSummary, out: &mut W) -> io::Result<()> {
+    writeln!(out, "site,tag,questions,words,chattiness")?;
+    let mut sites: Vec<_> = summary.sites.iter().collect();
+    sites.sort_by(|a, b| a.0.cmp(b.0));
+    for (site_name, site) in sites {
+        let mut tags: Vec<_> = site.tags.iter().collect();
+        tags.sort_by(|a, b| a.0.cmp(b.0));
+        for (tag_name, tag) in tags {
+            writeln!(
+                out,
+                "{},{},{},{},{}",
+                escape(site_name),
+                escape(tag_name),
+                tag.questions,
+                tag.words,
+                tag.chattiness()
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Quotes a field if it contains characters with special meaning in CSV (RFC 4180)
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{escape, write_site_tags, write_sites, write_tags};
+    use crate::output::fixture;
+
+    #[test]
+    fn plain_fields_are_kept() {
+        assert_eq!(escape("c++"), "c++");
+    }
+
+    #[test]
+    fn special_fields_are_quoted() {
+        assert_eq!(escape("a,b"), "\"a,b\"");
+        assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn sites_are_written_in_order() {
+        let mut out = Vec::new();
+        write_sites(&fixture::summary(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "site,questions,words,chattiness\n\
+             math,2,100,50\n\
+             \"say \"\"hi\"\"\",4,100,25\n"
+        );
+    }
+
+    #[test]
+    fn tags_are_written_in_order() {
+        let mut out = Vec::new();
+        write_tags(&fixture::summary(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "tag,questions,words,chattiness\n\
+             \"a,b\",4,120,30\n\
+             x|y,2,80,40\n"
+        );
+    }
+
+    #[test]
+    fn site_tags_are_written_in_order() {
+        let mut out = Vec::new();
+        write_site_tags(&fixture::summary(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "site,tag,questions,words,chattiness\n\
+             math,\"a,b\",2,100,50\n\
+             \"say \"\"hi\"\"\",\"a,b\",2,20,10\n\
+             \"say \"\"hi\"\"\",x|y,2,80,40\n"
+        );
+    }
+}
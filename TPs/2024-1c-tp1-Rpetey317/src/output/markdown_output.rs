@@ -0,0 +1,149 @@
+use std::io::{self, Write};
+use tp1::{FullSummary, N_CHATTY};
+
+/// Writes a human readable report: overall totals, the chattiest sites and tags, and the
+/// chattiest tags of each site
+pub fn write<W: Write>(summary: &FullSummary, out: &mut W) -> io::Result<()> {
+    let questions: u128 = summary.sites.values().map(|s| s.questions as u128).sum();
+    let words: u128 = summary.sites.values().map(|s| s.words as u128).sum();
+
+    writeln!(out, "# StackExchange summary")?;
+    writeln!(out)?;
+    writeln!(out, "- Sites: {}", summary.sites.len())?;
+    writeln!(out, "- Tags: {}", summary.tags.len())?;
+    writeln!(out, "- Questions: {}", questions)?;
+    writeln!(out, "- Words: {}", words)?;
+
+    writeln!(out)?;
+    writeln!(out, "## Chattiest sites")?;
+    writeln!(out)?;
+    table_header(out, "Site")?;
+    for (i, name) in summary.totals.chatty_sites.iter().enumerate() {
+        if let Some(site) = summary.sites.get(name) {
+            table_row(
+                out,
+                i + 1,
+                name,
+                site.questions,
+                site.words,
+                site.chattiness(),
+            )?;
+        }
+    }
+
+    writeln!(out)?;
+    writeln!(out, "## Chattiest tags")?;
+    writeln!(out)?;
+    table_header(out, "Tag")?;
+    for (i, name) in summary.totals.chatty_tags.iter().enumerate() {
+        if let Some(tag) = summary.tags.get(name).map(|t| &t.summary) {
+            table_row(out, i + 1, name, tag.questions, tag.words, tag.chattiness())?;
+        }
+    }
+
+    let mut sites: Vec<_> = summary.sites.iter().collect();
+    sites.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, site) in sites {
+        writeln!(out)?;
+        writeln!(out, "## {}", name)?;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{} questions, {} words, chattiness {:.2}",
+            site.questions,
+            site.words,
+            site.chattiness()
+        )?;
+        writeln!(out)?;
+        table_header(out, "Tag")?;
+        for (i, tag) in site.n_chattiest(N_CHATTY).iter().enumerate() {
+            table_row(
+                out,
+                i + 1,
+                &tag.name,
+                tag.questions,
+                tag.words,
+                tag.chattiness(),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the header of a ranking table whose entries are of the given kind
+fn table_header<W: Write>(out: &mut W, kind: &str) -> io::Result<()> {
+    writeln!(out, "| # | {} | Questions | Words | Chattiness |", kind)?;
+    writeln!(out, "|---|---|---:|---:|---:|")
+}
+
+/// Writes a row of a ranking table
+fn table_row<W: Write>(
+    out: &mut W,
+    rank: usize,
+    name: &str,
+    questions: u64,
+    words: u64,
+    chattiness: f32,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "| {} | {} | {} | {} | {:.2} |",
+        rank,
+        name.replace('|', "\\|"),
+        questions,
+        words,
+        chattiness
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::write;
+    use crate::output::fixture;
+
+    #[test]
+    fn report_has_totals_rankings_and_sites() {
+        let mut out = Vec::new();
+        write(&fixture::summary(), &mut out).unwrap();
+        let expected = "\
+# StackExchange summary
+
+- Sites: 2
+- Tags: 2
+- Questions: 6
+- Words: 200
+
+## Chattiest sites
+
+| # | Site | Questions | Words | Chattiness |
+|---|---|---:|---:|---:|
+| 1 | math | 2 | 100 | 50.00 |
+| 2 | say \"hi\" | 4 | 100 | 25.00 |
+
+## Chattiest tags
+
+| # | Tag | Questions | Words | Chattiness |
+|---|---|---:|---:|---:|
+| 1 | x\\|y | 2 | 80 | 40.00 |
+| 2 | a,b | 4 | 120 | 30.00 |
+
+## math
+
+2 questions, 100 words, chattiness 50.00
+
+| # | Tag | Questions | Words | Chattiness |
+|---|---|---:|---:|---:|
+| 1 | a,b | 2 | 100 | 50.00 |
+
+## say \"hi\"
+
+4 questions, 100 words, chattiness 25.00
+
+| # | Tag | Questions | Words | Chattiness |
+|---|---|---:|---:|---:|
+| 1 | x\\|y | 2 | 80 | 40.00 |
+| 2 | a,b | 2 | 20 | 10.00 |
+";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+}
@@ -0,0 +1,25 @@
+pub mod csv_output;
+pub mod markdown_output;
+pub mod ndjson_output;
+pub mod output_format;
+pub mod sharded_output;
+
+#[cfg(test)]
+mod fixture {
+    use std::collections::HashMap;
+    use tp1::{FullSummary, SiteSummary, TagSummary};
+
+    /// Two sites whose names need escaping in every output format
+    pub fn summary() -> FullSummary {
+        let math = SiteSummary::new("math", vec![TagSummary::new("a,b".to_string(), 2, 100)]);
+        let quoted = SiteSummary::new(
+            "say \"hi\"",
+            vec![
+                TagSummary::new("a,b".to_string(), 2, 20),
+                TagSummary::new("x|y".to_string(), 2, 80),
+            ],
+        );
+        let sites = HashMap::from([(math.name.clone(), math), (quoted.name.clone(), quoted)]);
+        FullSummary::from_sites(1, sites).unwrap()
+    }
+}
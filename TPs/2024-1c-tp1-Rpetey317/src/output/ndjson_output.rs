@@ -0,0 +1,92 @@
+use serde::Serialize;
+use std::io::{self, Write};
+use tp1::{FullSummary, N_CHATTY};
+
+/// A single line of the NDJSON output
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Record<'a> {
+    /// Totals and chattiest tags of a site
+    Site {
+        name: &'a str,
+        questions: u64,
+        words: u64,
+        chattiness: f32,
+        chatty_tags: Vec<String>,
+    },
+    /// Totals of a tag over all sites
+    Tag {
+        name: &'a str,
+        questions: u64,
+        words: u64,
+        chattiness: f32,
+        sites: usize,
+    },
+}
+
+/// Writes one JSON record per line: first every site, then every tag, each sorted by name
+pub fn write<W: Write>(summary: &FullSummary, out: &mut W) -> io::Result<()> {
+    let mut sites: Vec<_> = summary.sites.iter().collect();
+    sites.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, site) in sites {
+        let record = Record::Site {
+            name,
+            questions: site.questions,
+            words: site.words,
+            chattiness: site.chattiness(),
+            chatty_tags: site
+                .n_chattiest(N_CHATTY)
+                .into_iter()
+                .map(|tag| tag.name)
+                .collect(),
+        };
+        serde_json::to_writer(&mut *out, &record)?;
+        writeln!(out)?;
+    }
+
+    let mut tags: Vec<_> = summary.tags.iter().collect();
+    tags.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, tag) in tags {
+        let record = Record::Tag {
+            name,
+            questions: tag.summary.questions,
+            words: tag.summary.words,
+            chattiness: tag.summary.chattiness(),
+            sites: match &tag.sites {
+                Some(sites) => sites.len(),
+                None => summary
+                    .sites
+                    .values()
+                    .filter(|site| site.tags.contains_key(name))
+                    .count(),
+            },
+        };
+        serde_json::to_writer(&mut *out, &record)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::write;
+    use crate::output::fixture;
+
+    #[test]
+    fn one_record_per_line() {
+        let mut out = Vec::new();
+        write(&fixture::summary(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"type":"site","name":"math","questions":2,"words":100,"chattiness":50.0,"chatty_tags":["a,b"]}"#,
+                r#"{"type":"site","name":"say \"hi\"","questions":4,"words":100,"chattiness":25.0,"chatty_tags":["x|y","a,b"]}"#,
+                r#"{"type":"tag","name":"a,b","questions":4,"words":120,"chattiness":30.0,"sites":2}"#,
+                r#"{"type":"tag","name":"x|y","questions":2,"words":80,"chattiness":40.0,"sites":1}"#,
+            ]
+        );
+        assert!(out.ends_with('\n'));
+    }
+}
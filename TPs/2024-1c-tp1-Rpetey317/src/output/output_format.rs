@@ -0,0 +1,66 @@
+use crate::output::{csv_output, markdown_output, ndjson_output};
+use std::io::{self, Write};
+use tp1::FullSummary;
+
+/// Format in which the summary is written to stdout
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// Pretty printed JSON, as asked in the assignment specifications
+    Json,
+    /// CSV table with one row per site
+    CsvSites,
+    /// CSV table with one row per tag (aggregated over all sites)
+    CsvTags,
+    /// CSV table with one row per (site, tag) pair
+    CsvSiteTags,
+    /// One JSON record per line for each site and each tag
+    Ndjson,
+    /// Human readable report with the chattiest sites and tags
+    Markdown,
+}
+
+impl OutputFormat {
+    /// Parses a format from its command line name
+    pub fn parse(name: &str) -> Result<OutputFormat, String> {
+        match name {
+            "json" => Ok(OutputFormat::Json),
+            "csv-sites" => Ok(OutputFormat::CsvSites),
+            "csv-tags" => Ok(OutputFormat::CsvTags),
+            "csv" | "csv-site-tags" => Ok(OutputFormat::CsvSiteTags),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            _ => Err(format!(
+                "Unknown format: {}. Use one of [json, csv-sites, csv-tags, csv-site-tags, ndjson, markdown]",
+                name
+            )),
+        }
+    }
+
+    /// Writes the summary to `out` in this format
+    pub fn write<W: Write>(&self, summary: &FullSummary, out: &mut W) -> io::Result<()> {
+        match self {
+            OutputFormat::Json => {
+                let json = serde_json::to_string_pretty(summary)?;
+                writeln!(out, "{}", json)
+            }
+            OutputFormat::CsvSites => csv_output::write_sites(summary, out),
+            OutputFormat::CsvTags => csv_output::write_tags(summary, out),
+            OutputFormat::CsvSiteTags => csv_output::write_site_tags(summary, out),
+            OutputFormat::Ndjson => ndjson_output::write(summary, out),
+            OutputFormat::Markdown => markdown_output::write(summary, out),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::OutputFormat;
+
+    #[test]
+    fn parses_names_and_aliases() {
+        assert_eq!(OutputFormat::parse("json"), Ok(OutputFormat::Json));
+        assert_eq!(OutputFormat::parse("csv"), Ok(OutputFormat::CsvSiteTags));
+        assert_eq!(OutputFormat::parse("md"), Ok(OutputFormat::Markdown));
+        assert!(OutputFormat::parse("xml").is_err());
+    }
+}
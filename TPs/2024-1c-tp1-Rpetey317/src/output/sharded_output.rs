@@ -0,0 +1,152 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tp1::{AnomalyReport, FullSummary, PartialRun, SamplingReport, SiteSummary, Totals};
+
+/// Directory of the per-site files, inside the output directory
+const SITES_DIR: &str = "sites";
+
+/// Everything in the summary but the sites and tags, written to `index.json`
+#[derive(Serialize)]
+struct Index<'a> {
+    padron: u32,
+    /// File of each site, relative to the output directory
+    sites: BTreeMap<&'a str, String>,
+    totals: &'a Totals,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    anomalies: &'a Option<AnomalyReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    partial: &'a Option<PartialRun>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sampling: &'a Option<SamplingReport>,
+}
+
+/// Writes the summary split into files in a directory, instead of as a single JSON:
+/// `sites/<site>.json` with the summary of each site, `tags.json` with the global tags and
+/// `index.json` with the totals and rankings, the file of each site and the rest of the
+/// sections. Every file is pretty printed JSON, laid out as in the single JSON output.
+///
+/// Sites can be written one by one while the rest are being summarized (see
+/// `summarize_sites_with`)
+pub struct ShardedOutput {
+    dir: PathBuf,
+}
+
+impl ShardedOutput {
+    /// Creates the output directory and its `sites` subdirectory, if they don't exist.
+    /// Site files left there by a previous run are removed, so they aren't mistaken for sites
+    /// of this one
+    pub fn create(dir: &str) -> io::Result<ShardedOutput> {
+        let dir = PathBuf::from(dir);
+        let sites_dir = dir.join(SITES_DIR);
+        fs::create_dir_all(&sites_dir)?;
+        for entry in fs::read_dir(&sites_dir)? {
+            let path = entry?.path();
+            if path.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(ShardedOutput { dir })
+    }
+
+    /// Writes the summary of a site to its file
+    pub fn write_site(&self, name: &str, site: &SiteSummary) -> io::Result<()> {
+        write_json(&self.dir.join(site_file(name)), site)
+    }
+
+    /// Writes `tags.json` and `index.json`. The sites are expected to have been written already
+    pub fn write_index(&self, summary: &FullSummary) -> io::Result<()> {
+        write_json(&self.dir.join("tags.json"), &summary.tags)?;
+        let index = Index {
+            padron: summary.padron,
+            sites: summary
+                .sites
+                .keys()
+                .map(|name| (name.as_str(), site_file(name)))
+                .collect(),
+            totals: &summary.totals,
+            anomalies: &summary.anomalies,
+            partial: &summary.partial,
+            sampling: &summary.sampling,
+        };
+        write_json(&self.dir.join("index.json"), &index)
+    }
+}
+
+/// Path of the file of a site, relative to the output directory. Characters that can't be in
+/// a file name are percent-encoded, so every site gets a different file
+fn site_file(name: &str) -> String {
+    let mut file = format!("{}/", SITES_DIR);
+    for c in name.chars() {
+        match c {
+            '%' | '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => {
+                file.push_str(&format!("%{:02X}", c as u32))
+            }
+            c if c.is_control() => file.push_str(&format!("%{:02X}", c as u32)),
+            c => file.push(c),
+        }
+    }
+    file.push_str(".json");
+    file
+}
+
+/// Writes a value as pretty printed JSON to a file, replacing it if it exists
+fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, value)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{site_file, ShardedOutput};
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::{env, fs, process};
+    use tp1::{FullSummary, SiteSummary, TagSummary};
+
+    #[test]
+    fn site_names_are_safe_file_names() {
+        assert_eq!(site_file("so.jsonl"), "sites/so.jsonl.json");
+        assert_eq!(site_file("meta/so"), "sites/meta%2Fso.json");
+        assert_eq!(site_file("100%"), "sites/100%25.json");
+        assert_ne!(site_file("a/b"), site_file("a%2Fb"));
+    }
+
+    #[test]
+    fn writes_sites_tags_and_index() {
+        let site = SiteSummary::new("a/b", vec![TagSummary::new("x".to_string(), 2, 30)]);
+        let summary =
+            FullSummary::new(1, HashMap::from([("a/b".to_string(), site.clone())]), &site);
+        let dir = env::temp_dir().join(format!("tp1-sharded-{}", process::id()));
+        let output = ShardedOutput::create(dir.to_str().unwrap()).unwrap();
+        output.write_site("a/b", &site).unwrap();
+        output.write_index(&summary).unwrap();
+
+        let read = |file: &str| -> Value {
+            serde_json::from_str(&fs::read_to_string(dir.join(file)).unwrap()).unwrap()
+        };
+        let index = read("index.json");
+        assert_eq!(index["sites"]["a/b"], "sites/a%2Fb.json");
+        assert_eq!(index["totals"]["chatty_tags"][0], "x");
+        assert!(index.get("partial").is_none());
+        assert_eq!(read("sites/a%2Fb.json")["words"], 30);
+        assert_eq!(read("tags.json")["x"]["questions"], 2);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn old_site_files_are_removed() {
+        let dir = env::temp_dir().join(format!("tp1-sharded-stale-{}", process::id()));
+        fs::create_dir_all(dir.join("sites")).unwrap();
+        fs::write(dir.join("sites/old.json"), "{}").unwrap();
+        fs::write(dir.join("sites/notes.txt"), "").unwrap();
+        ShardedOutput::create(dir.to_str().unwrap()).unwrap();
+        assert!(!dir.join("sites/old.json").exists());
+        assert!(dir.join("sites/notes.txt").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,17 @@
+use crate::anomaly::anomaly_mode::AnomalyMode;
+use crate::dedup_mode::DedupMode;
+use crate::sampling::Sampling;
+use crate::schema::Schema;
+
+/// Options that change how input files are parsed
+#[derive(Default)]
+pub struct ParseOptions {
+    /// Schema of the input lines
+    pub schema: Schema,
+    /// Which repeated questions are dropped
+    pub dedup: DedupMode,
+    /// What is done with questions whose word count is an extreme outlier
+    pub anomalies: AnomalyMode,
+    /// Fraction of the lines read, if not every one
+    pub sample: Option<Sampling>,
+}
@@ -1,8 +1,20 @@
+use crate::anomaly::anomaly_detector::AnomalyDetector;
+use crate::anomaly::anomaly_mode::AnomalyMode;
+use crate::anomaly::word_histogram::WordDistributions;
+use crate::cancellation::Cancellation;
+use crate::count_overflow::CountOverflow;
+use crate::deduplicator::{Deduplicator, Fingerprint};
+use crate::file_progress::FileProgress;
+use crate::parse_options::ParseOptions;
+use crate::question::Question;
 use crate::site_summary::SiteSummary;
 use crate::tag_summary::TagSummary;
 use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, BufReader};
+use std::ops::ControlFlow;
 use std::path::Path;
 
 /// Auxiliary struct to parse jsonl files
@@ -12,40 +24,299 @@ struct Line {
     tags: Vec<String>,
 }
 
-/// Parses a jsonl file containing site data and returns a SiteSummary with the given name
+/// Parses jsonl lines that follow the given schema and returns a SiteSummary for each site
+/// found in them, by site name.
+///
+/// Lines that don't name their site (every line, if the schema has no site field) belong to
+/// `site_name`. Lines that can't be read or parsed are skipped. If deduplicating, questions
+/// repeated within the reader are counted once and reported as duplicates of their site.
+/// Outliers are not looked for, as that takes a pass over the whole dataset first (see
+/// `summarize_sites`).
+///
+/// Fails if a count of some site or tag overflows
+pub fn parse_reader<R: BufRead>(
+    reader: R,
+    site_name: &str,
+    options: &ParseOptions,
+) -> Result<HashMap<String, SiteSummary>, CountOverflow> {
+    let mut dedup = Deduplicator::new(options.dedup, None, 0);
+    let mut anomalies = AnomalyDetector::new(AnomalyMode::Off, None, site_name);
+    parse_lines(
+        reader,
+        site_name,
+        site_name,
+        options,
+        &mut dedup,
+        &mut anomalies,
+        &FileProgress::default(),
+        &Cancellation::default(),
+    )
+}
+
+/// Parses a jsonl file whose lines follow the given schema and returns a SiteSummary for each
+/// site found in it, by site name.
+///
+/// Lines that don't name their site (every line, if the schema has no site field) belong to
+/// `site_name`. Lines that can't be parsed are skipped. Questions `dedup` doesn't keep are not
+/// counted, but reported as duplicates of their site. Questions are then checked by `anomalies`,
+/// which records the outliers. Every line read is accounted in `progress`.
+/// Stops before the next line once `cancellation` is cancelled, returning what was read so far.
+///
+/// May fail if the file cannot be opened. Once opened, fails if a count of some site or tag
+/// overflows
+pub(crate) fn parse_file(
+    filename: &str,
+    site_name: &str,
+    options: &ParseOptions,
+    dedup: &mut Deduplicator,
+    anomalies: &mut AnomalyDetector,
+    progress: &FileProgress,
+    cancellation: &Cancellation,
+) -> io::Result<Result<HashMap<String, SiteSummary>, CountOverflow>> {
+    let reader = open(filename)?;
+    Ok(parse_lines(
+        reader,
+        filename,
+        site_name,
+        options,
+        dedup,
+        anomalies,
+        progress,
+        cancellation,
+    ))
+}
+
+/// Parses the lines of a reader, as described in `parse_file`. `source` names the reader when
+/// choosing which lines are sampled
+#[allow(clippy::too_many_arguments)]
+fn parse_lines<R: BufRead>(
+    reader: R,
+    source: &str,
+    site_name: &str,
+    options: &ParseOptions,
+    dedup: &mut Deduplicator,
+    anomalies: &mut AnomalyDetector,
+    progress: &FileProgress,
+    cancellation: &Cancellation,
+) -> Result<HashMap<String, SiteSummary>, CountOverflow> {
+    let mut sites = HashMap::new();
+    if !options.schema.has_site() {
+        let mut site = SiteSummary::new(site_name, Vec::new());
+        if dedup.is_enabled() {
+            site.track_duplicates();
+        }
+        sites.insert(site_name.to_string(), site);
+    }
+
+    let mut overflow = None;
+    read_questions(
+        reader,
+        source,
+        options,
+        dedup.is_enabled(),
+        progress,
+        cancellation,
+        |line, question| match count_question(
+            &mut sites, site_name, dedup, anomalies, line, question,
+        ) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(e) => {
+                overflow = Some(e);
+                ControlFlow::Break(())
+            }
+        },
+    );
+
+    match overflow {
+        Some(e) => Err(e),
+        None => Ok(sites),
+    }
+}
+
+/// Counts a question of the given line in its site, unless `dedup` leaves it out, checking
+/// whether it's an outlier. Fails if a count of the site or its tags overflows
+fn count_question(
+    sites: &mut HashMap<String, SiteSummary>,
+    site_name: &str,
+    dedup: &mut Deduplicator,
+    anomalies: &mut AnomalyDetector,
+    line: usize,
+    question: Question,
+) -> Result<(), CountOverflow> {
+    let name = question.site.as_deref().unwrap_or(site_name);
+    if !sites.contains_key(name) {
+        let mut site = SiteSummary::new(name, Vec::new());
+        if dedup.is_enabled() {
+            site.track_duplicates();
+        }
+        sites.insert(name.to_string(), site);
+    }
+    let summary = match sites.get_mut(name) {
+        Some(s) => s,
+        None => return Ok(()),
+    };
+
+    if let Some(fingerprint) = question.fingerprint {
+        if !dedup.keep(dedup.key(name, fingerprint)) {
+            return summary.add_duplicate();
+        }
+    }
+    anomalies.check(line, name, question.words, &question.tags)?;
+
+    summary.add_question(question.words)?;
+    for tag in question.tags {
+        let new_tag = TagSummary::from_question(tag, question.words);
+        summary.add_tag(new_tag)?;
+    }
+    Ok(())
+}
+
+/// Scans the file before parsing it, without counting anything. Returns the dedup key of every
+/// question if `keys` is set, and the word count distributions of its sites and tags if `words`
+/// is set. Questions repeated within the file are left out of the distributions, as they won't
+/// be counted either.
+///
+/// Used to decide which file owns the questions repeated across files, and to find the outliers
+/// of each site and tag, in a single pass.
 ///
 /// May fail if the file cannot be opened
-pub fn parse_file(filename: &str, site_name: &str) -> io::Result<SiteSummary> {
-    let path = Path::new(filename);
-    let file = File::open(path)?;
-    let reader = io::BufReader::new(file);
+pub(crate) fn scan_file(
+    filename: &str,
+    site_name: &str,
+    options: &ParseOptions,
+    dedup: &mut Deduplicator,
+    keys: bool,
+    words: bool,
+    cancellation: &Cancellation,
+) -> io::Result<(HashSet<Fingerprint>, WordDistributions)> {
+    let mut found_keys = HashSet::new();
+    let mut distributions = WordDistributions::default();
+    read_questions(
+        open(filename)?,
+        filename,
+        options,
+        dedup.is_enabled(),
+        &FileProgress::default(),
+        cancellation,
+        |_, question| {
+            let name = question.site.as_deref().unwrap_or(site_name);
+            if let Some(fingerprint) = question.fingerprint {
+                let key = dedup.key(name, fingerprint);
+                if keys {
+                    found_keys.insert(key);
+                }
+                if words && !dedup.keep(key) {
+                    return ControlFlow::Continue(());
+                }
+            }
+            if words {
+                distributions.add(name, question.words, &question.tags);
+            }
+            ControlFlow::Continue(())
+        },
+    );
+    Ok((found_keys, distributions))
+}
+
+/// Opens a file for reading line by line
+fn open(filename: &str) -> io::Result<BufReader<File>> {
+    let file = File::open(Path::new(filename))?;
+    Ok(BufReader::new(file))
+}
 
-    let mut summary = SiteSummary::new(site_name, Vec::new());
+/// Reads every line, calling `f` with the line number (starting at 1) and question of each line
+/// that could be parsed. If sampling, lines of `source` that aren't sampled are skipped before
+/// being parsed. Stops early, at a line boundary, if cancelled or if `f` breaks
+fn read_questions<R: BufRead>(
+    reader: R,
+    source: &str,
+    options: &ParseOptions,
+    with_fingerprint: bool,
+    progress: &FileProgress,
+    cancellation: &Cancellation,
+    mut f: impl FnMut(usize, Question) -> ControlFlow<()>,
+) {
+    let schema = &options.schema;
+    let fast_path = schema.is_default();
 
-    for line in reader.lines() {
+    for (index, line) in reader.lines().enumerate() {
+        if cancellation.is_cancelled() {
+            break;
+        }
         let line = match line {
             Ok(l) => l,
             Err(_) => continue,
         };
-        if let Ok(line) = serde_json::from_str::<Line>(&line) {
-            let wc = line.texts[0].split_whitespace().count() as u32
-                + line.texts[1].split_whitespace().count() as u32;
-
-            summary.add_question(wc);
-
-            for tag in line.tags {
-                let new_tag = TagSummary::new(tag, 1, wc);
-                summary.add_tag(new_tag);
+        progress.add_line(line.len());
+        if let Some(sampling) = &options.sample {
+            if !sampling.keeps(source, index + 1) {
+                continue;
+            }
+        }
+        let question = if fast_path {
+            parse_line(&line, with_fingerprint)
+        } else {
+            serde_json::from_str::<Value>(&line)
+                .ok()
+                .and_then(|value| schema.extract(&value, with_fingerprint))
+        };
+        if let Some(q) = question {
+            if f(index + 1, q).is_break() {
+                break;
             }
         }
     }
+}
 
-    Ok(summary)
+/// Parses a line of the StackExchange dataset, without going through a generic JSON value
+fn parse_line(line: &str, with_fingerprint: bool) -> Option<Question> {
+    let line = serde_json::from_str::<Line>(line).ok()?;
+    let (title, body) = match line.texts.as_slice() {
+        [title, body, ..] => (title, body),
+        _ => return None,
+    };
+    let words = title.split_whitespace().count() as u32 + body.split_whitespace().count() as u32;
+    let fingerprint = with_fingerprint.then(|| crate::deduplicator::fingerprint(title, body));
+    Some(Question {
+        words,
+        tags: line.tags,
+        site: None,
+        fingerprint,
+    })
 }
 
 #[cfg(test)]
+#[allow(clippy::unnecessary_to_owned)]
 mod test {
-    use super::parse_file;
+    use crate::anomaly::anomaly_detector::AnomalyDetector;
+    use crate::anomaly::anomaly_mode::AnomalyMode;
+    use crate::cancellation::Cancellation;
+    use crate::dedup_mode::DedupMode;
+    use crate::deduplicator::Deduplicator;
+    use crate::file_progress::FileProgress;
+    use crate::parse_options::ParseOptions;
+    use crate::schema::Schema;
+    use crate::site_summary::SiteSummary;
+    use std::io;
+
+    /// Parses a file with the default schema, returning the summary of its only site
+    fn parse_file(filename: &str, site_name: &str) -> io::Result<SiteSummary> {
+        let progress = FileProgress::default();
+        let options = ParseOptions::default();
+        let mut dedup = Deduplicator::new(DedupMode::Off, None, 0);
+        let mut anomalies = AnomalyDetector::new(AnomalyMode::Off, None, filename);
+        let mut sites = super::parse_file(
+            filename,
+            site_name,
+            &options,
+            &mut dedup,
+            &mut anomalies,
+            &progress,
+            &Cancellation::default(),
+        )?
+        .unwrap();
+        Ok(sites.remove(site_name).unwrap())
+    }
 
     #[test]
     fn simplest_site_works() {
@@ -98,4 +369,147 @@ mod test {
         assert_eq!(summary.tags.len(), 11);
         assert_eq!(summary.chattiness(), 10.0)
     }
+
+    #[test]
+    fn site_field_splits_file_into_sites() {
+        let options = ParseOptions {
+            schema: Schema::from_file("schemas/flat.json").unwrap(),
+            ..ParseOptions::default()
+        };
+        let progress = FileProgress::default();
+        let mut dedup = Deduplicator::new(DedupMode::Off, None, 0);
+        let mut anomalies = AnomalyDetector::new(AnomalyMode::Off, None, "flat.jsonl");
+        let sites = super::parse_file(
+            "test_data/flat.jsonl",
+            "flat.jsonl",
+            &options,
+            &mut dedup,
+            &mut anomalies,
+            &progress,
+            &Cancellation::default(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(sites.len(), 3);
+        assert_eq!(sites["rust"].questions, 2);
+        assert_eq!(sites["rust"].words, 10);
+        assert_eq!(sites["rust"].tags.len(), 3);
+        assert_eq!(sites["python"].questions, 1);
+        assert_eq!(sites["python"].words, 5);
+        // line without a site belongs to the file
+        assert_eq!(sites["flat.jsonl"].questions, 1);
+        // unparseable lines are still read
+        assert_eq!(progress.lines(), 6);
+    }
+
+    #[test]
+    fn short_texts_are_skipped() {
+        let summary = parse_file("test_data/flat.jsonl", "test").unwrap();
+        assert_eq!(summary.questions, 0);
+    }
+
+    #[test]
+    fn repeated_questions_are_dropped() {
+        let options = ParseOptions {
+            dedup: DedupMode::Site,
+            ..ParseOptions::default()
+        };
+        let mut dedup = Deduplicator::new(DedupMode::Site, None, 0);
+        let mut anomalies = AnomalyDetector::new(AnomalyMode::Off, None, "a");
+        let progress = FileProgress::default();
+        let sites = super::parse_file(
+            "test_data/dedup/a.jsonl",
+            "a",
+            &options,
+            &mut dedup,
+            &mut anomalies,
+            &progress,
+            &Cancellation::default(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(sites["a"].questions, 2);
+        assert_eq!(sites["a"].words, 9);
+        assert_eq!(sites["a"].duplicates, Some(2));
+        assert_eq!(sites["a"].tags["x"].questions, 1);
+    }
+
+    #[test]
+    fn duplicates_are_not_tracked_without_dedup() {
+        let summary = parse_file("test_data/dedup/a.jsonl", "a").unwrap();
+        assert_eq!(summary.questions, 4);
+        assert_eq!(summary.duplicates, None);
+    }
+
+    #[test]
+    fn outliers_are_found_and_still_counted() {
+        let options = ParseOptions {
+            anomalies: AnomalyMode::Exclude,
+            ..ParseOptions::default()
+        };
+        let filename = "test_data/anomalies.jsonl";
+        let mut dedup = Deduplicator::new(DedupMode::Off, None, 0);
+        let (keys, distributions) = super::scan_file(
+            filename,
+            "spam",
+            &options,
+            &mut dedup,
+            false,
+            true,
+            &Cancellation::default(),
+        )
+        .unwrap();
+        assert!(keys.is_empty());
+        let cutoffs = distributions.cutoffs();
+        let mut dedup = Deduplicator::new(DedupMode::Off, None, 0);
+        let mut anomalies =
+            AnomalyDetector::new(AnomalyMode::Exclude, Some(&cutoffs), "anomalies.jsonl");
+        let progress = FileProgress::default();
+        let sites = super::parse_file(
+            filename,
+            "spam",
+            &options,
+            &mut dedup,
+            &mut anomalies,
+            &progress,
+            &Cancellation::default(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(sites["spam"].questions, 12);
+        assert_eq!(sites["spam"].tags["ads"].questions, 4);
+
+        let (found, excluded) = anomalies.into_anomalies();
+        assert_eq!(excluded["spam"].questions, 1);
+        assert_eq!(excluded["spam"].words, 400);
+        assert_eq!(excluded["spam"].tags["rust"].questions, 1);
+        assert_eq!(found.len(), 1);
+        let (site, anomaly) = &found[0];
+        assert_eq!(site, "spam");
+        assert_eq!(anomaly.line, 7);
+        assert_eq!(anomaly.words, 400);
+        // too few questions are tagged `ads` to tell its outliers apart
+        assert_eq!(anomaly.tags, vec!["rust".to_string()]);
+    }
+
+    #[test]
+    fn readers_are_parsed_like_files() {
+        let lines = concat!(
+            "{\"texts\": [\"A title\", \"a body\"], \"tags\": [\"x\"]}\n",
+            "not json\n",
+            "{\"texts\": [\"a  TITLE\", \"a body\"], \"tags\": [\"x\", \"y\"]}\n",
+        );
+        let sites = super::parse_reader(lines.as_bytes(), "s", &ParseOptions::default()).unwrap();
+        assert_eq!(sites["s"].questions, 2);
+        assert_eq!(sites["s"].words, 8);
+        assert_eq!(sites["s"].tags["x"].questions, 2);
+
+        let options = ParseOptions {
+            dedup: DedupMode::Site,
+            ..ParseOptions::default()
+        };
+        let sites = super::parse_reader(lines.as_bytes(), "s", &options).unwrap();
+        assert_eq!(sites["s"].questions, 1);
+        assert_eq!(sites["s"].duplicates, Some(1));
+    }
 }
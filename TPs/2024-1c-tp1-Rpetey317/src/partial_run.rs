@@ -0,0 +1,24 @@
+use crate::file_progress::FileProgress;
+use crate::stop_reason::StopReason;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Marks a summary as covering only part of the data, as the run stopped early
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PartialRun {
+    /// Why the run stopped
+    pub reason: StopReason,
+    /// Percentage (0 to 100) of each input file that was read and counted, by file name
+    pub completion: BTreeMap<String, f32>,
+}
+
+impl PartialRun {
+    /// Records how much of each file was read when the run stopped
+    pub fn new(reason: StopReason, files: &[FileProgress]) -> PartialRun {
+        let completion = files
+            .iter()
+            .map(|file| (file.name.clone(), (file.completion() * 100.0) as f32))
+            .collect();
+        PartialRun { reason, completion }
+    }
+}
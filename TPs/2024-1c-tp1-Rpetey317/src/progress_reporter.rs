@@ -0,0 +1,112 @@
+use crate::timing_report::megabytes_per_second;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tp1::RunProgress;
+
+/// How often the status line is refreshed on a terminal
+const TERMINAL_INTERVAL: Duration = Duration::from_millis(250);
+/// How often a status line is printed when stderr is redirected
+const LOG_INTERVAL: Duration = Duration::from_secs(2);
+/// How often the stop flag is checked
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Max n° of in-progress files listed in the status line
+const MAX_LISTED_FILES: usize = 4;
+
+/// Prints the progress of the run to stderr until `stop` is set.
+///
+/// On a terminal the status line is rewritten in place; otherwise a new line is printed
+/// every few seconds, so logs stay readable. Never writes to stdout.
+pub fn report_progress(progress: &RunProgress, stop: &AtomicBool) {
+    let terminal = io::stderr().is_terminal();
+    let interval = if terminal {
+        TERMINAL_INTERVAL
+    } else {
+        LOG_INTERVAL
+    };
+
+    let mut waited = Duration::ZERO;
+    while !stop.load(Ordering::Relaxed) {
+        thread::sleep(POLL_INTERVAL);
+        waited += POLL_INTERVAL;
+        if waited < interval {
+            continue;
+        }
+        waited = Duration::ZERO;
+        print_status(progress, terminal);
+    }
+    print_status(progress, terminal);
+    if terminal {
+        eprintln!();
+    }
+}
+
+/// Prints the name of each file to stderr as it starts being parsed, until `stop` is set
+pub fn report_started_files(progress: &RunProgress, stop: &AtomicBool) {
+    let mut reported = vec![false; progress.files().len()];
+    loop {
+        let stopping = stop.load(Ordering::Relaxed);
+        for (file, reported) in progress.files().iter().zip(reported.iter_mut()) {
+            if !*reported && file.is_started() {
+                eprintln!("Processing {}", file.name);
+                *reported = true;
+            }
+        }
+        if stopping {
+            break;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Prints a single status line
+fn print_status(progress: &RunProgress, terminal: bool) {
+    let line = status_line(progress);
+    let mut stderr = io::stderr().lock();
+    let _ = if terminal {
+        write!(stderr, "\r\x1b[2K{}", line)
+    } else {
+        writeln!(stderr, "{}", line)
+    };
+    let _ = stderr.flush();
+}
+
+/// Builds a status line like
+/// `[3.2s] 2/5 files | 123.4/456.7 MB (27%) | 1200000 lines | 88.1 MB/s | site1.jsonl 45%`
+fn status_line(progress: &RunProgress) -> String {
+    let files = progress.files();
+    let done = files.iter().filter(|f| f.is_done()).count();
+    let bytes: u64 = files.iter().map(|f| f.bytes()).sum();
+    let total: u64 = files.iter().map(|f| f.size).sum();
+    let lines: u64 = files.iter().map(|f| f.lines()).sum();
+    let elapsed = progress.elapsed().as_secs_f64();
+    let percent = if total > 0 {
+        100.0 * bytes as f64 / total as f64
+    } else {
+        0.0
+    };
+
+    let mut line = format!(
+        "[{:.1}s] {}/{} files | {:.1}/{:.1} MB ({:.0}%) | {} lines | {:.1} MB/s",
+        elapsed,
+        done,
+        files.len(),
+        bytes as f64 / 1e6,
+        total as f64 / 1e6,
+        percent,
+        lines,
+        megabytes_per_second(bytes, elapsed)
+    );
+    let active: Vec<String> = files
+        .iter()
+        .filter(|f| f.is_started() && !f.is_done())
+        .take(MAX_LISTED_FILES)
+        .map(|f| format!("{} {:.0}%", f.name, 100.0 * f.completion()))
+        .collect();
+    if !active.is_empty() {
+        line.push_str(" | ");
+        line.push_str(&active.join(", "));
+    }
+    line
+}
@@ -0,0 +1,13 @@
+use crate::deduplicator::Fingerprint;
+
+/// Data extracted from a single input line
+pub struct Question {
+    /// Word count of title and body
+    pub words: u32,
+    /// Tags of the question
+    pub tags: Vec<String>,
+    /// Site given by the line itself, if the schema has a site field
+    pub site: Option<String>,
+    /// Fingerprint of title and body, only computed if questions are deduplicated
+    pub fingerprint: Option<Fingerprint>,
+}
@@ -0,0 +1,21 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Information about a run of the summarizer, kept alongside exported results
+pub struct RunMetadata {
+    /// N° of worker threads used
+    pub n_threads: usize,
+    /// Input files that were processed
+    pub input_files: Vec<String>,
+    /// When the run started
+    pub started_at: SystemTime,
+}
+
+impl RunMetadata {
+    /// Seconds since the Unix epoch at which the run started
+    pub fn started_at_unix(&self) -> u64 {
+        self.started_at
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+}
@@ -0,0 +1,92 @@
+use crate::file_progress::FileProgress;
+use crate::input_file::InputFile;
+use std::fs;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Progress of a run: how much of each file was parsed and how long each phase took. Shared by
+/// the workers and whoever watches the run
+pub struct RunProgress {
+    started_at: Instant,
+    files: Vec<FileProgress>,
+    phases: Mutex<Vec<(String, Duration)>>,
+}
+
+impl RunProgress {
+    /// Creates the progress of a run over the given files, started at `started_at`
+    pub fn new(started_at: Instant, files: &[InputFile]) -> RunProgress {
+        let files = files
+            .iter()
+            .map(|file| {
+                let size = fs::metadata(&file.path).map(|m| m.len()).unwrap_or(0);
+                FileProgress::new(&file.name, size)
+            })
+            .collect();
+        RunProgress {
+            started_at,
+            files,
+            phases: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Progress of every file, in the order they were given
+    pub fn files(&self) -> &[FileProgress] {
+        &self.files
+    }
+
+    /// Time since the run started
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    /// Records the duration of a phase of the run
+    pub fn record_phase(&self, phase: &str, duration: Duration) {
+        if let Ok(mut phases) = self.phases.lock() {
+            phases.push((phase.to_string(), duration));
+        }
+    }
+
+    /// Runs `f`, recording how long it took as the given phase
+    pub fn time_phase<T>(&self, phase: &str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record_phase(phase, start.elapsed());
+        result
+    }
+
+    /// Name and duration of every phase recorded so far, in order
+    pub fn phases(&self) -> Vec<(String, Duration)> {
+        match self.phases.lock() {
+            Ok(phases) => phases.clone(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RunProgress;
+    use crate::input_file::InputFile;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn files_and_phases_are_recorded() {
+        let files: Vec<InputFile> = ["site1.jsonl", "missing.jsonl"]
+            .iter()
+            .map(|name| InputFile {
+                path: format!("test_data/{}", name),
+                name: name.to_string(),
+                site: name.to_string(),
+            })
+            .collect();
+        let progress = RunProgress::new(Instant::now(), &files);
+        assert!(progress.files()[0].size > 0);
+        assert_eq!(progress.files()[1].size, 0);
+
+        progress.record_phase("parse", Duration::from_secs(1));
+        assert_eq!(
+            progress.phases(),
+            vec![("parse".to_string(), Duration::from_secs(1))]
+        );
+    }
+}
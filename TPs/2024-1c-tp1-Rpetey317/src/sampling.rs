@@ -0,0 +1,87 @@
+/// Line-level sampling of the input, to get quick estimates instead of exact counts.
+///
+/// Whether a line is sampled depends only on the seed, the file it's in and its line number,
+/// so a run is reproducible regardless of how files are split among threads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sampling {
+    /// Fraction of the lines that are read, in (0, 1]
+    pub fraction: f64,
+    /// Seed of the pseudo-random choice of lines
+    pub seed: u64,
+}
+
+impl Sampling {
+    /// Creates a sampling of the given fraction of lines. Fails if it's not in (0, 1]
+    pub fn new(fraction: f64, seed: u64) -> Result<Sampling, String> {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err(format!(
+                "Invalid sample fraction: {}. Must be in (0, 1]",
+                fraction
+            ));
+        }
+        Ok(Sampling { fraction, seed })
+    }
+
+    /// Whether the given line (starting at 1) of the given source is sampled
+    pub fn keeps(&self, source: &str, line: usize) -> bool {
+        let source_seed = splitmix64(self.seed ^ fnv1a(source));
+        let x = splitmix64(source_seed.wrapping_add(line as u64));
+        // top 53 bits as a uniform number in [0, 1)
+        ((x >> 11) as f64 / (1u64 << 53) as f64) < self.fraction
+    }
+
+    /// Factor sampled counts are multiplied by to estimate the real ones
+    pub fn scale(&self) -> f64 {
+        1.0 / self.fraction
+    }
+}
+
+/// SplitMix64 finalizer, mixing every bit of the input
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// FNV-1a hash of a string, stable across platforms and Rust versions
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::Sampling;
+
+    #[test]
+    fn roughly_the_fraction_is_kept() {
+        let sampling = Sampling::new(0.2, 7).unwrap();
+        let kept = (1..=100_000)
+            .filter(|line| sampling.keeps("site.jsonl", *line))
+            .count();
+        assert!((19_000..21_000).contains(&kept), "kept {}", kept);
+        assert_eq!(sampling.scale(), 5.0);
+    }
+
+    #[test]
+    fn choice_depends_on_seed_and_source() {
+        let a = Sampling::new(0.5, 1).unwrap();
+        let b = Sampling::new(0.5, 2).unwrap();
+        let lines = |s: &Sampling, source: &str| -> Vec<bool> {
+            (1..=64).map(|line| s.keeps(source, line)).collect()
+        };
+        assert_eq!(lines(&a, "x"), lines(&a, "x"));
+        assert_ne!(lines(&a, "x"), lines(&b, "x"));
+        assert_ne!(lines(&a, "x"), lines(&a, "y"));
+        assert!((1..=64).all(|line| Sampling::new(1.0, 3).unwrap().keeps("x", line)));
+    }
+
+    #[test]
+    fn fraction_must_be_valid() {
+        assert!(Sampling::new(0.0, 0).is_err());
+        assert!(Sampling::new(1.5, 0).is_err());
+        assert!(Sampling::new(f64::NAN, 0).is_err());
+    }
+}
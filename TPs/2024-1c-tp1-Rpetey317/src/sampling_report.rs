@@ -0,0 +1,106 @@
+use crate::chattiness_estimate::ChattinessEstimate;
+use crate::full_summary::{FullSummary, N_CHATTY};
+use crate::sampling::Sampling;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// How a summary was sampled, with the chattiness estimate of every site and tag and the
+/// ranked entries whose place may be due to chance
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SamplingReport {
+    /// Fraction of the lines that were read
+    pub fraction: f64,
+    /// Seed of the choice of lines
+    pub seed: u64,
+    /// Chattiness estimate of each site
+    pub sites: BTreeMap<String, ChattinessEstimate>,
+    /// Chattiness estimate of each tag, over all sites
+    pub tags: BTreeMap<String, ChattinessEstimate>,
+    /// Chattiest sites whose interval overlaps with that of a neighbor in the ranking
+    pub uncertain_sites: Vec<String>,
+    /// Chattiest tags whose interval overlaps with that of a neighbor in the ranking
+    pub uncertain_tags: Vec<String>,
+}
+
+impl SamplingReport {
+    /// Estimates the chattiness of every site and tag of a summary of the sampled data, before
+    /// its counts are scaled
+    pub fn new(sampling: &Sampling, summary: &FullSummary) -> SamplingReport {
+        let estimate = |questions, words, words_squared| {
+            ChattinessEstimate::new(questions, words, words_squared, sampling.fraction)
+        };
+        let sites: BTreeMap<String, ChattinessEstimate> = summary
+            .sites
+            .iter()
+            .map(|(name, site)| {
+                let e = estimate(site.questions, site.words, site.words_squared);
+                (name.clone(), e)
+            })
+            .collect();
+        let tags: BTreeMap<String, ChattinessEstimate> = summary
+            .tags
+            .iter()
+            .map(|(name, tag)| {
+                let t = &tag.summary;
+                (
+                    name.clone(),
+                    estimate(t.questions, t.words, t.words_squared),
+                )
+            })
+            .collect();
+
+        SamplingReport {
+            fraction: sampling.fraction,
+            seed: sampling.seed,
+            uncertain_sites: uncertain(&sites),
+            uncertain_tags: uncertain(&tags),
+            sites,
+            tags,
+        }
+    }
+}
+
+/// Returns the entries among the `N_CHATTY` chattiest whose interval overlaps with that of the
+/// entry ranked right before or after them, in ranking order
+fn uncertain(estimates: &BTreeMap<String, ChattinessEstimate>) -> Vec<String> {
+    let mut ranked: Vec<(&String, &ChattinessEstimate)> = estimates.iter().collect();
+    ranked.sort_by(|a, b| {
+        b.1.chattiness
+            .partial_cmp(&a.1.chattiness)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.0.cmp(b.0))
+    });
+    (0..ranked.len().min(N_CHATTY))
+        .filter(|&i| {
+            let estimate = ranked[i].1;
+            let before = i > 0 && estimate.overlaps(ranked[i - 1].1);
+            let after = ranked
+                .get(i + 1)
+                .is_some_and(|next| estimate.overlaps(next.1));
+            before || after
+        })
+        .map(|i| ranked[i].0.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::uncertain;
+    use crate::chattiness_estimate::ChattinessEstimate;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn neighbors_with_overlapping_intervals_are_uncertain() {
+        // chattiness 10 +- ~2.1, 11 +- ~2.1 and 100 +- ~1.2
+        let estimates = BTreeMap::from([
+            ("a".to_string(), ChattinessEstimate::new(3, 30, 308, 0.1)),
+            ("b".to_string(), ChattinessEstimate::new(3, 33, 371, 0.1)),
+            (
+                "c".to_string(),
+                ChattinessEstimate::new(2, 200, 20_002, 0.1),
+            ),
+        ]);
+        assert_eq!(uncertain(&estimates), vec!["b", "a"]);
+    }
+}
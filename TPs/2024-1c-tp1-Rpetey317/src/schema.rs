@@ -0,0 +1,196 @@
+use crate::deduplicator;
+use crate::question::Question;
+use serde::Deserialize;
+use serde_json::Value;
+use std::fs;
+
+/// Maps the fields of an input JSON line to the data the summarizer needs.
+///
+/// Each field is a path into the JSON object, written either as a JSON pointer (`/texts/0`)
+/// or with dots (`texts.0`). The default schema is the one of the StackExchange dataset:
+/// `{"texts": [title, body], "tags": [...]}`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Schema {
+    /// Path to the question title (must be a string)
+    title: String,
+    /// Path to the question body (must be a string)
+    body: String,
+    /// Path to the tags. Either an array of strings or a single string that gets split
+    tags: String,
+    /// Separator used when tags are a single string. Splits on whitespace if not given
+    #[serde(default)]
+    tags_separator: Option<String>,
+    /// Optional path to the site name. Lines without it belong to the file's site
+    #[serde(default)]
+    site: Option<String>,
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Schema {
+            title: "/texts/0".to_string(),
+            body: "/texts/1".to_string(),
+            tags: "/tags".to_string(),
+            tags_separator: None,
+            site: None,
+        }
+    }
+}
+
+impl Schema {
+    /// Reads a schema from a JSON file, like:
+    ///
+    /// `{"title": "title", "body": "body", "tags": "tags", "tags_separator": " ", "site": "meta.site"}`
+    pub fn from_file(path: &str) -> Result<Schema, String> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let schema: Schema = serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid schema {}: {}", path, e))?;
+        Ok(schema.normalized())
+    }
+
+    /// Whether this is the StackExchange schema, which the parser handles with a faster path
+    pub fn is_default(&self) -> bool {
+        *self == Schema::default()
+    }
+
+    /// Whether lines may carry their own site name
+    pub fn has_site(&self) -> bool {
+        self.site.is_some()
+    }
+
+    /// Extracts a question from a parsed line.
+    ///
+    /// Returns None if the title or body are missing or not strings. Missing tags mean no tags.
+    /// Title and body are fingerprinted only if `with_fingerprint` is set.
+    pub fn extract(&self, value: &Value, with_fingerprint: bool) -> Option<Question> {
+        let title = value.pointer(&self.title)?.as_str()?;
+        let body = value.pointer(&self.body)?.as_str()?;
+        let words =
+            title.split_whitespace().count() as u32 + body.split_whitespace().count() as u32;
+
+        let tags = match value.pointer(&self.tags) {
+            Some(Value::Array(tags)) => tags
+                .iter()
+                .filter_map(|t| t.as_str().map(String::from))
+                .collect(),
+            Some(Value::String(tags)) => match &self.tags_separator {
+                Some(sep) => tags
+                    .split(sep.as_str())
+                    .filter(|t| !t.is_empty())
+                    .map(String::from)
+                    .collect(),
+                None => tags.split_whitespace().map(String::from).collect(),
+            },
+            _ => Vec::new(),
+        };
+
+        let site = self
+            .site
+            .as_ref()
+            .and_then(|path| value.pointer(path))
+            .and_then(|s| s.as_str())
+            .map(String::from);
+
+        let fingerprint = with_fingerprint.then(|| deduplicator::fingerprint(title, body));
+
+        Some(Question {
+            words,
+            tags,
+            site,
+            fingerprint,
+        })
+    }
+
+    /// Converts every path to a JSON pointer
+    fn normalized(self) -> Schema {
+        Schema {
+            title: to_pointer(&self.title),
+            body: to_pointer(&self.body),
+            tags: to_pointer(&self.tags),
+            tags_separator: self.tags_separator,
+            site: self.site.as_deref().map(to_pointer),
+        }
+    }
+}
+
+/// Converts a dotted path (`a.b.0`) to a JSON pointer (`/a/b/0`). JSON pointers are kept as is
+fn to_pointer(path: &str) -> String {
+    if path.is_empty() || path.starts_with('/') {
+        return path.to_string();
+    }
+    path.split('.')
+        .map(|part| format!("/{}", part.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::Schema;
+    use serde_json::json;
+
+    fn flat_schema() -> Schema {
+        serde_json::from_str::<Schema>(
+            r#"{"title": "title", "body": "body", "tags": "tags", "site": "meta.site"}"#,
+        )
+        .unwrap()
+        .normalized()
+    }
+
+    #[test]
+    fn default_schema_reads_stackexchange_lines() {
+        let line = json!({"texts": ["A title", "a body"], "tags": ["a", "b"]});
+        let question = Schema::default().extract(&line, false).unwrap();
+        assert_eq!(question.words, 4);
+        assert_eq!(question.tags, vec!["a", "b"]);
+        assert_eq!(question.site, None);
+        assert_eq!(question.fingerprint, None);
+    }
+
+    #[test]
+    fn fingerprint_is_computed_when_asked_for() {
+        let line = json!({"texts": ["A title", "a body"], "tags": []});
+        let question = Schema::default().extract(&line, true).unwrap();
+        assert_eq!(
+            question.fingerprint,
+            Some(crate::deduplicator::fingerprint("A title", "a body"))
+        );
+    }
+
+    #[test]
+    fn dotted_paths_become_pointers() {
+        let schema = flat_schema();
+        assert_eq!(schema.title, "/title");
+        assert_eq!(schema.site, Some("/meta/site".to_string()));
+        assert!(!schema.is_default());
+    }
+
+    #[test]
+    fn string_tags_are_split() {
+        let line =
+            json!({"title": "t", "body": "b b", "tags": "rust  serde", "meta": {"site": "s"}});
+        let question = flat_schema().extract(&line, false).unwrap();
+        assert_eq!(question.words, 3);
+        assert_eq!(question.tags, vec!["rust", "serde"]);
+        assert_eq!(question.site, Some("s".to_string()));
+    }
+
+    #[test]
+    fn custom_separator_is_used() {
+        let mut schema = flat_schema();
+        schema.tags_separator = Some(",".to_string());
+        let line = json!({"title": "t", "body": "b", "tags": "a,b c,"});
+        let question = schema.extract(&line, false).unwrap();
+        assert_eq!(question.tags, vec!["a", "b c"]);
+        assert_eq!(question.site, None);
+    }
+
+    #[test]
+    fn missing_texts_are_rejected() {
+        let line = json!({"title": "t", "tags": []});
+        assert!(flat_schema().extract(&line, false).is_none());
+        let line = json!({"texts": ["only title"], "tags": []});
+        assert!(Schema::default().extract(&line, false).is_none());
+    }
+}
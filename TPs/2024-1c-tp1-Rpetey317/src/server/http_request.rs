@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+/// The parts of an HTTP request the query server cares about
+#[derive(Debug)]
+pub struct HttpRequest {
+    /// Request method, like `GET`
+    pub method: String,
+    /// Percent-decoded path segments, without empty segments (`/tags/c%23` is `["tags", "c#"]`)
+    pub segments: Vec<String>,
+    /// Percent-decoded query parameters
+    pub query: HashMap<String, String>,
+}
+
+impl HttpRequest {
+    /// Parses an HTTP request line, like `GET /top?n=5 HTTP/1.1`.
+    ///
+    /// Returns None if the line is malformed
+    pub fn parse(request_line: &str) -> Option<HttpRequest> {
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next()?.to_string();
+        let target = parts.next()?;
+        if !target.starts_with('/') {
+            return None;
+        }
+
+        let (path, query_string) = target.split_once('?').unwrap_or((target, ""));
+        let segments = path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(|s| percent_decode(s, false))
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut query = HashMap::new();
+        for pair in query_string.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            query.insert(percent_decode(key, true)?, percent_decode(value, true)?);
+        }
+
+        Some(HttpRequest {
+            method,
+            segments,
+            query,
+        })
+    }
+}
+
+/// Decodes `%XX` escapes (and `+` as a space in query strings).
+///
+/// Returns None on malformed escapes or if the result is not UTF-8
+fn percent_decode(s: &str, plus_is_space: bool) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = s.get(i + 1..i + 3)?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            b'+' if plus_is_space => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::HttpRequest;
+
+    #[test]
+    fn parses_path_and_query() {
+        let request =
+            HttpRequest::parse("GET /top?metric=chattiness&n=20&min_questions=5 HTTP/1.1").unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.segments, vec!["top"]);
+        assert_eq!(request.query["metric"], "chattiness");
+        assert_eq!(request.query["n"], "20");
+        assert_eq!(request.query["min_questions"], "5");
+    }
+
+    #[test]
+    fn decodes_escapes() {
+        let request = HttpRequest::parse("GET /tags/c%23/ HTTP/1.1").unwrap();
+        assert_eq!(request.segments, vec!["tags", "c#"]);
+        let request = HttpRequest::parse("GET /sites/a+b?q=a+b HTTP/1.1").unwrap();
+        assert_eq!(request.segments, vec!["sites", "a+b"]);
+        assert_eq!(request.query["q"], "a b");
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        assert!(HttpRequest::parse("").is_none());
+        assert!(HttpRequest::parse("GET").is_none());
+        assert!(HttpRequest::parse("GET top HTTP/1.1").is_none());
+        assert!(HttpRequest::parse("GET /tags/%zz HTTP/1.1").is_none());
+    }
+}
@@ -0,0 +1,53 @@
+use serde::Serialize;
+use serde_json::json;
+use std::io::{self, Write};
+
+/// A JSON response of the query server
+pub struct HttpResponse {
+    /// HTTP status code
+    pub status: u16,
+    /// JSON body
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// A `200 OK` response with the given value as body
+    pub fn ok<T: Serialize>(value: &T) -> HttpResponse {
+        match serde_json::to_string(value) {
+            Ok(body) => HttpResponse { status: 200, body },
+            Err(e) => HttpResponse::error(500, &format!("Failed to serialize response: {}", e)),
+        }
+    }
+
+    /// An error response with a `{"error": message}` body
+    pub fn error(status: u16, message: &str) -> HttpResponse {
+        HttpResponse {
+            status,
+            body: json!({ "error": message }).to_string(),
+        }
+    }
+
+    /// Writes the response (status line, headers and body) to the given stream
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        write!(
+            out,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason(self.status),
+            self.body.len(),
+            self.body
+        )?;
+        out.flush()
+    }
+}
+
+/// Reason phrase of the status codes used by the server
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    }
+}
@@ -0,0 +1,4 @@
+pub mod http_request;
+pub mod http_response;
+pub mod query_server;
+pub mod routes;
@@ -0,0 +1,95 @@
+use crate::server::http_request::HttpRequest;
+use crate::server::http_response::HttpResponse;
+use crate::server::routes;
+use std::io::{self, BufRead, BufReader, Read};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tp1::FullSummary;
+
+/// Maximum size of a request head (request line and headers)
+const MAX_REQUEST_SIZE: u64 = 16 * 1024;
+
+/// Time a client has to send its request, or to take its response, before it's dropped
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Accepted connections waiting for a worker per worker. Once full, accepting blocks
+const QUEUED_PER_WORKER: usize = 4;
+
+/// Answers HTTP queries about the summary on `127.0.0.1:port`, until the process is killed.
+///
+/// The summary is read-only, so it's shared by a fixed pool of `workers` threads that answer
+/// the accepted connections one at a time. Clients that are too slow to send their request
+/// are dropped after `IO_TIMEOUT`, so they can't hold a worker forever.
+///
+/// May fail if the port cannot be bound
+pub fn serve(summary: FullSummary, port: u16, workers: usize) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+    let summary = Arc::new(summary);
+
+    let workers = workers.max(1);
+    let (sender, receiver) = mpsc::sync_channel(workers * QUEUED_PER_WORKER);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..workers {
+        let summary = Arc::clone(&summary);
+        let receiver = Arc::clone(&receiver);
+        thread::spawn(move || work(&summary, &receiver));
+    }
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        if sender.send(stream).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Answers the connections sent to the pool until its sender is dropped
+fn work(summary: &FullSummary, receiver: &Mutex<Receiver<TcpStream>>) {
+    loop {
+        let stream = match receiver.lock().map(|receiver| receiver.recv()) {
+            Ok(Ok(stream)) => stream,
+            _ => return,
+        };
+        if let Err(e) = handle_connection(summary, stream) {
+            eprintln!("Failed to answer request: {}", e);
+        }
+    }
+}
+
+/// Reads a single request from the stream and writes its response
+fn handle_connection(summary: &FullSummary, mut stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut reader = BufReader::new((&stream).take(MAX_REQUEST_SIZE));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // skip headers, requests have no meaningful body
+    let mut header = String::new();
+    loop {
+        header.clear();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let response = match HttpRequest::parse(&request_line) {
+        Some(request) => {
+            eprintln!("{} /{}", request.method, request.segments.join("/"));
+            routes::handle(summary, &request)
+        }
+        None => HttpResponse::error(400, "Malformed request"),
+    };
+    response.write_to(&mut stream)
+}
@@ -0,0 +1,289 @@
+use crate::server::http_request::HttpRequest;
+use crate::server::http_response::HttpResponse;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use tp1::{FullSummary, TagSiteShare, TagSummary, N_CHATTY};
+
+/// Counts of a site or tag, as listed by `/sites` and `/top`
+#[derive(Serialize)]
+struct Entry<'a> {
+    name: &'a str,
+    questions: u64,
+    words: u64,
+    chattiness: f32,
+}
+
+/// A site with its tags, as shown by `/sites/{name}`
+#[derive(Serialize)]
+struct SiteDetail<'a> {
+    name: &'a str,
+    questions: u64,
+    words: u64,
+    chattiness: f32,
+    chatty_tags: Vec<String>,
+    tags: &'a HashMap<String, TagSummary>,
+}
+
+/// A tag with its counts in each site, as shown by `/tags/{name}`
+#[derive(Serialize)]
+struct TagDetail<'a> {
+    name: &'a str,
+    questions: u64,
+    words: u64,
+    chattiness: f32,
+    sites: Vec<TagInSite>,
+}
+
+/// Counts of a tag in one site, as listed by `/tags/{name}`
+#[derive(Serialize)]
+struct TagInSite {
+    site: String,
+    chattiness: f32,
+    #[serde(flatten)]
+    counts: TagSiteShare,
+}
+
+/// Answers a request over the given summary.
+///
+/// Routes:
+/// - `/sites`: every site with its counts
+/// - `/sites/{name}`: a site with its tags and chattiest tags
+/// - `/tags/{name}`: a tag with its counts in each site
+/// - `/top?metric=chattiness&n=20&min_questions=5&kind=tags`: ranking of tags or sites by
+///   `chattiness`, `questions` or `words`, ignoring entries with fewer questions than asked
+/// - `/totals`: the global rankings
+pub fn handle(summary: &FullSummary, request: &HttpRequest) -> HttpResponse {
+    if request.method != "GET" {
+        return HttpResponse::error(405, "Only GET is supported");
+    }
+    let segments: Vec<&str> = request.segments.iter().map(String::as_str).collect();
+    match segments.as_slice() {
+        ["sites"] => sites(summary),
+        ["sites", name] => site(summary, name),
+        ["tags", name] => tag(summary, name),
+        ["top"] => top(summary, request),
+        ["totals"] => HttpResponse::ok(&summary.totals),
+        _ => HttpResponse::error(404, "Unknown route"),
+    }
+}
+
+/// `/sites`
+fn sites(summary: &FullSummary) -> HttpResponse {
+    let mut entries: Vec<Entry> = summary
+        .sites
+        .iter()
+        .map(|(name, site)| Entry {
+            name,
+            questions: site.questions,
+            words: site.words,
+            chattiness: site.chattiness(),
+        })
+        .collect();
+    entries.sort_by(|a, b| a.name.cmp(b.name));
+    HttpResponse::ok(&entries)
+}
+
+/// `/sites/{name}`
+fn site(summary: &FullSummary, name: &str) -> HttpResponse {
+    let site = match summary.sites.get(name) {
+        Some(site) => site,
+        None => return HttpResponse::error(404, &format!("Unknown site: {}", name)),
+    };
+    let chatty_tags: Vec<String> = site
+        .n_chattiest(N_CHATTY)
+        .into_iter()
+        .map(|tag| tag.name)
+        .collect();
+    HttpResponse::ok(&SiteDetail {
+        name,
+        questions: site.questions,
+        words: site.words,
+        chattiness: site.chattiness(),
+        chatty_tags,
+        tags: &site.tags,
+    })
+}
+
+/// `/tags/{name}`
+fn tag(summary: &FullSummary, name: &str) -> HttpResponse {
+    let tag = match summary.tags.get(name) {
+        Some(tag) => &tag.summary,
+        None => return HttpResponse::error(404, &format!("Unknown tag: {}", name)),
+    };
+    let sites: Vec<TagInSite> = summary
+        .site_shares(name)
+        .into_iter()
+        .map(|(site, counts)| TagInSite {
+            chattiness: counts.chattiness(),
+            site,
+            counts,
+        })
+        .collect();
+    HttpResponse::ok(&TagDetail {
+        name,
+        questions: tag.questions,
+        words: tag.words,
+        chattiness: tag.chattiness(),
+        sites,
+    })
+}
+
+/// `/top`
+fn top(summary: &FullSummary, request: &HttpRequest) -> HttpResponse {
+    let n = match parse_param(request, "n", N_CHATTY) {
+        Ok(n) => n,
+        Err(e) => return e,
+    };
+    let min_questions = match parse_param(request, "min_questions", 0u64) {
+        Ok(m) => m,
+        Err(e) => return e,
+    };
+
+    let mut entries: Vec<Entry> = match request.query.get("kind").map(String::as_str) {
+        None | Some("tags") => summary
+            .tags
+            .iter()
+            .map(|(name, tag)| Entry {
+                name,
+                questions: tag.summary.questions,
+                words: tag.summary.words,
+                chattiness: tag.summary.chattiness(),
+            })
+            .collect(),
+        Some("sites") => summary
+            .sites
+            .iter()
+            .map(|(name, site)| Entry {
+                name,
+                questions: site.questions,
+                words: site.words,
+                chattiness: site.chattiness(),
+            })
+            .collect(),
+        Some(kind) => {
+            return HttpResponse::error(400, &format!("Unknown kind: {}. Use tags or sites", kind))
+        }
+    };
+    entries.retain(|entry| entry.questions >= min_questions);
+
+    let metric: fn(&Entry) -> f64 = match request.query.get("metric").map(String::as_str) {
+        None | Some("chattiness") => |e| e.chattiness as f64,
+        Some("questions") => |e| e.questions as f64,
+        Some("words") => |e| e.words as f64,
+        Some(metric) => {
+            return HttpResponse::error(
+                400,
+                &format!(
+                    "Unknown metric: {}. Use chattiness, questions or words",
+                    metric
+                ),
+            )
+        }
+    };
+    entries.sort_by(|a, b| {
+        metric(b)
+            .partial_cmp(&metric(a))
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.name.cmp(b.name))
+    });
+    entries.truncate(n);
+    HttpResponse::ok(&entries)
+}
+
+/// Parses an optional numeric query parameter, returning a `400` response if it's invalid
+fn parse_param<T: std::str::FromStr>(
+    request: &HttpRequest,
+    name: &str,
+    default: T,
+) -> Result<T, HttpResponse> {
+    match request.query.get(name) {
+        None => Ok(default),
+        Some(value) => value.parse::<T>().map_err(|_| {
+            HttpResponse::error(400, &format!("Invalid value for {}: {}", name, value))
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::handle;
+    use crate::server::http_request::HttpRequest;
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use tp1::{FullSummary, SiteSummary, TagSummary};
+
+    fn test_summary() -> FullSummary {
+        let site1 = SiteSummary::new(
+            "site1",
+            vec![
+                TagSummary::new("rust".to_string(), 3, 300),
+                TagSummary::new("c#".to_string(), 10, 50),
+            ],
+        );
+        let site2 = SiteSummary::new("site2", vec![TagSummary::new("rust".to_string(), 1, 20)]);
+        let total = site1.combine(&site2).unwrap();
+        let sites = HashMap::from([("site1".to_string(), site1), ("site2".to_string(), site2)]);
+        FullSummary::new(1, sites, &total)
+    }
+
+    fn get(target: &str) -> (u16, Value) {
+        let request = HttpRequest::parse(&format!("GET {} HTTP/1.1", target)).unwrap();
+        let response = handle(&test_summary(), &request);
+        (
+            response.status,
+            serde_json::from_str(&response.body).unwrap(),
+        )
+    }
+
+    #[test]
+    fn lists_sites() {
+        let (status, body) = get("/sites");
+        assert_eq!(status, 200);
+        assert_eq!(body[0]["name"], "site1");
+        assert_eq!(body[1]["questions"], 1);
+    }
+
+    #[test]
+    fn shows_a_site() {
+        let (status, body) = get("/sites/site1");
+        assert_eq!(status, 200);
+        assert_eq!(body["questions"], 13);
+        assert_eq!(body["chatty_tags"][0], "rust");
+        assert_eq!(get("/sites/nope").0, 404);
+    }
+
+    #[test]
+    fn breaks_tags_down_by_site() {
+        let (status, body) = get("/tags/rust");
+        assert_eq!(status, 200);
+        assert_eq!(body["questions"], 4);
+        assert_eq!(body["sites"][0]["site"], "site1");
+        assert_eq!(body["sites"][0]["question_share"], 0.75);
+        assert!(body["sites"][0].get("share").is_none());
+        assert_eq!(body["sites"][1]["words"], 20);
+        assert_eq!(get("/tags/c%23").1["words"], 50);
+    }
+
+    #[test]
+    fn ranks_with_filters() {
+        let (_, body) = get("/top?metric=chattiness&n=1");
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["name"], "rust");
+        let (_, body) = get("/top?metric=questions&min_questions=5");
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["name"], "c#");
+        let (_, body) = get("/top?kind=sites&metric=words");
+        assert_eq!(body[0]["name"], "site1");
+    }
+
+    #[test]
+    fn rejects_bad_requests() {
+        assert_eq!(get("/top?n=many").0, 400);
+        assert_eq!(get("/top?metric=length").0, 400);
+        assert_eq!(get("/top?kind=users").0, 400);
+        assert_eq!(get("/nothing").0, 404);
+        let request = HttpRequest::parse("POST /sites HTTP/1.1").unwrap();
+        assert_eq!(handle(&test_summary(), &request).status, 405);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::count_overflow::CountOverflow;
+use crate::input_file::InputFile;
+use crate::site_summary::SiteSummary;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Puts together the summaries of the files of each site as they are parsed, to hand out each
+/// site as soon as every one of its files is done, before the rest finish.
+///
+/// Only works if every question of a file belongs to the file's site, that is, if the schema
+/// has no site field.
+pub(crate) struct SiteAssembler {
+    /// Files left and summary so far of each site not yet complete
+    pending: Mutex<HashMap<String, (usize, SiteSummary)>>,
+}
+
+impl SiteAssembler {
+    /// Creates an assembler for the sites of the given files
+    pub(crate) fn new(files: &[InputFile]) -> SiteAssembler {
+        let mut pending: HashMap<String, (usize, SiteSummary)> = HashMap::new();
+        for file in files {
+            pending
+                .entry(file.site.clone())
+                .or_insert_with(|| (0, SiteSummary::new(&file.site, Vec::new())))
+                .0 += 1;
+        }
+        SiteAssembler {
+            pending: Mutex::new(pending),
+        }
+    }
+
+    /// Adds the summary of one of the files of a site. Returns the whole site if that was its
+    /// last file. Fails if a count of the site overflows
+    pub(crate) fn add(&self, site: &SiteSummary) -> Result<Option<SiteSummary>, CountOverflow> {
+        let mut pending = match self.pending.lock() {
+            Ok(pending) => pending,
+            Err(_) => return Ok(None),
+        };
+        let (left, summary) = match pending.get_mut(&site.name) {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        summary.merge(site)?;
+        *left -= 1;
+        if *left > 0 {
+            return Ok(None);
+        }
+        Ok(pending.remove(&site.name).map(|(_, summary)| summary))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SiteAssembler;
+    use crate::input_file::InputFile;
+    use crate::site_summary::SiteSummary;
+
+    fn file(path: &str, site: &str) -> InputFile {
+        InputFile {
+            path: path.to_string(),
+            name: path.to_string(),
+            site: site.to_string(),
+        }
+    }
+
+    fn site(name: &str, questions: u32) -> SiteSummary {
+        let mut site = SiteSummary::new(name, Vec::new());
+        for _ in 0..questions {
+            site.add_question(5).unwrap();
+        }
+        site
+    }
+
+    #[test]
+    fn sites_are_complete_after_their_last_file() {
+        let assembler = SiteAssembler::new(&[file("a/1", "a"), file("b/1", "b"), file("a/2", "a")]);
+        assert!(assembler.add(&site("a", 1)).unwrap().is_none());
+        assert_eq!(assembler.add(&site("b", 2)).unwrap().unwrap().questions, 2);
+        let a = assembler.add(&site("a", 3)).unwrap().unwrap();
+        assert_eq!(a.name, "a");
+        assert_eq!(a.questions, 4);
+        assert!(assembler.add(&site("a", 1)).unwrap().is_none());
+    }
+}
@@ -0,0 +1,127 @@
+use std::fs;
+use std::path::Path;
+
+/// Placeholders that may appear in a site name pattern
+const PLACEHOLDERS: [&str; 4] = ["{file}", "{stem}", "{parent}", "{path}"];
+
+/// How the name of the site of each input file is derived from its path.
+///
+/// A template where `{file}` is the file name, `{stem}` the file name without its extension,
+/// `{parent}` the name of the directory it's in and `{path}` its path relative to the data
+/// directory (or as listed, if read from stdin). Defaults to `{file}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SiteNamePattern {
+    template: String,
+}
+
+impl Default for SiteNamePattern {
+    fn default() -> Self {
+        SiteNamePattern {
+            template: "{file}".to_string(),
+        }
+    }
+}
+
+impl SiteNamePattern {
+    /// Parses a template. Fails if it has an unknown placeholder or none at all
+    pub fn parse(template: &str) -> Result<SiteNamePattern, String> {
+        let mut rest = template;
+        let mut found = false;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}').map(|end| start + end + 1);
+            match end.map(|end| &rest[start..end]) {
+                Some(placeholder) if PLACEHOLDERS.contains(&placeholder) => found = true,
+                _ => {
+                    return Err(format!(
+                        "Invalid site name pattern: '{}'. Placeholders are {}",
+                        template,
+                        PLACEHOLDERS.join(", ")
+                    ))
+                }
+            }
+            rest = &rest[end.unwrap_or(rest.len())..];
+        }
+        if !found {
+            return Err(format!(
+                "Site name pattern '{}' must use one of {}",
+                template,
+                PLACEHOLDERS.join(", ")
+            ));
+        }
+        Ok(SiteNamePattern {
+            template: template.to_string(),
+        })
+    }
+
+    /// Returns the site name of the file at `path`, listed as `relative`
+    pub fn site_name(&self, path: &str, relative: &str) -> String {
+        let file_path = Path::new(path);
+        let file = file_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let stem = file_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut name = self
+            .template
+            .replace("{file}", &file)
+            .replace("{stem}", &stem)
+            .replace("{path}", relative);
+        if name.contains("{parent}") {
+            name = name.replace("{parent}", &parent_name(file_path));
+        }
+        name
+    }
+}
+
+/// Name of the directory the file is in, resolving relative paths if needed
+fn parent_name(path: &Path) -> String {
+    let parent = match path.parent() {
+        Some(parent) if parent.file_name().is_some() => parent.to_path_buf(),
+        _ => match fs::canonicalize(path) {
+            Ok(absolute) => match absolute.parent() {
+                Some(parent) => parent.to_path_buf(),
+                None => return String::new(),
+            },
+            Err(_) => return String::new(),
+        },
+    };
+    parent
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::SiteNamePattern;
+
+    #[test]
+    fn placeholders_are_replaced() {
+        let path = "data/stackoverflow/2020.jsonl";
+        let relative = "stackoverflow/2020.jsonl";
+        let name = |template: &str| {
+            SiteNamePattern::parse(template)
+                .unwrap()
+                .site_name(path, relative)
+        };
+        assert_eq!(name("{file}"), "2020.jsonl");
+        assert_eq!(name("{stem}"), "2020");
+        assert_eq!(name("{parent}"), "stackoverflow");
+        assert_eq!(name("{parent}-{stem}"), "stackoverflow-2020");
+        assert_eq!(name("{path}"), relative);
+        assert_eq!(
+            SiteNamePattern::default().site_name(path, relative),
+            "2020.jsonl"
+        );
+    }
+
+    #[test]
+    fn unknown_or_missing_placeholders_fail() {
+        assert!(SiteNamePattern::parse("{dir}").is_err());
+        assert!(SiteNamePattern::parse("{stem").is_err());
+        assert!(SiteNamePattern::parse("site").is_err());
+    }
+}
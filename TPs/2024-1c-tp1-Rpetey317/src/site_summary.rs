@@ -1,23 +1,35 @@
-use crate::tag_summary::TagSummary;
-use serde::Serialize;
+use crate::count_overflow::{self, CountOverflow};
+use crate::tag_summary::{scale_count, TagSummary};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
 /// A struct that contains aggregated tag and question data for a site
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct SiteSummary {
     /// Site name
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub name: String,
     /// N° of questions in this site
-    pub questions: u32,
+    pub questions: u64,
     /// Total word count for every question in this site
-    pub words: u32,
+    pub words: u64,
     /// Summaries of each tag in this site
     pub tags: HashMap<String, TagSummary>,
+    /// Sum of the squared word count of every question in this site, used to estimate the
+    /// variance of its chattiness when sampling. Not serialized
+    #[serde(skip)]
+    pub words_squared: u128,
+    /// N° of repeated questions dropped from this site. Only present if deduplicating
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub duplicates: Option<u64>,
 }
 
 impl SiteSummary {
     /// Creates a new SiteSummary with the given site name and adds given tags.
+    ///
+    /// # Panics
+    /// If the counts of the tags add up to more than a count can hold
     pub fn new(site: &str, tags: Vec<TagSummary>) -> SiteSummary {
         // TODO: sacarle el vec a esta función
         let mut summary = SiteSummary {
@@ -25,12 +37,21 @@ impl SiteSummary {
             questions: 0,
             words: 0,
             tags: HashMap::new(),
+            words_squared: 0,
+            duplicates: None,
         };
 
         for tag in tags {
-            summary.words += tag.words;
-            summary.questions += tag.questions;
-            summary.add_tag(tag);
+            let counted = SiteSummary {
+                questions: tag.questions,
+                words: tag.words,
+                words_squared: tag.words_squared,
+                ..SiteSummary::empty()
+            };
+            summary
+                .merge(&counted)
+                .and_then(|_| summary.add_tag(tag))
+                .unwrap_or_else(|e| panic!("Failed to create site {}: {}", site, e));
         }
 
         summary
@@ -43,43 +64,95 @@ impl SiteSummary {
             questions: 0,
             words: 0,
             tags: HashMap::new(),
+            words_squared: 0,
+            duplicates: None,
         }
     }
 
-    /// Combines two summaries, aggregating word and question counts and combining tags accordingly
-    pub fn combine(&self, other: &SiteSummary) -> SiteSummary {
-        let mut tags = self.tags.clone();
+    /// Combines two summaries, aggregating word and question counts and combining tags accordingly.
+    /// Fails if a count overflows
+    pub fn combine(&self, other: &SiteSummary) -> Result<SiteSummary, CountOverflow> {
+        let mut combined = self.clone();
+        combined.merge(other)?;
+        combined.name = "Combined".to_string();
+        Ok(combined)
+    }
+
+    /// Adds the word and question counts and the tags of another summary to this one, keeping
+    /// this one's name. Useful to put together summaries of the same site read separately.
+    ///
+    /// Fails if a count overflows, leaving this summary partially merged
+    pub fn merge(&mut self, other: &SiteSummary) -> Result<(), CountOverflow> {
         for (tag_name, tag) in &other.tags {
-            if let Some(my_tag) = tags.get_mut(tag_name) {
-                *my_tag = my_tag.combine(tag);
+            if let Some(my_tag) = self.tags.get_mut(tag_name) {
+                my_tag.merge(tag)?;
             } else {
-                tags.insert(tag_name.to_string(), tag.clone());
+                self.tags.insert(tag_name.to_string(), tag.clone());
             }
         }
-        SiteSummary {
-            name: "Combined".to_string(),
-            questions: self.questions + other.questions,
-            words: self.words + other.words,
-            tags,
-        }
+        self.questions = self.add(self.questions, other.questions, "questions")?;
+        self.words = self.add(self.words, other.words, "words")?;
+        self.words_squared =
+            count_overflow::add_squares(self.words_squared, other.words_squared, &self.name)?;
+        self.duplicates = match (self.duplicates, other.duplicates) {
+            (None, None) => None,
+            (a, b) => Some(self.add(a.unwrap_or(0), b.unwrap_or(0), "duplicates")?),
+        };
+        Ok(())
     }
 
     /// Adds a tag to the summary, combining it with an existing tag if it already exists.
     ///
     /// Does NOT update question and word counts, that has to be done using `add_question`.
-    /// This is because a question can have multiple tags, so it could count the same question multiple times
-    pub fn add_tag(&mut self, tag: TagSummary) {
+    /// This is because a question can have multiple tags, so it could count the same question multiple times.
+    /// Fails if a count of the tag overflows
+    pub fn add_tag(&mut self, tag: TagSummary) -> Result<(), CountOverflow> {
         if let Some(existing_tag) = self.tags.get_mut(&tag.name) {
-            *existing_tag = existing_tag.combine(&tag);
+            existing_tag.merge(&tag)?;
         } else {
             self.tags.insert(tag.name.to_string(), tag);
         }
+        Ok(())
+    }
+
+    /// Updates question and word counts of the site according to a new question of the given wordcount.
+    /// Fails, leaving the counts as they were, if one of them overflows
+    pub fn add_question(&mut self, word_count: u32) -> Result<(), CountOverflow> {
+        let questions = self.add(self.questions, 1, "questions")?;
+        let words = self.add(self.words, word_count as u64, "words")?;
+        let square = word_count as u128 * word_count as u128;
+        self.words_squared = count_overflow::add_squares(self.words_squared, square, &self.name)?;
+        self.questions = questions;
+        self.words = words;
+        Ok(())
+    }
+
+    /// Multiplies the word and question counts of the site and of its tags by the given factor,
+    /// rounding them. Used to estimate real counts from a sample
+    pub fn scale(&mut self, factor: f64) {
+        self.questions = scale_count(self.questions, factor);
+        self.words = scale_count(self.words, factor);
+        for tag in self.tags.values_mut() {
+            tag.scale(factor);
+        }
+    }
+
+    /// Accounts for a repeated question that was dropped instead of counted.
+    /// Fails if the count of duplicates overflows
+    pub fn add_duplicate(&mut self) -> Result<(), CountOverflow> {
+        let duplicates = self.add(self.duplicates.unwrap_or(0), 1, "duplicates")?;
+        self.duplicates = Some(duplicates);
+        Ok(())
     }
 
-    /// Updates question and word counts of the site according to a new question of the given wordcount
-    pub fn add_question(&mut self, word_count: u32) {
-        self.questions += 1;
-        self.words += word_count;
+    /// Starts reporting dropped duplicates, even if there are none
+    pub fn track_duplicates(&mut self) {
+        self.duplicates.get_or_insert(0);
+    }
+
+    /// Adds two counts of this site
+    fn add(&self, a: u64, b: u64, count: &str) -> Result<u64, CountOverflow> {
+        count_overflow::add(a, b, count, &self.name)
     }
 
     /// Returns chattiness score (word count / question count).
@@ -91,11 +164,15 @@ impl SiteSummary {
         self.words as f32 / self.questions as f32
     }
 
-    /// Returns the n tags with the highest chattiness score in the site
+    /// Returns the n tags with the highest chattiness score in the site.
+    /// Ties are broken by tag name, so the result doesn't depend on map iteration order
     pub fn n_chattiest(&self, n: usize) -> Vec<TagSummary> {
         let mut tags: Vec<TagSummary> = self.tags.values().cloned().collect();
-        tags.sort_by(|a, b| a.partial_cmp(b).unwrap());
-        tags.reverse();
+        tags.sort_by(|a, b| {
+            b.partial_cmp(a)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.name.cmp(&b.name))
+        });
         tags.truncate(n);
         tags
     }
@@ -109,14 +186,18 @@ impl Clone for SiteSummary {
             questions: self.questions,
             words: self.words,
             tags: self.tags.clone(),
+            words_squared: self.words_squared,
+            duplicates: self.duplicates,
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::unnecessary_to_owned)]
 mod test {
     use super::super::tag_summary::TagSummary;
     use super::SiteSummary;
+    use crate::count_overflow::CountOverflow;
 
     fn test_site1() -> SiteSummary {
         let tag1 = TagSummary::new("tag1".to_string(), 10, 100);
@@ -151,7 +232,7 @@ mod test {
     fn can_combine_sites() {
         let site1 = test_site1();
         let site2 = test_site2();
-        let combined = site1.combine(&site2);
+        let combined = site1.combine(&site2).unwrap();
         assert_eq!(combined.questions, 80);
         assert_eq!(combined.words, 550);
         assert_eq!(combined.tags.len(), 4);
@@ -161,18 +242,64 @@ mod test {
     fn overlapping_tags_get_combined() {
         let site1 = test_site1();
         let site3 = test_site3();
-        let combined = site1.combine(&site3);
+        let combined = site1.combine(&site3).unwrap();
         assert_eq!(combined.questions, 70);
         assert_eq!(combined.words, 600);
         assert_eq!(combined.tags.len(), 3);
     }
 
+    #[test]
+    fn merge_keeps_the_name() {
+        let mut site = test_site1();
+        site.merge(&test_site3()).unwrap();
+        assert_eq!(site.name, "site1");
+        assert_eq!(site.questions, 70);
+        assert_eq!(site.tags["tag2"].questions, 30);
+        assert_eq!(site.tags["tag2"].name, "tag2");
+    }
+
+    #[test]
+    fn duplicates_are_combined() {
+        let mut site1 = test_site1();
+        site1.add_duplicate().unwrap();
+        site1.add_duplicate().unwrap();
+        let mut site2 = test_site2();
+        assert_eq!(site1.combine(&site2).unwrap().duplicates, Some(2));
+        assert_eq!(site2.combine(&test_site3()).unwrap().duplicates, None);
+        site2.track_duplicates();
+        assert_eq!(site2.combine(&test_site3()).unwrap().duplicates, Some(0));
+    }
+
     #[test]
     fn n_chattiest_works() {
-        let site = test_site1().combine(&test_site2());
+        let site = test_site1().combine(&test_site2()).unwrap();
         let chattiest = site.n_chattiest(2);
         assert_eq!(chattiest.len(), 2);
         assert_eq!(chattiest[0].name, "tag1");
         assert_eq!(chattiest[1].name, "tag2");
     }
+
+    #[test]
+    fn overflowing_counts_are_errors() {
+        let mut site = SiteSummary::new("site", Vec::new());
+        site.words = u64::MAX - 1;
+        site.add_question(1).unwrap();
+        let overflow = site.add_question(1).unwrap_err();
+        assert_eq!(overflow, CountOverflow::new("words", "site"));
+        assert_eq!(site.questions, 1);
+        assert_eq!(site.words, u64::MAX);
+        assert!(site.combine(&test_site1()).is_err());
+    }
+
+    #[test]
+    fn n_chattiest_breaks_ties_by_name() {
+        let tags = vec![
+            TagSummary::new("b".to_string(), 1, 5),
+            TagSummary::new("c".to_string(), 2, 10),
+            TagSummary::new("a".to_string(), 3, 15),
+        ];
+        let site = SiteSummary::new("site", tags);
+        let names: Vec<String> = site.n_chattiest(3).into_iter().map(|t| t.name).collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+    }
 }
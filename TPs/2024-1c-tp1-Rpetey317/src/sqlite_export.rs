@@ -0,0 +1,346 @@
+use crate::distribution::Distribution;
+use crate::run_metadata::RunMetadata;
+use rusqlite::{params, Connection};
+use tp1::FullSummary;
+
+/// Tables of the exported database, in the order they are dropped when overwriting
+const TABLES: [&str; 5] = ["distributions", "metadata", "site_tags", "sites", "tags"];
+
+/// Tables and indexes of the exported database
+const SCHEMA: &str = "
+    CREATE TABLE metadata (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE sites (
+        name TEXT PRIMARY KEY,
+        questions INTEGER NOT NULL,
+        words INTEGER NOT NULL,
+        chattiness REAL NOT NULL,
+        tag_count INTEGER NOT NULL,
+        chatty_rank INTEGER
+    );
+    CREATE TABLE tags (
+        name TEXT PRIMARY KEY,
+        questions INTEGER NOT NULL,
+        words INTEGER NOT NULL,
+        chattiness REAL NOT NULL,
+        site_count INTEGER NOT NULL,
+        chatty_rank INTEGER
+    );
+    CREATE TABLE site_tags (
+        site TEXT NOT NULL REFERENCES sites(name),
+        tag TEXT NOT NULL REFERENCES tags(name),
+        questions INTEGER NOT NULL,
+        words INTEGER NOT NULL,
+        chattiness REAL NOT NULL,
+        share_of_tag_questions REAL NOT NULL,
+        PRIMARY KEY (site, tag)
+    );
+    CREATE TABLE distributions (
+        entity TEXT NOT NULL,
+        metric TEXT NOT NULL,
+        count INTEGER NOT NULL,
+        min REAL NOT NULL,
+        max REAL NOT NULL,
+        mean REAL NOT NULL,
+        median REAL NOT NULL,
+        p90 REAL NOT NULL,
+        p99 REAL NOT NULL,
+        PRIMARY KEY (entity, metric)
+    );
+
+    CREATE INDEX sites_chattiness ON sites(chattiness);
+    CREATE INDEX tags_chattiness ON tags(chattiness);
+    CREATE INDEX tags_site_count ON tags(site_count);
+    CREATE INDEX site_tags_tag ON site_tags(tag);
+    CREATE INDEX site_tags_chattiness ON site_tags(chattiness);
+";
+
+/// Writes the summary to a SQLite database at `path`, creating it if it doesn't exist.
+///
+/// Creates the `sites`, `tags` and `site_tags` tables, plus a `metadata` key/value table with
+/// the thread count, input files (as a JSON array) and start time of the run, and a
+/// `distributions` table with the count, min, max, mean, median, 90th and 99th percentiles of
+/// the questions, words and chattiness of the sites and of the tags (one row per `entity` and
+/// `metric`). `chatty_rank` is the 1-based position in the global chattiness rankings (NULL if not ranked).
+/// Fails if the database already has any of these tables, unless `overwrite` is set, in which
+/// case they are replaced.
+pub fn export(
+    summary: &FullSummary,
+    path: &str,
+    metadata: &RunMetadata,
+    overwrite: bool,
+) -> Result<(), String> {
+    let mut conn = Connection::open(path).map_err(|e| e.to_string())?;
+    let existing = existing_tables(&conn).map_err(|e| e.to_string())?;
+    if !existing.is_empty() && !overwrite {
+        return Err(format!(
+            "it already has the tables {}. Use --overwrite to replace them",
+            existing.join(", ")
+        ));
+    }
+    write_summary(&mut conn, summary, metadata).map_err(|e| e.to_string())
+}
+
+/// Names of the exported tables that are already in the database
+fn existing_tables(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type = 'table'")?;
+    let names = stmt.query_map([], |r| r.get::<_, String>(0))?;
+    let mut existing = Vec::new();
+    for name in names {
+        let name = name?;
+        if TABLES.contains(&name.as_str()) {
+            existing.push(name);
+        }
+    }
+    Ok(existing)
+}
+
+/// Writes every table in a single transaction, replacing the ones that already exist
+fn write_summary(
+    conn: &mut Connection,
+    summary: &FullSummary,
+    metadata: &RunMetadata,
+) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    for table in TABLES {
+        tx.execute_batch(&format!("DROP TABLE IF EXISTS {};", table))?;
+    }
+    tx.execute_batch(SCHEMA)?;
+
+    {
+        let mut stmt = tx.prepare("INSERT INTO metadata (key, value) VALUES (?1, ?2)")?;
+        let input_files = serde_json::to_string(&metadata.input_files).unwrap_or_default();
+        stmt.execute(params!["padron", summary.padron.to_string()])?;
+        stmt.execute(params!["n_threads", metadata.n_threads.to_string()])?;
+        stmt.execute(params!["input_files", input_files])?;
+        stmt.execute(params![
+            "started_at_unix",
+            metadata.started_at_unix().to_string()
+        ])?;
+    }
+
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO sites (name, questions, words, chattiness, tag_count, chatty_rank)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for (name, site) in &summary.sites {
+            let rank = rank_of(&summary.totals.chatty_sites, name);
+            stmt.execute(params![
+                name,
+                site.questions,
+                site.words,
+                site.chattiness(),
+                site.tags.len(),
+                rank
+            ])?;
+        }
+    }
+
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO tags (name, questions, words, chattiness, site_count, chatty_rank)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for (name, tag) in &summary.tags {
+            let site_count = summary
+                .sites
+                .values()
+                .filter(|site| site.tags.contains_key(name))
+                .count();
+            let rank = rank_of(&summary.totals.chatty_tags, name);
+            stmt.execute(params![
+                name,
+                tag.summary.questions,
+                tag.summary.words,
+                tag.summary.chattiness(),
+                site_count,
+                rank
+            ])?;
+        }
+    }
+
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO site_tags (site, tag, questions, words, chattiness, share_of_tag_questions)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for (site_name, site) in &summary.sites {
+            for (tag_name, tag) in &site.tags {
+                let total = summary
+                    .tags
+                    .get(tag_name)
+                    .map(|t| t.summary.questions)
+                    .unwrap_or(0);
+                let share = if total == 0 {
+                    0.0
+                } else {
+                    tag.questions as f64 / total as f64
+                };
+                stmt.execute(params![
+                    site_name,
+                    tag_name,
+                    tag.questions,
+                    tag.words,
+                    tag.chattiness(),
+                    share
+                ])?;
+            }
+        }
+    }
+
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO distributions (entity, metric, count, min, max, mean, median, p90, p99)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?;
+        let sites: Vec<(u64, u64, f32)> = summary
+            .sites
+            .values()
+            .map(|site| (site.questions, site.words, site.chattiness()))
+            .collect();
+        let tags: Vec<(u64, u64, f32)> = summary
+            .tags
+            .values()
+            .map(|tag| {
+                let tag = &tag.summary;
+                (tag.questions, tag.words, tag.chattiness())
+            })
+            .collect();
+        for (entity, counts) in [("sites", &sites), ("tags", &tags)] {
+            let metrics = [
+                (
+                    "questions",
+                    Distribution::of(counts.iter().map(|c| c.0 as f64)),
+                ),
+                ("words", Distribution::of(counts.iter().map(|c| c.1 as f64))),
+                (
+                    "chattiness",
+                    Distribution::of(counts.iter().map(|c| c.2 as f64)),
+                ),
+            ];
+            for (metric, distribution) in metrics {
+                if let Some(d) = distribution {
+                    stmt.execute(params![
+                        entity, metric, d.count, d.min, d.max, d.mean, d.median, d.p90, d.p99
+                    ])?;
+                }
+            }
+        }
+    }
+
+    tx.commit()
+}
+
+/// 1-based position of `name` in a ranking
+fn rank_of(ranking: &[String], name: &str) -> Option<usize> {
+    ranking.iter().position(|n| n == name).map(|i| i + 1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{existing_tables, write_summary};
+    use crate::run_metadata::RunMetadata;
+    use rusqlite::Connection;
+    use std::collections::HashMap;
+    use std::time::SystemTime;
+    use tp1::{FullSummary, SiteSummary, TagSummary};
+
+    fn test_summary() -> FullSummary {
+        let site1 = SiteSummary::new(
+            "site1",
+            vec![
+                TagSummary::new("a".to_string(), 2, 100),
+                TagSummary::new("b".to_string(), 1, 10),
+            ],
+        );
+        let site2 = SiteSummary::new("site2", vec![TagSummary::new("a".to_string(), 2, 300)]);
+        let total = site1.combine(&site2).unwrap();
+        let sites = HashMap::from([("site1".to_string(), site1), ("site2".to_string(), site2)]);
+        FullSummary::new(1, sites, &total)
+    }
+
+    #[test]
+    fn exports_all_tables() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let metadata = RunMetadata {
+            n_threads: 3,
+            input_files: vec!["site1".to_string(), "site2".to_string()],
+            started_at: SystemTime::now(),
+        };
+        write_summary(&mut conn, &test_summary(), &metadata).unwrap();
+
+        let sites: i64 = conn
+            .query_row("SELECT COUNT(*) FROM sites", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(sites, 2);
+
+        let (site_count, words): (i64, i64) = conn
+            .query_row(
+                "SELECT site_count, words FROM tags WHERE name = 'a'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(site_count, 2);
+        assert_eq!(words, 400);
+
+        let share: f64 = conn
+            .query_row(
+                "SELECT share_of_tag_questions FROM site_tags WHERE site = 'site1' AND tag = 'a'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(share, 0.5);
+
+        let threads: String = conn
+            .query_row(
+                "SELECT value FROM metadata WHERE key = 'n_threads'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(threads, "3");
+
+        let (count, min, max, median): (i64, f64, f64, f64) = conn
+            .query_row(
+                "SELECT count, min, max, median FROM distributions
+                 WHERE entity = 'tags' AND metric = 'words'",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!((count, min, max, median), (2, 10.0, 400.0, 205.0));
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM distributions", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(rows, 6);
+    }
+
+    #[test]
+    fn existing_tables_are_found() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert!(existing_tables(&conn).unwrap().is_empty());
+
+        conn.execute_batch("CREATE TABLE other (id INTEGER)")
+            .unwrap();
+        assert!(existing_tables(&conn).unwrap().is_empty());
+
+        let metadata = RunMetadata {
+            n_threads: 1,
+            input_files: Vec::new(),
+            started_at: SystemTime::now(),
+        };
+        write_summary(&mut conn, &test_summary(), &metadata).unwrap();
+        write_summary(&mut conn, &test_summary(), &metadata).unwrap();
+        let mut existing = existing_tables(&conn).unwrap();
+        existing.sort();
+        assert_eq!(
+            existing,
+            vec!["distributions", "metadata", "site_tags", "sites", "tags"]
+        );
+    }
+}
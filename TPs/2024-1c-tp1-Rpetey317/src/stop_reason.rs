@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Why a run stopped before reading every file
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// The user pressed Ctrl-C (SIGINT)
+    Interrupted,
+    /// The time budget ran out
+    TimeBudget,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Interrupted => write!(f, "interrupted"),
+            StopReason::TimeBudget => write!(f, "time budget exceeded"),
+        }
+    }
+}
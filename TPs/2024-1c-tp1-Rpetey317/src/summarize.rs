@@ -0,0 +1,445 @@
+use crate::anomaly::anomaly_detector::AnomalyDetector;
+use crate::anomaly::anomaly_mode::AnomalyMode;
+use crate::anomaly::anomaly_report::AnomalyReport;
+use crate::anomaly::word_cutoff::OutlierCutoffs;
+use crate::anomaly::word_histogram::WordDistributions;
+use crate::cancellation::Cancellation;
+use crate::count_overflow::CountOverflow;
+use crate::deduplicator::{self, Deduplicator, Fingerprint};
+use crate::full_summary::FullSummary;
+use crate::input_file::InputFile;
+use crate::parse_options::ParseOptions;
+use crate::parser;
+use crate::partial_run::PartialRun;
+use crate::run_progress::RunProgress;
+use crate::sampling_report::SamplingReport;
+use crate::site_assembler::SiteAssembler;
+use crate::site_summary::SiteSummary;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::time::Instant;
+
+/// Student ID, printed in every summary
+pub const PADRON: u32 = 109442;
+
+/// Called with the name and summary of each site once it's complete
+type OnSite<'a> = dyn Fn(&str, &SiteSummary) + Sync + 'a;
+
+/// Summarizes the `.jsonl` files of a directory (not of its subdirectories), each as a site
+/// named after its file, on rayon's global thread pool.
+///
+/// For more control over which files are read, how their sites are named, progress and
+/// cancellation, see `summarize_sites`.
+///
+/// May fail if the directory cannot be read, or with an `InvalidData` error wrapping a
+/// `CountOverflow` if a count overflows
+pub fn summarize_dir(path: &str, options: &ParseOptions) -> io::Result<FullSummary> {
+    let files = jsonl_files(path)?;
+    let progress = RunProgress::new(Instant::now(), &files);
+    summarize_sites(&files, options, &progress, &Cancellation::default())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Parses the given files in parallel (on rayon's global thread pool) and builds the full
+/// summary.
+///
+/// Progress of each file and the duration of the `parse` and `reduce` phases are recorded in
+/// `progress`, whose files must be the given ones, in the same order.
+/// Files that can't be read count as empty sites.
+///
+/// Files of the same site are merged. If repeated questions may span files, every file is first scanned to decide which one keeps
+/// each repeated question, so the result doesn't depend on how files are split among threads.
+///
+/// If outliers are looked for, the same scan also gets the word count distribution of each site
+/// and tag. Questions repeated across files are counted in it once per file, which barely moves
+/// the median and MAD outliers are found with. If excluded, outliers are still counted, but
+/// left out of the chattiness rankings. The scan is recorded as the `scan` phase.
+///
+/// If sampling, counts are estimated from the sampled lines by scaling them up, and the summary
+/// carries a confidence interval for the chattiness of each site and tag.
+///
+/// If `cancellation` is cancelled, workers stop before their next line and the summary covers
+/// only what was read, flagged as partial along with how much of each file that was.
+///
+/// Fails if a count of some site or tag overflows.
+pub fn summarize_sites(
+    files: &[InputFile],
+    options: &ParseOptions,
+    progress: &RunProgress,
+    cancellation: &Cancellation,
+) -> Result<FullSummary, CountOverflow> {
+    summarize(files, options, progress, cancellation, None)
+}
+
+/// Like `summarize_sites`, but also calls `on_site` with the name and summary of every site,
+/// once each, before the summary is built.
+///
+/// If the schema has no site field, each site is handed out from the worker that parses its
+/// last file, as soon as that's done, so it can be written out while other sites are still
+/// being parsed. Otherwise any file could have questions of any site, so they're handed out
+/// once every file is parsed.
+///
+/// The summaries are the same ones the full summary has, with their counts scaled if sampling.
+/// If the run is cancelled, the sites handed out afterwards only cover what was read.
+pub fn summarize_sites_with(
+    files: &[InputFile],
+    options: &ParseOptions,
+    progress: &RunProgress,
+    cancellation: &Cancellation,
+    on_site: &OnSite<'_>,
+) -> Result<FullSummary, CountOverflow> {
+    summarize(files, options, progress, cancellation, Some(on_site))
+}
+
+/// Summarizes the given files, as described in `summarize_sites_with`
+fn summarize(
+    files: &[InputFile],
+    options: &ParseOptions,
+    progress: &RunProgress,
+    cancellation: &Cancellation,
+    on_site: Option<&OnSite<'_>>,
+) -> Result<FullSummary, CountOverflow> {
+    let hand_out = |site: &SiteSummary| {
+        if let Some(on_site) = on_site {
+            match &options.sample {
+                Some(sampling) => {
+                    let mut scaled = site.clone();
+                    scaled.scale(sampling.scale());
+                    on_site(&site.name, &scaled);
+                }
+                None => on_site(&site.name, site),
+            }
+        }
+    };
+    let assembler =
+        (on_site.is_some() && !options.schema.has_site()).then(|| SiteAssembler::new(files));
+
+    let sites_span_files = options.schema.has_site() || has_repeated_sites(files);
+    let find_owners = options.dedup.spans_files(sites_span_files);
+    let find_cutoffs = options.anomalies.is_enabled();
+    let (owners, cutoffs) = if find_owners || find_cutoffs {
+        progress.time_phase("scan", || {
+            scan(files, options, find_owners, find_cutoffs, cancellation)
+        })
+    } else {
+        (None, None)
+    };
+
+    // process files
+    let parse_start = Instant::now();
+    let results: Result<Vec<_>, CountOverflow> = files
+        .par_iter()
+        .zip(progress.files())
+        .enumerate()
+        .map(|(index, (file, file_progress))| {
+            let start = Instant::now();
+            file_progress.start(rayon::current_thread_index());
+            let mut dedup = Deduplicator::new(options.dedup, owners.as_ref(), index);
+            let mut anomalies =
+                AnomalyDetector::new(options.anomalies, cutoffs.as_ref(), &file.name);
+            let result = parser::parse_file(
+                &file.path,
+                &file.site,
+                options,
+                &mut dedup,
+                &mut anomalies,
+                file_progress,
+                cancellation,
+            );
+            let nanos = start.elapsed().as_nanos() as u64;
+            if cancellation.is_cancelled() {
+                file_progress.stop(nanos);
+            } else {
+                file_progress.finish(nanos);
+            }
+            let summaries = match result {
+                Ok(parsed) => parsed?,
+                Err(_) => {
+                    HashMap::from([(file.site.clone(), SiteSummary::new(&file.site, Vec::new()))])
+                }
+            };
+            if let Some(assembler) = &assembler {
+                for site in summaries.values() {
+                    if let Some(complete) = assembler.add(site)? {
+                        hand_out(&complete);
+                    }
+                }
+            }
+            Ok((summaries, anomalies.into_anomalies()))
+        })
+        .collect();
+    let (parsed, anomalies): (Vec<HashMap<String, SiteSummary>>, Vec<_>) =
+        results?.into_iter().unzip();
+    progress.record_phase("parse", parse_start.elapsed());
+    let reduce_start = Instant::now();
+
+    // a site may be spread over several files
+    let mut summaries: HashMap<String, SiteSummary> = HashMap::new();
+    for (name, site) in parsed.into_iter().flatten() {
+        if let Some(existing) = summaries.get_mut(&name) {
+            existing.merge(&site)?;
+        } else {
+            summaries.insert(name, site);
+        }
+    }
+    if assembler.is_none() {
+        for site in summaries.values() {
+            hand_out(site);
+        }
+    }
+
+    // get aggregated summary
+    let total = summaries
+        .par_iter()
+        .map(|(_, site)| Ok(site.clone()))
+        .try_reduce(SiteSummary::empty, |a, b| a.combine(&b))?;
+
+    let mut summary = FullSummary::new(PADRON, summaries, &total);
+    if let Some(cutoffs) = &cutoffs {
+        let mut found = Vec::new();
+        let mut excluded: HashMap<String, SiteSummary> = HashMap::new();
+        for (file_found, file_excluded) in anomalies {
+            found.extend(file_found);
+            for (name, site) in file_excluded {
+                match excluded.get_mut(&name) {
+                    Some(existing) => existing.merge(&site)?,
+                    None => {
+                        excluded.insert(name, site);
+                    }
+                }
+            }
+        }
+        let exclude = options.anomalies == AnomalyMode::Exclude;
+        if exclude {
+            summary = summary.ranked_without(&excluded)?;
+        }
+        summary = summary.with_anomalies(AnomalyReport::new(cutoffs, found, exclude));
+    }
+    if let Some(sampling) = &options.sample {
+        let report = SamplingReport::new(sampling, &summary);
+        summary = summary.scaled(sampling.scale()).with_sampling(report);
+    }
+    if let Some(reason) = cancellation.reason() {
+        summary = summary.with_partial(PartialRun::new(reason, progress.files()));
+    }
+    progress.record_phase("reduce", reduce_start.elapsed());
+    Ok(summary)
+}
+
+/// Scans every file in parallel, once. If `find_owners` is set, decides which file owns each
+/// question repeated across files. If `find_cutoffs` is set, computes the word count statistics
+/// of each site and tag. Files that can't be read own nothing and have no questions
+fn scan(
+    files: &[InputFile],
+    options: &ParseOptions,
+    find_owners: bool,
+    find_cutoffs: bool,
+    cancellation: &Cancellation,
+) -> (Option<HashMap<Fingerprint, usize>>, Option<OutlierCutoffs>) {
+    let (keys, distributions): (Vec<HashSet<Fingerprint>>, Vec<WordDistributions>) = files
+        .par_iter()
+        .enumerate()
+        .map(|(index, file)| {
+            let mut dedup = Deduplicator::new(options.dedup, None, index);
+            parser::scan_file(
+                &file.path,
+                &file.site,
+                options,
+                &mut dedup,
+                find_owners,
+                find_cutoffs,
+                cancellation,
+            )
+            .unwrap_or_default()
+        })
+        .unzip();
+    let owners = find_owners.then(|| deduplicator::owners(&keys));
+    let cutoffs = find_cutoffs.then(|| {
+        distributions
+            .into_iter()
+            .fold(WordDistributions::default(), WordDistributions::merge)
+            .cutoffs()
+    });
+    (owners, cutoffs)
+}
+
+/// Returns the `.jsonl` files of a directory (not of its subdirectories), sorted by path, each
+/// as a site named after its file
+fn jsonl_files(dir: &str) -> io::Result<Vec<InputFile>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)?.flatten() {
+        let name = match entry.file_name().to_str() {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        if name.ends_with(".jsonl") && entry.path().is_file() {
+            files.push(InputFile {
+                path: format!("{}/{}", dir, name),
+                name: name.clone(),
+                site: name,
+            });
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Whether some site is made of more than one file
+fn has_repeated_sites(files: &[InputFile]) -> bool {
+    let mut sites = HashSet::new();
+    !files.iter().all(|file| sites.insert(&file.site))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{jsonl_files, summarize_dir, summarize_sites, summarize_sites_with};
+    use crate::anomaly::anomaly_mode::AnomalyMode;
+    use crate::cancellation::Cancellation;
+    use crate::dedup_mode::DedupMode;
+    use crate::input_file::InputFile;
+    use crate::parse_options::ParseOptions;
+    use crate::run_progress::RunProgress;
+    use crate::sampling::Sampling;
+    use crate::stop_reason::StopReason;
+    use std::sync::Mutex;
+    use std::time::Instant;
+
+    fn summarize_with(
+        dedup: DedupMode,
+        cancellation: &Cancellation,
+    ) -> crate::full_summary::FullSummary {
+        let files = jsonl_files("test_data/dedup").unwrap();
+        let progress = RunProgress::new(Instant::now(), &files);
+        let options = ParseOptions {
+            dedup,
+            ..ParseOptions::default()
+        };
+        summarize_sites(&files, &options, &progress, cancellation).unwrap()
+    }
+
+    fn summarize(dedup: DedupMode) -> crate::full_summary::FullSummary {
+        summarize_with(dedup, &Cancellation::default())
+    }
+
+    #[test]
+    fn global_dedup_keeps_questions_in_the_first_file() {
+        let summary = summarize(DedupMode::Global);
+        assert_eq!(summary.sites["a.jsonl"].questions, 2);
+        assert_eq!(summary.sites["b.jsonl"].questions, 1);
+        assert_eq!(summary.sites["b.jsonl"].duplicates, Some(1));
+        assert_eq!(summary.tags["y"].summary.questions, 2);
+    }
+
+    #[test]
+    fn site_dedup_keeps_questions_repeated_in_other_sites() {
+        let summary = summarize(DedupMode::Site);
+        assert_eq!(summary.sites["a.jsonl"].questions, 2);
+        assert_eq!(summary.sites["b.jsonl"].questions, 2);
+        assert_eq!(summary.sites["b.jsonl"].duplicates, Some(0));
+        assert_eq!(summary.tags["y"].summary.questions, 3);
+    }
+
+    #[test]
+    fn cancelled_runs_are_partial() {
+        assert_eq!(summarize(DedupMode::Off).partial, None);
+
+        let summary = summarize_with(DedupMode::Off, &Cancellation::new(Some(Instant::now())));
+        assert_eq!(summary.sites["a.jsonl"].questions, 0);
+        let partial = summary.partial.unwrap();
+        assert_eq!(partial.reason, StopReason::TimeBudget);
+        assert_eq!(partial.completion["a.jsonl"], 0.0);
+        assert_eq!(partial.completion["b.jsonl"], 0.0);
+    }
+
+    #[test]
+    fn every_site_is_handed_out_once() {
+        let files = jsonl_files("test_data/dedup").unwrap();
+        let progress = RunProgress::new(Instant::now(), &files);
+        let handed_out = Mutex::new(Vec::new());
+        let summary = summarize_sites_with(
+            &files,
+            &ParseOptions::default(),
+            &progress,
+            &Cancellation::default(),
+            &|name, site| {
+                handed_out
+                    .lock()
+                    .unwrap()
+                    .push((name.to_string(), site.questions))
+            },
+        )
+        .unwrap();
+        let mut handed_out = handed_out.into_inner().unwrap();
+        handed_out.sort();
+        assert_eq!(
+            handed_out,
+            vec![("a.jsonl".to_string(), 4), ("b.jsonl".to_string(), 2)]
+        );
+        assert_eq!(summary.sites["b.jsonl"].questions, 2);
+    }
+
+    #[test]
+    fn sampled_counts_are_scaled_up() {
+        let sampled = |fraction| {
+            let options = ParseOptions {
+                sample: Some(Sampling::new(fraction, 3).unwrap()),
+                ..ParseOptions::default()
+            };
+            summarize_dir("test_data/dedup", &options).unwrap()
+        };
+
+        let all = sampled(1.0);
+        assert_eq!(all.sites["a.jsonl"].questions, 4);
+        assert_eq!(all.tags["y"].summary.questions, 4);
+        let report = all.sampling.unwrap();
+        assert_eq!(report.sites["a.jsonl"].sampled_questions, 4);
+        assert_eq!(report.sites["a.jsonl"].low, report.sites["a.jsonl"].high);
+
+        let half = sampled(0.5);
+        let report = half.sampling.unwrap();
+        let sampled_questions = report.sites["a.jsonl"].sampled_questions;
+        assert_eq!(half.sites["a.jsonl"].questions, 2 * sampled_questions);
+        assert!(sampled_questions < 4);
+    }
+
+    #[test]
+    fn directories_are_summarized() {
+        let summary = summarize_dir("test_data/dedup", &ParseOptions::default()).unwrap();
+        assert_eq!(summary.sites.len(), 2);
+        assert_eq!(summary.sites["a.jsonl"].questions, 4);
+        assert_eq!(summary.tags["y"].summary.questions, 4);
+        assert!(summarize_dir("test_data/missing", &ParseOptions::default()).is_err());
+    }
+
+    #[test]
+    fn excluded_outliers_are_only_left_out_of_rankings() {
+        let files: Vec<InputFile> = ["anomalies", "site4"]
+            .iter()
+            .map(|name| InputFile {
+                path: format!("test_data/{}.jsonl", name),
+                name: name.to_string(),
+                site: name.to_string(),
+            })
+            .collect();
+        let summarize = |anomalies| {
+            let options = ParseOptions {
+                anomalies,
+                ..ParseOptions::default()
+            };
+            let progress = RunProgress::new(Instant::now(), &files);
+            summarize_sites(&files, &options, &progress, &Cancellation::default()).unwrap()
+        };
+
+        let reported = summarize(AnomalyMode::Report);
+        let excluded = summarize(AnomalyMode::Exclude);
+        assert_eq!(reported.sites["anomalies"].questions, 12);
+        assert_eq!(excluded.sites["anomalies"].questions, 12);
+        assert_eq!(excluded.tags["ads"].summary.words, 430);
+        assert_eq!(reported.totals.chatty_tags[..2], ["ads", "rust"]);
+        assert_eq!(excluded.totals.chatty_tags[0], "keywords");
+        assert!(excluded.anomalies.unwrap().excluded);
+        assert!(!reported.anomalies.unwrap().excluded);
+    }
+}
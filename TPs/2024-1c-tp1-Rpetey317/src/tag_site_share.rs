@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+/// How much a single site contributes to a tag aggregated over all sites
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TagSiteShare {
+    /// N° of questions with this tag in the site
+    pub questions: u64,
+    /// Total word count of the questions with this tag in the site
+    pub words: u64,
+    /// Fraction of the tag's questions (over all sites) that belong to this site
+    pub question_share: f64,
+    /// Fraction of the tag's words (over all sites) that belong to this site
+    pub word_share: f64,
+}
+
+impl TagSiteShare {
+    /// Creates the share of a site with the given counts, out of the tag's total counts
+    pub fn new(questions: u64, words: u64, total_questions: u64, total_words: u64) -> TagSiteShare {
+        TagSiteShare {
+            questions,
+            words,
+            question_share: ratio(questions, total_questions),
+            word_share: ratio(words, total_words),
+        }
+    }
+
+    /// Returns chattiness score (word count / question count) of the tag in this site.
+    /// Returns 0 if there are no questions
+    pub fn chattiness(&self) -> f32 {
+        if self.questions == 0 {
+            return 0.0;
+        }
+        self.words as f32 / self.questions as f32
+    }
+}
+
+/// `part / total`, or 0 if the total is 0
+fn ratio(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    part as f64 / total as f64
+}
@@ -1,39 +1,72 @@
-use serde::Serialize;
+use crate::count_overflow::{self, CountOverflow};
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 
 /// A strcut that contains word and question count for a given tag
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct TagSummary {
     /// Tag name. Necessary to make sure only similar tags are combined
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub name: String,
     /// N° of questions this tag appears in
-    pub questions: u32,
+    pub questions: u64,
     /// Total word count for every question this tag appears in
-    pub words: u32,
+    pub words: u64,
+    /// Sum of the squared word count of every question this tag appears in, used to estimate
+    /// the variance of its chattiness when sampling. Not serialized
+    #[serde(skip)]
+    pub words_squared: u128,
 }
 
 impl TagSummary {
     /// Creates a new TagSummary with the given name, question count and word count
-    pub fn new(name: String, question_count: u32, word_count: u32) -> TagSummary {
+    pub fn new(name: String, question_count: u64, word_count: u64) -> TagSummary {
         TagSummary {
             name,
             questions: question_count,
             words: word_count,
+            words_squared: 0,
         }
     }
 
-    /// Combines two summaries into one, adding word and question counts.
-    /// Fails if the tags have different names
-    pub fn combine(&self, other: &TagSummary) -> TagSummary {
-        assert!(self.name == other.name, "Cannot combine two different tags");
+    /// Creates the summary of a tag appearing in a single question with the given word count
+    pub fn from_question(name: String, word_count: u32) -> TagSummary {
         TagSummary {
-            name: self.name.clone(),
-            questions: self.questions + other.questions,
-            words: self.words + other.words,
+            words_squared: word_count as u128 * word_count as u128,
+            ..TagSummary::new(name, 1, word_count as u64)
         }
     }
 
+    /// Combines two summaries into one, adding word and question counts.
+    /// Fails if a count overflows. Panics if the tags have different names
+    pub fn combine(&self, other: &TagSummary) -> Result<TagSummary, CountOverflow> {
+        let mut combined = self.clone();
+        combined.merge(other)?;
+        Ok(combined)
+    }
+
+    /// Adds the word and question counts of another summary to this one.
+    /// Fails, leaving this one as it was, if a count overflows. Panics if the tags have
+    /// different names
+    pub fn merge(&mut self, other: &TagSummary) -> Result<(), CountOverflow> {
+        assert!(self.name == other.name, "Cannot combine two different tags");
+        let questions =
+            count_overflow::add(self.questions, other.questions, "questions", &self.name)?;
+        let words = count_overflow::add(self.words, other.words, "words", &self.name)?;
+        let words_squared =
+            count_overflow::add_squares(self.words_squared, other.words_squared, &self.name)?;
+        self.questions = questions;
+        self.words = words;
+        self.words_squared = words_squared;
+        Ok(())
+    }
+
+    /// Multiplies the word and question counts by the given factor, rounding them
+    pub fn scale(&mut self, factor: f64) {
+        self.questions = scale_count(self.questions, factor);
+        self.words = scale_count(self.words, factor);
+    }
+
     /// Returns chattiness score (word count / question count).
     /// Returns 0 if there are no questions
     pub fn chattiness(&self) -> f32 {
@@ -51,10 +84,16 @@ impl Clone for TagSummary {
             name: self.name.clone(),
             questions: self.questions,
             words: self.words,
+            words_squared: self.words_squared,
         }
     }
 }
 
+/// Multiplies a count by the given factor, rounding it
+pub(crate) fn scale_count(count: u64, factor: f64) -> u64 {
+    (count as f64 * factor).round() as u64
+}
+
 // TODO: chequear si puedo sacar eq y ord
 impl PartialEq for TagSummary {
     fn eq(&self, other: &Self) -> bool {
@@ -71,6 +110,7 @@ impl PartialOrd for TagSummary {
 #[cfg(test)]
 mod test {
     use super::TagSummary;
+    use crate::count_overflow::CountOverflow;
 
     fn test_tag1() -> TagSummary {
         TagSummary::new("tag1".to_string(), 10, 100)
@@ -94,12 +134,42 @@ mod test {
     fn can_combine_similar_tags() {
         let tag1 = test_tag1();
         let tag2 = test_tag1_alt();
-        let combined = tag1.combine(&tag2);
+        let combined = tag1.combine(&tag2).unwrap();
         assert_eq!(combined.questions, 20);
         assert_eq!(combined.words, 300);
         assert_eq!(combined.chattiness(), 15.0);
     }
 
+    #[test]
+    fn can_merge_in_place() {
+        let mut tag = test_tag1();
+        tag.merge(&test_tag1_alt()).unwrap();
+        assert_eq!(tag.questions, 20);
+        assert_eq!(tag.words, 300);
+    }
+
+    #[test]
+    fn squares_are_kept_through_merges() {
+        let mut tag = TagSummary::from_question("tag1".to_string(), 3);
+        tag.merge(&TagSummary::from_question("tag1".to_string(), 4))
+            .unwrap();
+        assert_eq!(tag.questions, 2);
+        assert_eq!(tag.words, 7);
+        assert_eq!(tag.words_squared, 25);
+        tag.scale(2.5);
+        assert_eq!(tag.questions, 5);
+        assert_eq!(tag.words, 18);
+    }
+
+    #[test]
+    fn overflowing_merges_are_errors() {
+        let mut tag = TagSummary::new("tag1".to_string(), 1, u64::MAX);
+        let overflow = tag.merge(&test_tag1()).unwrap_err();
+        assert_eq!(overflow, CountOverflow::new("words", "tag1"));
+        assert_eq!(tag.questions, 1);
+        assert_eq!(tag.words, u64::MAX);
+    }
+
     #[test]
     #[should_panic]
     fn cannot_combine_different_tags() {
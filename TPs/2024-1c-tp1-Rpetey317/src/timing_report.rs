@@ -0,0 +1,168 @@
+use serde::Serialize;
+use tp1::RunProgress;
+
+/// Time spent in one phase of a run
+#[derive(Serialize)]
+pub struct PhaseTiming {
+    /// Phase name: `discovery`, `parse`, `reduce` or `serialize`
+    pub phase: String,
+    /// Duration of the phase
+    pub seconds: f64,
+}
+
+/// Time spent parsing a single file
+#[derive(Serialize)]
+pub struct FileTiming {
+    /// File name
+    pub file: String,
+    /// Bytes read
+    pub bytes: u64,
+    /// Lines read
+    pub lines: u64,
+    /// Time spent parsing it
+    pub parse_seconds: f64,
+    /// Worker thread that parsed it
+    pub worker: Option<usize>,
+    /// Read throughput
+    pub mb_per_second: f64,
+}
+
+/// How busy a worker thread was during the parse phase
+#[derive(Serialize)]
+pub struct WorkerTiming {
+    /// Worker thread index
+    pub worker: usize,
+    /// N° of files it parsed
+    pub files: usize,
+    /// Time spent parsing files
+    pub busy_seconds: f64,
+    /// Fraction of the parse phase spent parsing files. Low values mean starved threads
+    pub utilization: f64,
+}
+
+/// Machine-readable report of where the time of a run went
+#[derive(Serialize)]
+pub struct TimingReport {
+    /// N° of worker threads
+    pub threads: usize,
+    /// Total duration of the run
+    pub wall_seconds: f64,
+    /// Total bytes read
+    pub bytes: u64,
+    /// Total lines read
+    pub lines: u64,
+    /// Read throughput over the parse phase
+    pub mb_per_second: f64,
+    /// Duration of each phase, in order
+    pub phases: Vec<PhaseTiming>,
+    /// Per-file timings, slowest first
+    pub files: Vec<FileTiming>,
+    /// Per-worker utilization
+    pub workers: Vec<WorkerTiming>,
+}
+
+impl TimingReport {
+    /// Builds the timing report of everything recorded so far in a run on `n_threads` workers
+    pub fn new(progress: &RunProgress, n_threads: usize) -> TimingReport {
+        let phases: Vec<PhaseTiming> = progress
+            .phases()
+            .into_iter()
+            .map(|(phase, duration)| PhaseTiming {
+                phase,
+                seconds: duration.as_secs_f64(),
+            })
+            .collect();
+        let parse_seconds = phases
+            .iter()
+            .find(|p| p.phase == "parse")
+            .map(|p| p.seconds)
+            .unwrap_or_else(|| progress.elapsed().as_secs_f64());
+
+        let mut files: Vec<FileTiming> = progress
+            .files()
+            .iter()
+            .map(|f| {
+                let seconds = f.parse_nanos() as f64 / 1e9;
+                FileTiming {
+                    file: f.name.clone(),
+                    bytes: f.bytes(),
+                    lines: f.lines(),
+                    parse_seconds: seconds,
+                    worker: f.worker(),
+                    mb_per_second: megabytes_per_second(f.bytes(), seconds),
+                }
+            })
+            .collect();
+        files.sort_by(|a, b| b.parse_seconds.total_cmp(&a.parse_seconds));
+
+        let workers = (0..n_threads)
+            .map(|worker| {
+                let parsed: Vec<&FileTiming> =
+                    files.iter().filter(|f| f.worker == Some(worker)).collect();
+                let busy_seconds: f64 = parsed.iter().map(|f| f.parse_seconds).sum();
+                WorkerTiming {
+                    worker,
+                    files: parsed.len(),
+                    busy_seconds,
+                    utilization: if parse_seconds > 0.0 {
+                        (busy_seconds / parse_seconds).min(1.0)
+                    } else {
+                        0.0
+                    },
+                }
+            })
+            .collect();
+
+        let bytes = progress.files().iter().map(|f| f.bytes()).sum();
+        TimingReport {
+            threads: n_threads,
+            wall_seconds: progress.elapsed().as_secs_f64(),
+            bytes,
+            lines: progress.files().iter().map(|f| f.lines()).sum(),
+            mb_per_second: megabytes_per_second(bytes, parse_seconds),
+            phases,
+            files,
+            workers,
+        }
+    }
+}
+
+/// Throughput in MB (10^6 bytes) per second, or 0 if no time passed
+pub fn megabytes_per_second(bytes: u64, seconds: f64) -> f64 {
+    if seconds <= 0.0 {
+        return 0.0;
+    }
+    bytes as f64 / 1e6 / seconds
+}
+
+#[cfg(test)]
+mod test {
+    use super::TimingReport;
+    use std::time::{Duration, Instant};
+    use tp1::{InputFile, RunProgress};
+
+    #[test]
+    fn report_aggregates_files_and_workers() {
+        let files: Vec<InputFile> = ["site1.jsonl", "missing.jsonl"]
+            .iter()
+            .map(|name| InputFile {
+                path: format!("test_data/{}", name),
+                name: name.to_string(),
+                site: name.to_string(),
+            })
+            .collect();
+        let progress = RunProgress::new(Instant::now(), &files);
+        progress.files()[0].start(Some(1));
+        progress.files()[0].add_line(9);
+        progress.files()[0].finish(500_000_000);
+        progress.record_phase("parse", Duration::from_secs(1));
+
+        let report = TimingReport::new(&progress, 2);
+        assert_eq!(report.bytes, 10);
+        assert_eq!(report.lines, 1);
+        assert_eq!(report.files[0].file, "site1.jsonl");
+        assert_eq!(report.workers[0].files, 0);
+        assert_eq!(report.workers[1].files, 1);
+        assert_eq!(report.workers[1].utilization, 0.5);
+    }
+}
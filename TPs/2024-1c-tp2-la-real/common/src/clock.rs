@@ -0,0 +1,336 @@
+//! Relojes lógicos de los nodos.
+//!
+//! Cada proceso mantiene un `NodeClock` con un reloj de Lamport y, si se configura
+//! `clock.vector`, un reloj vectorial. El reloj avanza con cada mensaje enviado y, al recibir
+//! uno, se adelanta al `Timestamp` que trae el `Envelope`. Los logs de cada nodo empiezan con
+//! un `LogStamp` con el valor actual del reloj, lo que permite mezclar los logs de todos los
+//! procesos en una única línea de tiempo consistente con la causalidad.
+
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::communication::handshake::NodeKind;
+
+/// Reloj del proceso, compartido por todas sus conexiones y sus logs.
+static GLOBAL_CLOCK: OnceLock<NodeClock> = OnceLock::new();
+
+/// Reloj vectorial: cuántos eventos de cada nodo se conocen.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(transparent)]
+pub struct VectorClock(BTreeMap<String, u64>);
+
+impl VectorClock {
+    /// Cantidad de eventos conocidos del nodo.
+    ///
+    /// # Argumentos
+    ///
+    /// * `node` - Nombre del nodo.
+    pub fn get(&self, node: &str) -> u64 {
+        self.0.get(node).copied().unwrap_or_default()
+    }
+
+    /// Registra un evento del nodo.
+    ///
+    /// # Argumentos
+    ///
+    /// * `node` - Nombre del nodo.
+    pub fn increment(&mut self, node: &str) {
+        *self.0.entry(node.to_string()).or_default() += 1;
+    }
+
+    /// Incorpora los eventos conocidos por otro reloj, quedándose con el máximo de cada nodo.
+    ///
+    /// # Argumentos
+    ///
+    /// * `other` - Reloj recibido.
+    pub fn merge(&mut self, other: &VectorClock) {
+        for (node, &count) in &other.0 {
+            let known = self.0.entry(node.clone()).or_default();
+            *known = (*known).max(count);
+        }
+    }
+}
+
+impl PartialOrd for VectorClock {
+    /// Orden causal: un reloj es menor si todos sus eventos los conoce el otro. Dos relojes
+    /// concurrentes no son comparables.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let nodes = self.0.keys().chain(other.0.keys());
+        let (mut less, mut greater) = (false, false);
+        for node in nodes {
+            match self.get(node).cmp(&other.get(node)) {
+                Ordering::Less => less = true,
+                Ordering::Greater => greater = true,
+                Ordering::Equal => {}
+            }
+        }
+        match (less, greater) {
+            (false, false) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Less),
+            (false, true) => Some(Ordering::Greater),
+            (true, true) => None,
+        }
+    }
+}
+
+/// Valor del reloj de un nodo, tal como viaja en cada `Envelope`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Timestamp {
+    pub lamport: u64,
+    /// Reloj vectorial, presente solo si el nodo lo tiene activado.
+    pub vector: Option<VectorClock>,
+}
+
+/// `NodeClock` es el reloj lógico de un nodo. Sus copias comparten el mismo estado.
+#[derive(Debug, Clone)]
+pub struct NodeClock {
+    state: Arc<Mutex<ClockState>>,
+}
+
+#[derive(Debug)]
+struct ClockState {
+    node: String,
+    lamport: u64,
+    vector: Option<VectorClock>,
+}
+
+impl NodeClock {
+    /// Crea un reloj en cero.
+    ///
+    /// # Argumentos
+    ///
+    /// * `node` - Nombre del nodo en los logs y en los relojes vectoriales.
+    /// * `vector` - Si además del reloj de Lamport se lleva un reloj vectorial.
+    pub fn new(node: &str, vector: bool) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ClockState {
+                node: node.to_string(),
+                lamport: 0,
+                vector: vector.then(VectorClock::default),
+            })),
+        }
+    }
+
+    /// Nombre con el que un nodo aparece en los logs y en los relojes vectoriales.
+    ///
+    /// # Argumentos
+    ///
+    /// * `kind` - Tipo de nodo.
+    /// * `id` - Id del nodo, el mismo que declara en el handshake.
+    pub fn node_name(kind: NodeKind, id: usize) -> String {
+        format!("{}-{}", kind, id)
+    }
+
+    /// Inicializa el reloj del proceso. Solo tiene efecto la primera vez que se llama, y antes
+    /// de cualquier llamada a `NodeClock::global`.
+    ///
+    /// # Argumentos
+    ///
+    /// * `node` - Nombre del nodo en los logs y en los relojes vectoriales.
+    /// * `vector` - Si además del reloj de Lamport se lleva un reloj vectorial.
+    ///
+    /// # Retorna
+    ///
+    /// El reloj del proceso.
+    pub fn init_global(node: &str, vector: bool) -> NodeClock {
+        GLOBAL_CLOCK
+            .get_or_init(|| NodeClock::new(node, vector))
+            .clone()
+    }
+
+    /// Reloj del proceso. Si no se inicializó, se crea uno de Lamport con el pid como nombre.
+    pub fn global() -> NodeClock {
+        GLOBAL_CLOCK
+            .get_or_init(|| NodeClock::new(&format!("pid-{}", std::process::id()), false))
+            .clone()
+    }
+
+    /// Avanza el reloj por el envío de un mensaje.
+    ///
+    /// # Retorna
+    ///
+    /// El `Timestamp` que lleva el mensaje.
+    pub fn send(&self) -> Timestamp {
+        let mut state = self.lock();
+        state.lamport += 1;
+        let node = state.node.clone();
+        if let Some(vector) = &mut state.vector {
+            vector.increment(&node);
+        }
+        state.timestamp()
+    }
+
+    /// Adelanta el reloj por la recepción de un mensaje.
+    ///
+    /// # Argumentos
+    ///
+    /// * `remote` - `Timestamp` que trae el mensaje.
+    ///
+    /// # Retorna
+    ///
+    /// El valor del reloj al recibir el mensaje.
+    pub fn receive(&self, remote: &Timestamp) -> Timestamp {
+        let mut state = self.lock();
+        state.lamport = state.lamport.max(remote.lamport) + 1;
+        let node = state.node.clone();
+        if let Some(vector) = &mut state.vector {
+            if let Some(remote) = &remote.vector {
+                vector.merge(remote);
+            }
+            vector.increment(&node);
+        }
+        state.timestamp()
+    }
+
+    /// Valor actual del reloj, sin avanzarlo.
+    pub fn now(&self) -> Timestamp {
+        self.lock().timestamp()
+    }
+
+    /// Marca con la que empieza cada línea de log del nodo.
+    pub fn stamp(&self) -> LogStamp {
+        let state = self.lock();
+        LogStamp {
+            node: state.node.clone(),
+            timestamp: state.timestamp(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ClockState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl ClockState {
+    fn timestamp(&self) -> Timestamp {
+        Timestamp {
+            lamport: self.lamport,
+            vector: self.vector.clone(),
+        }
+    }
+}
+
+/// Marca de una línea de log: el nodo que la escribió y su reloj en ese momento. Se muestra
+/// como `[L12 robot-10000]`, o `[L12 robot-10000 {"robot-10000":3,"screen-42":1}]` si el
+/// nodo lleva un reloj vectorial.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogStamp {
+    pub node: String,
+    pub timestamp: Timestamp,
+}
+
+impl LogStamp {
+    /// Separa la marca del comienzo de una línea de log.
+    ///
+    /// # Argumentos
+    ///
+    /// * `line` - Línea de log.
+    ///
+    /// # Retorna
+    ///
+    /// La marca y el resto de la línea, o `None` si la línea no empieza con una marca.
+    pub fn parse(line: &str) -> Option<(LogStamp, &str)> {
+        let (stamp, rest) = line.strip_prefix("[L")?.split_once(']')?;
+        let mut fields = stamp.splitn(3, ' ');
+        let lamport = fields.next()?.parse().ok()?;
+        let node = fields.next().filter(|node| !node.is_empty())?.to_string();
+        let vector = match fields.next() {
+            Some(vector) => Some(serde_json::from_str(vector).ok()?),
+            None => None,
+        };
+        let stamp = LogStamp {
+            node,
+            timestamp: Timestamp { lamport, vector },
+        };
+        Some((stamp, rest.strip_prefix(' ').unwrap_or(rest)))
+    }
+}
+
+impl fmt::Display for LogStamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[L{} {}", self.timestamp.lamport, self.node)?;
+        if let Some(vector) = &self.timestamp.vector {
+            let vector = serde_json::to_string(vector).map_err(|_| fmt::Error)?;
+            write!(f, " {}", vector)?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Como `println!`, pero empezando la línea con la marca del reloj del proceso.
+#[macro_export]
+macro_rules! clocked_println {
+    ($($arg:tt)*) => {
+        println!(
+            "{} {}",
+            $crate::clock::NodeClock::global().stamp(),
+            format_args!($($arg)*)
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lamport_clock_advances_past_received_messages() {
+        let robot = NodeClock::new("robot-10000", false);
+        let screen = NodeClock::new("screen-42", false);
+
+        assert_eq!(robot.send().lamport, 1);
+        let sent = screen.send();
+        for _ in 0..5 {
+            screen.send();
+        }
+        assert_eq!(robot.receive(&screen.now()).lamport, 7);
+        assert_eq!(robot.receive(&sent).lamport, 8);
+        assert_eq!(robot.now().vector, None);
+    }
+
+    #[test]
+    fn test_vector_clocks_order_causally_related_events() {
+        let robot = NodeClock::new("robot-10000", true);
+        let screen = NodeClock::new("screen-42", true);
+        let shop = NodeClock::new("shop-9999", true);
+
+        let order = screen.send();
+        let received = robot.receive(&order);
+        let unrelated = shop.send();
+
+        let (order, received, unrelated) = (
+            order.vector.unwrap(),
+            received.vector.unwrap(),
+            unrelated.vector.unwrap(),
+        );
+        assert!(order < received);
+        assert_eq!(received.get("screen-42"), 1);
+        assert_eq!(received.get("robot-10000"), 1);
+        assert_eq!(received.partial_cmp(&unrelated), None);
+    }
+
+    #[test]
+    fn test_log_stamps_round_trip() {
+        let clock = NodeClock::new("robot-10000", true);
+        clock.receive(&NodeClock::new("screen-42", true).send());
+        let lamport_only = NodeClock::new("shop-9999", false);
+        lamport_only.send();
+
+        for stamp in [clock.stamp(), lamport_only.stamp()] {
+            let line = format!("{} Order finished", stamp);
+            assert_eq!(LogStamp::parse(&line), Some((stamp, "Order finished")));
+        }
+        assert_eq!(
+            lamport_only.stamp().to_string(),
+            "[L1 shop-9999]".to_string()
+        );
+        assert_eq!(LogStamp::parse("Order finished"), None);
+        assert_eq!(LogStamp::parse("[Registry] Listening"), None);
+    }
+}
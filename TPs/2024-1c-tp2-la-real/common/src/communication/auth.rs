@@ -0,0 +1,366 @@
+//! Autenticación de los nodos con un secreto compartido.
+//!
+//! Si el cluster configura `auth.secret`, cada conexión se autentica en el handshake con un
+//! desafío-respuesta: cada extremo envía un desafío aleatorio y prueba conocer el secreto
+//! devolviendo el HMAC-SHA256 de ambos desafíos. De los mismos desafíos se derivan dos claves
+//! de sesión, una por sentido, con las que se firma cada frame junto a su número de secuencia.
+//! Un frame con una firma inválida o fuera de secuencia se descarta.
+
+use std::fmt;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use super::registry_communication::RobotRecord;
+use crate::config::AuthConfig;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Largo, en bytes, de las claves y de las firmas.
+const KEY_SIZE: usize = 32;
+
+/// Extremo de una conexión: quien la inició o quien la aceptó.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Initiator,
+    Acceptor,
+}
+
+impl Role {
+    fn label(&self) -> &'static [u8] {
+        match self {
+            Role::Initiator => b"initiator",
+            Role::Acceptor => b"acceptor",
+        }
+    }
+
+    fn peer(&self) -> Role {
+        match self {
+            Role::Initiator => Role::Acceptor,
+            Role::Acceptor => Role::Initiator,
+        }
+    }
+}
+
+/// Clave derivada del secreto compartido del cluster. Nunca se serializa ni se muestra.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SecretKey([u8; KEY_SIZE]);
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretKey(..)")
+    }
+}
+
+impl SecretKey {
+    /// Deriva la clave de un secreto.
+    ///
+    /// # Argumentos
+    ///
+    /// * `secret` - Secreto compartido por todos los nodos del cluster.
+    pub fn new(secret: &str) -> Self {
+        Self(Sha256::digest(secret.as_bytes()).into())
+    }
+
+    /// Clave configurada para el cluster, o `None` si la autenticación está desactivada.
+    ///
+    /// # Argumentos
+    ///
+    /// * `config` - Configuración de la autenticación.
+    pub fn from_config(config: &AuthConfig) -> Option<Self> {
+        if config.secret.is_empty() {
+            None
+        } else {
+            Some(Self::new(&config.secret))
+        }
+    }
+
+    /// Genera un desafío aleatorio para un handshake.
+    pub fn challenge() -> Uuid {
+        Uuid::new_v4()
+    }
+
+    /// Prueba de que un extremo conoce el secreto, atada a los desafíos de esta conexión.
+    ///
+    /// # Argumentos
+    ///
+    /// * `role` - Extremo que genera la prueba.
+    /// * `initiator` - Desafío de quien inició la conexión.
+    /// * `acceptor` - Desafío de quien la aceptó.
+    pub fn proof(&self, role: Role, initiator: &Uuid, acceptor: &Uuid) -> Vec<u8> {
+        self.transcript(role.label(), initiator, acceptor)
+            .finalize()
+            .into_bytes()
+            .to_vec()
+    }
+
+    /// Verifica, en tiempo constante, la prueba recibida del par.
+    ///
+    /// # Argumentos
+    ///
+    /// * `role` - Extremo que generó la prueba.
+    /// * `initiator` - Desafío de quien inició la conexión.
+    /// * `acceptor` - Desafío de quien la aceptó.
+    /// * `proof` - Prueba recibida.
+    pub fn verify_proof(
+        &self,
+        role: Role,
+        initiator: &Uuid,
+        acceptor: &Uuid,
+        proof: &[u8],
+    ) -> bool {
+        self.transcript(role.label(), initiator, acceptor)
+            .verify_slice(proof)
+            .is_ok()
+    }
+
+    /// Deriva las claves de sesión de una conexión.
+    ///
+    /// # Argumentos
+    ///
+    /// * `role` - Extremo de la conexión para el que se derivan las claves.
+    /// * `initiator` - Desafío de quien inició la conexión.
+    /// * `acceptor` - Desafío de quien la aceptó.
+    pub fn session(&self, role: Role, initiator: &Uuid, acceptor: &Uuid) -> SessionKeys {
+        let key = |sender: Role| -> [u8; KEY_SIZE] {
+            let mut label = b"frames from ".to_vec();
+            label.extend_from_slice(sender.label());
+            self.transcript(&label, initiator, acceptor)
+                .finalize()
+                .into_bytes()
+                .into()
+        };
+        SessionKeys {
+            send: key(role),
+            receive: key(role.peer()),
+        }
+    }
+
+    /// Firma la anotación de un robot en el registro con un desafío del registro.
+    ///
+    /// # Argumentos
+    ///
+    /// * `challenge` - Desafío entregado por el registro.
+    /// * `robot` - Anotación a firmar.
+    pub fn registration_proof(&self, challenge: &Uuid, robot: &RobotRecord) -> Vec<u8> {
+        self.registration(challenge, robot)
+            .finalize()
+            .into_bytes()
+            .to_vec()
+    }
+
+    /// Verifica, en tiempo constante, la firma de una anotación en el registro.
+    ///
+    /// # Argumentos
+    ///
+    /// * `challenge` - Desafío entregado por el registro.
+    /// * `robot` - Anotación recibida.
+    /// * `proof` - Firma recibida.
+    pub fn verify_registration(&self, challenge: &Uuid, robot: &RobotRecord, proof: &[u8]) -> bool {
+        self.registration(challenge, robot)
+            .verify_slice(proof)
+            .is_ok()
+    }
+
+    fn registration(&self, challenge: &Uuid, robot: &RobotRecord) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.0).expect("HMAC accepts keys of any size");
+        mac.update(b"registration");
+        mac.update(challenge.as_bytes());
+        for address in [&robot.robot_address, &robot.screen_address] {
+            mac.update(&(address.len() as u64).to_be_bytes());
+            mac.update(address.as_bytes());
+        }
+        mac.update(&[robot.is_leader as u8]);
+        mac
+    }
+
+    fn transcript(&self, label: &[u8], initiator: &Uuid, acceptor: &Uuid) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.0).expect("HMAC accepts keys of any size");
+        mac.update(label);
+        mac.update(initiator.as_bytes());
+        mac.update(acceptor.as_bytes());
+        mac
+    }
+}
+
+/// Claves con las que un extremo firma los frames que envía y verifica los que recibe.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SessionKeys {
+    send: [u8; KEY_SIZE],
+    receive: [u8; KEY_SIZE],
+}
+
+impl fmt::Debug for SessionKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SessionKeys(..)")
+    }
+}
+
+/// Frame firmado: el `Envelope` serializado con el codec de la conexión, su número de
+/// secuencia y la firma de ambos. En los codecs de texto el cuerpo y la firma viajan en base64.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SignedFrame {
+    pub sequence: u64,
+    #[serde(with = "super::base64_bytes")]
+    pub body: Vec<u8>,
+    #[serde(with = "super::base64_bytes")]
+    pub tag: Vec<u8>,
+}
+
+/// `FrameAuthenticator` firma los frames salientes de una conexión y verifica los entrantes,
+/// llevando la secuencia de cada sentido para que no se puedan repetir ni reordenar frames.
+#[derive(Debug, Clone)]
+pub struct FrameAuthenticator {
+    keys: SessionKeys,
+    next_send: u64,
+    next_receive: u64,
+}
+
+impl FrameAuthenticator {
+    /// Crea el autenticador de una conexión recién negociada.
+    ///
+    /// # Argumentos
+    ///
+    /// * `keys` - Claves de sesión derivadas en el handshake.
+    pub fn new(keys: SessionKeys) -> Self {
+        Self {
+            keys,
+            next_send: 0,
+            next_receive: 0,
+        }
+    }
+
+    /// Firma el próximo frame a enviar.
+    ///
+    /// # Argumentos
+    ///
+    /// * `body` - `Envelope` ya serializado.
+    pub fn seal(&mut self, body: Vec<u8>) -> SignedFrame {
+        let sequence = self.next_send;
+        self.next_send += 1;
+        let tag = frame_mac(&self.keys.send, sequence, &body)
+            .finalize()
+            .into_bytes()
+            .to_vec();
+        SignedFrame {
+            sequence,
+            body,
+            tag,
+        }
+    }
+
+    /// Verifica un frame recibido. Un frame rechazado no avanza la secuencia esperada.
+    ///
+    /// # Argumentos
+    ///
+    /// * `frame` - Frame firmado recibido del par.
+    ///
+    /// # Retorna
+    ///
+    /// El `Envelope` serializado, o un `String` con el motivo del rechazo.
+    pub fn open(&mut self, frame: SignedFrame) -> Result<Vec<u8>, String> {
+        if frame.sequence != self.next_receive {
+            return Err(format!(
+                "unauthenticated frame: expected sequence {}, got {}",
+                self.next_receive, frame.sequence
+            ));
+        }
+        frame_mac(&self.keys.receive, frame.sequence, &frame.body)
+            .verify_slice(&frame.tag)
+            .map_err(|_| "unauthenticated frame: invalid signature".to_string())?;
+        self.next_receive += 1;
+        Ok(frame.body)
+    }
+}
+
+fn frame_mac(key: &[u8; KEY_SIZE], sequence: u64, body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(&sequence.to_be_bytes());
+    mac.update(body);
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sessions(
+        initiator_secret: &str,
+        acceptor_secret: &str,
+    ) -> (FrameAuthenticator, FrameAuthenticator) {
+        let (initiator, acceptor) = (SecretKey::challenge(), SecretKey::challenge());
+        (
+            FrameAuthenticator::new(SecretKey::new(initiator_secret).session(
+                Role::Initiator,
+                &initiator,
+                &acceptor,
+            )),
+            FrameAuthenticator::new(SecretKey::new(acceptor_secret).session(
+                Role::Acceptor,
+                &initiator,
+                &acceptor,
+            )),
+        )
+    }
+
+    #[test]
+    fn test_proofs_depend_on_secret_role_and_challenges() {
+        let secret = SecretKey::new("helado");
+        let (initiator, acceptor) = (SecretKey::challenge(), SecretKey::challenge());
+        let proof = secret.proof(Role::Acceptor, &initiator, &acceptor);
+
+        assert!(secret.verify_proof(Role::Acceptor, &initiator, &acceptor, &proof));
+        assert!(!secret.verify_proof(Role::Initiator, &initiator, &acceptor, &proof));
+        assert!(!secret.verify_proof(Role::Acceptor, &acceptor, &initiator, &proof));
+        assert!(!SecretKey::new("otro").verify_proof(
+            Role::Acceptor,
+            &initiator,
+            &acceptor,
+            &proof
+        ));
+    }
+
+    #[test]
+    fn test_frames_are_verified_in_sequence() {
+        let (mut initiator, mut acceptor) = sessions("helado", "helado");
+        let first = initiator.seal(b"first".to_vec());
+        let second = initiator.seal(b"second".to_vec());
+
+        assert!(acceptor.open(second.clone()).is_err());
+        assert_eq!(acceptor.open(first.clone()), Ok(b"first".to_vec()));
+        assert!(acceptor.open(first).is_err());
+
+        let mut tampered = second.clone();
+        tampered.body = b"tampered".to_vec();
+        assert!(acceptor.open(tampered).is_err());
+        assert_eq!(acceptor.open(second), Ok(b"second".to_vec()));
+
+        // Un frame propio reflejado no vale en el otro sentido.
+        let reflected = acceptor.seal(b"reply".to_vec());
+        assert!(acceptor.open(reflected.clone()).is_err());
+        assert_eq!(initiator.open(reflected), Ok(b"reply".to_vec()));
+    }
+
+    #[test]
+    fn test_frames_signed_with_another_secret_are_rejected() {
+        let (mut initiator, mut acceptor) = sessions("helado", "otro");
+
+        assert!(acceptor.open(initiator.seal(b"order".to_vec())).is_err());
+    }
+
+    #[test]
+    fn test_signed_frames_carry_bytes_as_base64_in_json() {
+        let (mut initiator, _) = sessions("helado", "helado");
+        let frame = initiator.seal(b"order".to_vec());
+
+        let json = serde_json::to_value(&frame).unwrap();
+        assert_eq!(json["body"], "b3JkZXI=");
+        assert!(json["tag"].is_string());
+        assert_eq!(serde_json::from_value::<SignedFrame>(json).unwrap(), frame);
+
+        let binary = bincode::serialize(&frame).unwrap();
+        assert_eq!(bincode::deserialize::<SignedFrame>(&binary).unwrap(), frame);
+    }
+}
@@ -0,0 +1,366 @@
+//! Codecs con los que se delimitan y serializan los mensajes sobre un socket.
+//!
+//! Se soportan dos formatos:
+//! - `JsonLines`: un JSON por línea, terminado en `\n`. Es el formato del handshake.
+//! - `LengthPrefixed`: un largo de 4 bytes big-endian seguido del mensaje en bincode.
+//!
+//! El formato de cada conexión se negocia durante el handshake.
+
+use std::{fmt, io, time::Duration};
+
+use bytes::{Buf, BytesMut};
+use futures_util::StreamExt;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::{io::AsyncRead, time::timeout};
+use tokio_util::codec::{Decoder, FramedRead};
+
+use super::auth::SessionKeys;
+
+/// Tamaño máximo, en bytes, de un frame. Un par que lo supere es desconectado.
+pub const MAX_FRAME_SIZE: usize = 64 * 1024;
+
+/// Largo del prefijo de `LengthPrefixed`.
+const LENGTH_PREFIX_SIZE: usize = 4;
+
+/// Formato de los frames de una conexión.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CodecKind {
+    JsonLines,
+    LengthPrefixed,
+}
+
+impl fmt::Display for CodecKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecKind::JsonLines => write!(f, "json-lines"),
+            CodecKind::LengthPrefixed => write!(f, "length-prefixed"),
+        }
+    }
+}
+
+/// Errores al delimitar o (de)serializar un frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodecError {
+    /// Error de entrada/salida sobre el socket.
+    Io(String),
+    /// El par anunció o envió un frame mayor al permitido.
+    FrameTooLarge { size: usize, max: usize },
+    /// El contenido del frame no es un mensaje válido.
+    Malformed(String),
+    /// No llegó un frame completo dentro del plazo.
+    TimedOut(Duration),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Io(error) => write!(f, "io error: {}", error),
+            CodecError::FrameTooLarge { size, max } => {
+                write!(f, "frame of {} bytes exceeds the maximum of {}", size, max)
+            }
+            CodecError::Malformed(error) => write!(f, "malformed frame: {}", error),
+            CodecError::TimedOut(limit) => {
+                write!(f, "no frame received within {} ms", limit.as_millis())
+            }
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl From<io::Error> for CodecError {
+    fn from(error: io::Error) -> Self {
+        CodecError::Io(error.to_string())
+    }
+}
+
+/// Codec negociado para una conexión. Implementa `Decoder` para separar los frames
+/// recibidos y permite (de)serializar los mensajes que viajan en ellos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameCodec {
+    kind: CodecKind,
+    max_frame_size: usize,
+    /// Claves con las que se firman los frames, si la conexión se autenticó en el handshake.
+    session: Option<SessionKeys>,
+}
+
+impl FrameCodec {
+    /// Crea un codec del tipo dado con el tamaño máximo de frame por defecto.
+    ///
+    /// # Argumentos
+    ///
+    /// * `kind` - Formato de los frames.
+    pub fn new(kind: CodecKind) -> Self {
+        Self {
+            kind,
+            max_frame_size: MAX_FRAME_SIZE,
+            session: None,
+        }
+    }
+
+    /// Cambia el tamaño máximo de frame aceptado.
+    ///
+    /// # Argumentos
+    ///
+    /// * `max_frame_size` - Tamaño máximo, en bytes, del contenido de un frame.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Asocia al codec las claves de sesión negociadas en el handshake.
+    ///
+    /// # Argumentos
+    ///
+    /// * `session` - Claves con las que se firman y verifican los frames.
+    pub fn with_session(mut self, session: SessionKeys) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    /// Formato de los frames de este codec.
+    pub fn kind(&self) -> CodecKind {
+        self.kind
+    }
+
+    /// Claves de sesión de la conexión, o `None` si no se autenticó.
+    pub fn session(&self) -> Option<SessionKeys> {
+        self.session
+    }
+
+    /// Envuelve la mitad de lectura de un socket en un stream de frames.
+    ///
+    /// # Argumentos
+    ///
+    /// * `reader` - Lector del socket, posicionado después del handshake.
+    pub fn framed_read<R: AsyncRead>(self, reader: R) -> FramedRead<R, Self> {
+        FramedRead::new(reader, self)
+    }
+
+    /// Espera el próximo frame de un stream creado con `framed_read`, como mucho `limit`.
+    ///
+    /// # Argumentos
+    ///
+    /// * `frames` - Stream de frames del socket.
+    /// * `limit` - Tiempo máximo de espera.
+    ///
+    /// # Retorna
+    ///
+    /// El contenido del frame, `None` si el par cerró la conexión, o el `CodecError`
+    /// correspondiente (`CodecError::TimedOut` si no llegó a tiempo).
+    pub async fn next_frame<R: AsyncRead + Unpin>(
+        frames: &mut FramedRead<R, Self>,
+        limit: Duration,
+    ) -> Result<Option<Vec<u8>>, CodecError> {
+        match timeout(limit, frames.next()).await {
+            Ok(frame) => frame.transpose(),
+            Err(_) => Err(CodecError::TimedOut(limit)),
+        }
+    }
+
+    /// Serializa un mensaje y le agrega la delimitación del frame.
+    ///
+    /// # Argumentos
+    ///
+    /// * `message` - Mensaje a serializar.
+    ///
+    /// # Retorna
+    ///
+    /// Los bytes listos para escribir en el socket, o el `CodecError` correspondiente.
+    pub fn encode<T: Serialize>(&self, message: &T) -> Result<Vec<u8>, CodecError> {
+        let mut frame = self.serialize(message)?;
+        if frame.len() > self.max_frame_size {
+            return Err(CodecError::FrameTooLarge {
+                size: frame.len(),
+                max: self.max_frame_size,
+            });
+        }
+        match self.kind {
+            CodecKind::JsonLines => {
+                frame.push(b'\n');
+                Ok(frame)
+            }
+            CodecKind::LengthPrefixed => {
+                let mut prefixed = (frame.len() as u32).to_be_bytes().to_vec();
+                prefixed.append(&mut frame);
+                Ok(prefixed)
+            }
+        }
+    }
+
+    /// Serializa un mensaje sin delimitarlo, como el contenido de un frame.
+    ///
+    /// # Argumentos
+    ///
+    /// * `message` - Mensaje a serializar.
+    pub fn serialize<T: Serialize>(&self, message: &T) -> Result<Vec<u8>, CodecError> {
+        match self.kind {
+            CodecKind::JsonLines => serde_json::to_vec(message)
+                .map_err(|error| CodecError::Malformed(error.to_string())),
+            CodecKind::LengthPrefixed => bincode::serialize(message)
+                .map_err(|error| CodecError::Malformed(error.to_string())),
+        }
+    }
+
+    /// Deserializa el contenido de un frame recibido.
+    ///
+    /// # Argumentos
+    ///
+    /// * `frame` - Contenido del frame, tal como lo devuelve el `Decoder`.
+    ///
+    /// # Retorna
+    ///
+    /// El mensaje deserializado, o un `CodecError::Malformed`.
+    pub fn decode<T: DeserializeOwned>(&self, frame: &[u8]) -> Result<T, CodecError> {
+        match self.kind {
+            CodecKind::JsonLines => serde_json::from_slice(frame)
+                .map_err(|error| CodecError::Malformed(error.to_string())),
+            CodecKind::LengthPrefixed => bincode::deserialize(frame)
+                .map_err(|error| CodecError::Malformed(error.to_string())),
+        }
+    }
+
+    fn decode_line(&self, src: &mut BytesMut) -> Result<Option<Vec<u8>>, CodecError> {
+        match src.iter().position(|byte| *byte == b'\n') {
+            Some(end) if end > self.max_frame_size => Err(CodecError::FrameTooLarge {
+                size: end,
+                max: self.max_frame_size,
+            }),
+            Some(end) => {
+                let line = src.split_to(end + 1);
+                let line = line.strip_suffix(b"\n").unwrap_or(&line);
+                Ok(Some(line.strip_suffix(b"\r").unwrap_or(line).to_vec()))
+            }
+            None if src.len() > self.max_frame_size => Err(CodecError::FrameTooLarge {
+                size: src.len(),
+                max: self.max_frame_size,
+            }),
+            None => Ok(None),
+        }
+    }
+
+    fn decode_length_prefixed(&self, src: &mut BytesMut) -> Result<Option<Vec<u8>>, CodecError> {
+        if src.len() < LENGTH_PREFIX_SIZE {
+            return Ok(None);
+        }
+        let mut prefix = [0u8; LENGTH_PREFIX_SIZE];
+        prefix.copy_from_slice(&src[..LENGTH_PREFIX_SIZE]);
+        let size = u32::from_be_bytes(prefix) as usize;
+        if size > self.max_frame_size {
+            return Err(CodecError::FrameTooLarge {
+                size,
+                max: self.max_frame_size,
+            });
+        }
+        if src.len() < LENGTH_PREFIX_SIZE + size {
+            src.reserve(LENGTH_PREFIX_SIZE + size - src.len());
+            return Ok(None);
+        }
+        src.advance(LENGTH_PREFIX_SIZE);
+        Ok(Some(src.split_to(size).to_vec()))
+    }
+}
+
+impl Decoder for FrameCodec {
+    type Item = Vec<u8>;
+    type Error = CodecError;
+
+    /// Separa el próximo frame completo del buffer. Un frame mayor al máximo permitido
+    /// es un error y termina el stream.
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.kind {
+            CodecKind::JsonLines => self.decode_line(src),
+            CodecKind::LengthPrefixed => self.decode_length_prefixed(src),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::NodeClock;
+    use crate::communication::{envelope::Envelope, order_id::OrderId, shop_requests::ShopRequest};
+
+    fn order() -> Envelope<ShopRequest> {
+        Envelope::new(
+            10001,
+            NodeClock::new("robot-10001", true).send(),
+            ShopRequest::IceCreamOrder {
+                order_id: OrderId::new(),
+                flavors: vec!["dulce\nde leche".to_string()],
+                size: 250,
+                screen_id: 3,
+            },
+        )
+    }
+
+    fn round_trip(kind: CodecKind) {
+        let mut codec = FrameCodec::new(kind);
+        let order = order();
+        let mut buffer = BytesMut::from(&codec.encode(&order).unwrap()[..]);
+        buffer.extend_from_slice(&codec.encode(&order).unwrap());
+
+        for _ in 0..2 {
+            let frame = Decoder::decode(&mut codec, &mut buffer).unwrap().unwrap();
+            assert_eq!(
+                codec.decode::<Envelope<ShopRequest>>(&frame).unwrap(),
+                order
+            );
+        }
+        assert_eq!(Decoder::decode(&mut codec, &mut buffer).unwrap(), None);
+    }
+
+    #[test]
+    fn test_json_lines_round_trip() {
+        round_trip(CodecKind::JsonLines);
+    }
+
+    #[test]
+    fn test_length_prefixed_round_trip() {
+        round_trip(CodecKind::LengthPrefixed);
+    }
+
+    #[test]
+    fn test_partial_frame_waits_for_more_bytes() {
+        let mut codec = FrameCodec::new(CodecKind::LengthPrefixed);
+        let encoded = codec.encode(&order()).unwrap();
+        let mut buffer = BytesMut::from(&encoded[..encoded.len() - 1]);
+
+        assert_eq!(Decoder::decode(&mut codec, &mut buffer).unwrap(), None);
+        buffer.extend_from_slice(&encoded[encoded.len() - 1..]);
+        assert!(Decoder::decode(&mut codec, &mut buffer).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_oversized_frames_are_rejected() {
+        let mut codec = FrameCodec::new(CodecKind::LengthPrefixed).with_max_frame_size(16);
+        let mut buffer = BytesMut::from(&1024u32.to_be_bytes()[..]);
+        assert_eq!(
+            Decoder::decode(&mut codec, &mut buffer),
+            Err(CodecError::FrameTooLarge {
+                size: 1024,
+                max: 16
+            })
+        );
+
+        let mut codec = FrameCodec::new(CodecKind::JsonLines).with_max_frame_size(16);
+        let mut buffer = BytesMut::from(&[b'a'; 17][..]);
+        assert!(matches!(
+            Decoder::decode(&mut codec, &mut buffer),
+            Err(CodecError::FrameTooLarge { .. })
+        ));
+        assert!(matches!(
+            codec.encode(&order()),
+            Err(CodecError::FrameTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_malformed_payload_is_reported() {
+        let codec = FrameCodec::new(CodecKind::JsonLines);
+        assert!(matches!(
+            codec.decode::<Envelope<ShopRequest>>(b"not json"),
+            Err(CodecError::Malformed(_))
+        ));
+    }
+}
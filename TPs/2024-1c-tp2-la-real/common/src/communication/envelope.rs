@@ -0,0 +1,58 @@
+//! Sobre tipado con el que viaja cada mensaje una vez terminado el handshake.
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock::Timestamp;
+
+/// `Envelope` envuelve un mensaje tipado (`ShopRequest`, `Request`, `RobotRequest`, ...)
+/// junto con el id del nodo que lo emitió y su reloj lógico. Se serializa con el
+/// `FrameCodec` negociado.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Envelope<T> {
+    /// Id del nodo que envía el mensaje, el mismo que declaró en el handshake.
+    pub sender: usize,
+    /// Reloj del nodo al enviar el mensaje.
+    pub clock: Timestamp,
+    /// Mensaje transportado.
+    pub payload: Payload<T>,
+}
+
+/// Contenido de un `Envelope`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum Payload<T> {
+    /// Mensaje para el dueño de la conexión.
+    Message(T),
+    /// Señal de vida del par, que la conexión consume sin reenviarla.
+    Heartbeat,
+}
+
+impl<T> Envelope<T> {
+    /// Crea un nuevo `Envelope`.
+    ///
+    /// # Argumentos
+    ///
+    /// * `sender` - Id del nodo que envía el mensaje.
+    /// * `clock` - Reloj del nodo, ya avanzado por el envío.
+    /// * `payload` - Mensaje a transportar.
+    pub fn new(sender: usize, clock: Timestamp, payload: T) -> Self {
+        Self {
+            sender,
+            clock,
+            payload: Payload::Message(payload),
+        }
+    }
+
+    /// Crea un `Envelope` con un heartbeat. Los heartbeats no avanzan el reloj del par.
+    ///
+    /// # Argumentos
+    ///
+    /// * `sender` - Id del nodo que envía el heartbeat.
+    /// * `clock` - Reloj actual del nodo.
+    pub fn heartbeat(sender: usize, clock: Timestamp) -> Self {
+        Self {
+            sender,
+            clock,
+            payload: Payload::Heartbeat,
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Detectores de fallas para los extremos de una conexión.
+//!
+//! Cada `FramedConnection` registra en su `FailureDetector` la llegada de cada frame del par
+//! y le pregunta periódicamente si debe sospechar de él. Cualquier frame prueba que el par está
+//! vivo, pero solo los heartbeats, que llegan a ritmo constante, se toman como muestras de los
+//! intervalos esperados, para que una ráfaga de mensajes no los achique. Así un par ocupado en
+//! enviar mensajes, que puede no tener lugar en su cola para los heartbeats, no es sospechado.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::config::{DetectorKind, HeartbeatConfig};
+
+/// Cantidad de intervalos entre llegadas que recuerda el detector phi accrual.
+const MAX_SAMPLES: usize = 100;
+/// Desvío estándar mínimo, en milisegundos, para que un par muy regular no sea
+/// sospechado ante la menor demora.
+const MIN_STD_DEVIATION: f64 = 100.0;
+
+/// `FailureDetector` decide, a partir de las llegadas de frames de un par, si hay que
+/// sospechar que dejó de responder.
+#[derive(Debug, Clone)]
+pub struct FailureDetector {
+    kind: DetectorKind,
+    timeout: Duration,
+    phi_threshold: f64,
+    /// Última llegada de un frame cualquiera del par.
+    last_arrival: Instant,
+    /// Última llegada de un heartbeat del par.
+    last_heartbeat: Instant,
+    /// Milisegundos entre heartbeats consecutivos, el más reciente al final.
+    intervals: VecDeque<f64>,
+}
+
+impl FailureDetector {
+    /// Crea un detector para un par del que se acaba de tener noticia.
+    ///
+    /// # Argumentos
+    ///
+    /// * `config` - Configuración de los heartbeats. El intervalo configurado se toma como
+    ///   primera muestra del detector phi accrual.
+    /// * `now` - Momento de creación, tomado como la última llegada.
+    pub fn new(config: &HeartbeatConfig, now: Instant) -> Self {
+        Self {
+            kind: config.detector,
+            timeout: Duration::from_millis(config.timeout),
+            phi_threshold: config.phi_threshold,
+            last_arrival: now,
+            last_heartbeat: now,
+            intervals: VecDeque::from([config.interval as f64]),
+        }
+    }
+
+    /// Registra la llegada de un heartbeat del par.
+    pub fn heartbeat(&mut self, now: Instant) {
+        let interval = now.duration_since(self.last_heartbeat).as_secs_f64() * 1000.0;
+        if self.intervals.len() == MAX_SAMPLES {
+            self.intervals.pop_front();
+        }
+        self.intervals.push_back(interval);
+        self.last_heartbeat = now;
+        self.last_arrival = now;
+    }
+
+    /// Registra la llegada de un mensaje del par, que no es una muestra de los intervalos.
+    pub fn message(&mut self, now: Instant) {
+        self.last_arrival = now;
+    }
+
+    /// Valor phi del par: `-log10` de la probabilidad de que un par vivo tarde más de lo que
+    /// ya tardó, estimada con una distribución normal de los intervalos observados.
+    pub fn phi(&self, now: Instant) -> f64 {
+        let elapsed = now.duration_since(self.last_arrival).as_secs_f64() * 1000.0;
+        let samples = self.intervals.len() as f64;
+        let mean = self.intervals.iter().sum::<f64>() / samples;
+        let variance = self
+            .intervals
+            .iter()
+            .map(|interval| (interval - mean).powi(2))
+            .sum::<f64>()
+            / samples;
+        let std_deviation = variance.sqrt().max(MIN_STD_DEVIATION);
+
+        // Aproximación logística de la función de distribución normal.
+        let y = (elapsed - mean) / std_deviation;
+        let e = (-y * (1.5976 + 0.070566 * y * y)).exp();
+        if elapsed > mean {
+            -(e / (1.0 + e)).log10()
+        } else {
+            -(1.0 - 1.0 / (1.0 + e)).log10()
+        }
+    }
+
+    /// Indica si hay que sospechar del par.
+    ///
+    /// # Retorna
+    ///
+    /// El motivo de la sospecha, o `None` si el par se considera vivo.
+    pub fn suspicion(&self, now: Instant) -> Option<String> {
+        let elapsed = now.duration_since(self.last_arrival);
+        match self.kind {
+            DetectorKind::Timeout if elapsed >= self.timeout => {
+                Some(format!("nothing received for {} ms", elapsed.as_millis()))
+            }
+            DetectorKind::PhiAccrual => {
+                let phi = self.phi(now);
+                if phi >= self.phi_threshold {
+                    Some(format!(
+                        "nothing received for {} ms (phi {:.1})",
+                        elapsed.as_millis(),
+                        phi
+                    ))
+                } else {
+                    None
+                }
+            }
+            DetectorKind::Timeout => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(detector: DetectorKind) -> HeartbeatConfig {
+        HeartbeatConfig {
+            detector,
+            ..HeartbeatConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_timeout_detector_suspects_after_timeout() {
+        let start = Instant::now();
+        let mut detector = FailureDetector::new(&config(DetectorKind::Timeout), start);
+        detector.heartbeat(start + Duration::from_millis(1000));
+
+        assert!(detector
+            .suspicion(start + Duration::from_millis(5900))
+            .is_none());
+        assert!(detector
+            .suspicion(start + Duration::from_millis(6000))
+            .is_some());
+    }
+
+    #[test]
+    fn test_phi_grows_with_silence() {
+        let start = Instant::now();
+        let mut detector = FailureDetector::new(&config(DetectorKind::PhiAccrual), start);
+        for beat in 1..=10 {
+            detector.heartbeat(start + Duration::from_millis(beat * 1000));
+        }
+        let last = start + Duration::from_millis(10_000);
+
+        let on_time = detector.phi(last + Duration::from_millis(1000));
+        let late = detector.phi(last + Duration::from_millis(1300));
+        assert!(on_time < 1.0);
+        assert!(late > on_time);
+        assert!(detector
+            .suspicion(last + Duration::from_millis(1200))
+            .is_none());
+        assert!(detector
+            .suspicion(last + Duration::from_millis(2000))
+            .is_some());
+    }
+
+    #[test]
+    fn test_messages_keep_the_peer_alive_without_sampling() {
+        let start = Instant::now();
+        let mut detector = FailureDetector::new(&config(DetectorKind::PhiAccrual), start);
+        for beat in 1..=10 {
+            detector.heartbeat(start + Duration::from_millis(beat * 1000));
+        }
+        for message in 1..=50 {
+            detector.message(start + Duration::from_millis(10_000 + message * 100));
+        }
+        let last = start + Duration::from_millis(15_000);
+
+        assert!(detector.phi(last + Duration::from_millis(1000)) < 1.0);
+        assert!(detector
+            .suspicion(last + Duration::from_millis(1200))
+            .is_none());
+        assert!(detector
+            .suspicion(last + Duration::from_millis(2000))
+            .is_some());
+    }
+}
@@ -0,0 +1,778 @@
+//! Actor genérico que encapsula un socket ya negociado.
+//!
+//! Un `FramedConnection<In, Out>` lee frames del socket, los deserializa como
+//! `Envelope<In>` y se los reenvía a su dueño como `ConnectionEvent`s. En sentido
+//! contrario, recibe `SendMessageThroughSocket<Out>` y los escribe en orden a través
+//! de una cola acotada: si la cola está llena el envío se rechaza en lugar de acumular
+//! mensajes sin límite. Los errores de escritura y la desconexión del par también se
+//! informan al dueño como eventos.
+//!
+//! Si se le configuran heartbeats, la conexión envía uno por intervalo y vigila al par con un
+//! `FailureDetector`, al que le cuenta cada frame recibido: si la cola está llena el heartbeat
+//! se omite, pero los frames encolados también prueban que el nodo está vivo. Un par sospechado
+//! se trata como uno desconectado: la conexión se cierra y el dueño recibe
+//! `SocketEvent::Disconnected` con el motivo de la sospecha.
+//!
+//! Si el codec trae claves de sesión, cada frame viaja como un `SignedFrame`. Los frames
+//! recibidos con una firma inválida o fuera de secuencia se descartan y se informan al dueño
+//! como `SocketEvent::Malformed`.
+//!
+//! Cada mensaje enviado avanza el `NodeClock` de la conexión, el del proceso salvo que se
+//! indique otro, y cada mensaje recibido lo adelanta al reloj que trae su `Envelope`.
+
+use std::{
+    collections::VecDeque,
+    marker::PhantomData,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use actix::{
+    fut::wrap_future, Actor, ActorContext, ActorFutureExt, Addr, AsyncContext, Context, Handler,
+    Message, Recipient, StreamHandler,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+
+use super::{
+    auth::{FrameAuthenticator, SignedFrame},
+    codec::{CodecError, FrameCodec},
+    envelope::{Envelope, Payload},
+    failure_detector::FailureDetector,
+    handshake::Handshake,
+};
+use crate::{clock::NodeClock, config::HeartbeatConfig};
+
+/// Cantidad máxima por defecto de frames esperando ser escritos en el socket.
+pub const DEFAULT_QUEUE_CAPACITY: usize = 256;
+
+type Writer = Box<dyn AsyncWrite + Unpin>;
+
+/// Actor que maneja un socket por el que se reciben mensajes `In` y se envían mensajes `Out`.
+pub struct FramedConnection<In, Out>
+where
+    In: DeserializeOwned + Send + 'static,
+    Out: Serialize + Send + 'static,
+{
+    /// Mitad de escritura del socket. Es `None` mientras hay una escritura en curso.
+    writer: Option<Writer>,
+    codec: FrameCodec,
+    local_id: usize,
+    peer: Handshake,
+    peer_addr: SocketAddr,
+    owner: Recipient<ConnectionEvent<In, Out>>,
+    queue: VecDeque<Vec<u8>>,
+    queue_capacity: usize,
+    closing: bool,
+    close_reason: Option<String>,
+    heartbeat: Option<HeartbeatConfig>,
+    detector: Option<FailureDetector>,
+    authenticator: Option<FrameAuthenticator>,
+    clock: NodeClock,
+    _messages: PhantomData<fn(In) -> Out>,
+}
+
+/// Lo que un `FramedConnection` le informa a su dueño.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SocketEvent<In> {
+    /// Se recibió un mensaje del par.
+    Received(In),
+    /// Se descartó un frame que no se pudo deserializar o que no pasó la autenticación.
+    Malformed(String),
+    /// Falló una escritura en el socket. La conexión se cierra a continuación.
+    WriteFailed(String),
+    /// La conexión se cerró, con el motivo si fue por un error.
+    Disconnected(Option<String>),
+}
+
+/// Mensaje que recibe el dueño de un `FramedConnection` por cada evento del socket.
+pub struct ConnectionEvent<In, Out>
+where
+    In: DeserializeOwned + Send + 'static,
+    Out: Serialize + Send + 'static,
+{
+    /// Dirección de la conexión que generó el evento, para poder responderle.
+    pub connection: Addr<FramedConnection<In, Out>>,
+    /// Handshake del par.
+    pub peer: Handshake,
+    /// Dirección del socket del par.
+    pub peer_addr: SocketAddr,
+    pub event: SocketEvent<In>,
+}
+
+impl<In, Out> Message for ConnectionEvent<In, Out>
+where
+    In: DeserializeOwned + Send + 'static,
+    Out: Serialize + Send + 'static,
+{
+    type Result = ();
+}
+
+/// Mensaje para enviar datos a través del socket.
+pub struct SendMessageThroughSocket<Out> {
+    pub message_to_send_through_socket: Out,
+}
+
+impl<Out: Send + 'static> Message for SendMessageThroughSocket<Out> {
+    type Result = Result<(), String>;
+}
+
+/// Mensaje para cerrar la conexión una vez escritos los mensajes pendientes.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct CloseConnection;
+
+impl<In, Out> FramedConnection<In, Out>
+where
+    In: DeserializeOwned + Send + 'static,
+    Out: Serialize + Send + 'static,
+{
+    /// Crea una nueva conexión sobre un socket que ya completó el handshake.
+    ///
+    /// # Argumentos
+    ///
+    /// * `writer` - Mitad de escritura del socket.
+    /// * `codec` - Codec negociado en el handshake.
+    /// * `local_id` - ID de este nodo, usado como remitente de los mensajes.
+    /// * `peer` - Handshake del par.
+    /// * `peer_addr` - Dirección del socket del par.
+    /// * `owner` - Destinatario de los eventos de la conexión.
+    pub fn new<W>(
+        writer: W,
+        codec: FrameCodec,
+        local_id: usize,
+        peer: Handshake,
+        peer_addr: SocketAddr,
+        owner: Recipient<ConnectionEvent<In, Out>>,
+    ) -> Self
+    where
+        W: AsyncWrite + Unpin + 'static,
+    {
+        Self {
+            writer: Some(Box::new(writer)),
+            codec,
+            local_id,
+            peer,
+            peer_addr,
+            owner,
+            queue: VecDeque::new(),
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            closing: false,
+            close_reason: None,
+            heartbeat: None,
+            detector: None,
+            authenticator: codec.session().map(FrameAuthenticator::new),
+            clock: NodeClock::global(),
+            _messages: PhantomData,
+        }
+    }
+
+    /// Cambia la cantidad máxima de frames esperando ser escritos.
+    ///
+    /// # Argumentos
+    ///
+    /// * `queue_capacity` - Cantidad máxima de frames en cola.
+    pub fn with_queue_capacity(mut self, queue_capacity: usize) -> Self {
+        self.queue_capacity = queue_capacity;
+        self
+    }
+
+    /// Activa los heartbeats y la detección de fallas del par. Con un intervalo de 0 no tiene efecto.
+    ///
+    /// # Argumentos
+    ///
+    /// * `heartbeat` - Configuración de los heartbeats.
+    pub fn with_heartbeat(mut self, heartbeat: HeartbeatConfig) -> Self {
+        if heartbeat.interval > 0 {
+            self.heartbeat = Some(heartbeat);
+        }
+        self
+    }
+
+    /// Cambia el reloj lógico que avanzan los mensajes de la conexión.
+    ///
+    /// # Argumentos
+    ///
+    /// * `clock` - Reloj del nodo.
+    pub fn with_clock(mut self, clock: NodeClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Inicia el actor leyendo frames de la mitad de lectura del socket.
+    ///
+    /// # Argumentos
+    ///
+    /// * `reader` - Mitad de lectura del socket, posicionada después del handshake.
+    ///
+    /// # Retorna
+    ///
+    /// La dirección del actor.
+    pub fn start_with_reader<R>(self, reader: R) -> Addr<Self>
+    where
+        R: AsyncRead + 'static,
+    {
+        Self::create(|ctx| {
+            ctx.add_stream(self.codec.framed_read(reader));
+            self
+        })
+    }
+
+    fn notify_owner(&self, event: SocketEvent<In>, ctx: &mut Context<Self>) {
+        self.owner.do_send(ConnectionEvent {
+            connection: ctx.address(),
+            peer: self.peer,
+            peer_addr: self.peer_addr,
+            event,
+        });
+    }
+
+    /// Envía un heartbeat al par y cierra la conexión si el detector sospecha de él.
+    fn beat(&mut self, ctx: &mut Context<Self>) {
+        if self.closing {
+            return;
+        }
+        if let Some(suspicion) = self
+            .detector
+            .as_ref()
+            .and_then(|detector| detector.suspicion(Instant::now()))
+        {
+            self.close_reason = Some(format!("peer suspected dead: {}", suspicion));
+            ctx.stop();
+            return;
+        }
+        if self.queue.len() < self.queue_capacity {
+            let heartbeat = Envelope::<Out>::heartbeat(self.local_id, self.clock.now());
+            if let Ok(frame) = self.encode(&heartbeat) {
+                self.queue.push_back(frame);
+                self.flush(ctx);
+            }
+        }
+    }
+
+    /// Serializa un `Envelope` como frame, firmándolo si la conexión está autenticada.
+    fn encode(&mut self, envelope: &Envelope<Out>) -> Result<Vec<u8>, CodecError> {
+        match &mut self.authenticator {
+            Some(authenticator) => {
+                let body = self.codec.serialize(envelope)?;
+                self.codec.encode(&authenticator.seal(body))
+            }
+            None => self.codec.encode(envelope),
+        }
+    }
+
+    /// Deserializa un frame recibido, verificando su firma si la conexión está autenticada.
+    fn decode(&mut self, frame: &[u8]) -> Result<Envelope<In>, String> {
+        match &mut self.authenticator {
+            Some(authenticator) => {
+                let signed = self
+                    .codec
+                    .decode::<SignedFrame>(frame)
+                    .map_err(|error| error.to_string())?;
+                let body = authenticator.open(signed)?;
+                self.codec.decode(&body).map_err(|error| error.to_string())
+            }
+            None => self.codec.decode(frame).map_err(|error| error.to_string()),
+        }
+    }
+
+    /// Escribe el próximo frame de la cola si no hay otra escritura en curso.
+    fn flush(&mut self, ctx: &mut Context<Self>) {
+        if self.writer.is_none() {
+            return;
+        }
+        let frame = match self.queue.pop_front() {
+            Some(frame) => frame,
+            None => {
+                if self.closing {
+                    ctx.stop();
+                }
+                return;
+            }
+        };
+        if let Some(mut writer) = self.writer.take() {
+            let write = async move {
+                let result = writer.write_all(&frame).await;
+                (writer, result)
+            };
+            ctx.spawn(
+                wrap_future::<_, Self>(write).map(|(writer, result), act, ctx| {
+                    act.writer = Some(writer);
+                    match result {
+                        Ok(()) => act.flush(ctx),
+                        Err(error) => {
+                            act.notify_owner(SocketEvent::WriteFailed(error.to_string()), ctx);
+                            act.close_reason = Some(error.to_string());
+                            ctx.stop();
+                        }
+                    }
+                }),
+            );
+        }
+    }
+}
+
+impl<In, Out> Actor for FramedConnection<In, Out>
+where
+    In: DeserializeOwned + Send + 'static,
+    Out: Serialize + Send + 'static,
+{
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if let Some(heartbeat) = self.heartbeat {
+            self.detector = Some(FailureDetector::new(&heartbeat, Instant::now()));
+            ctx.run_interval(Duration::from_millis(heartbeat.interval), |act, ctx| {
+                act.beat(ctx)
+            });
+        }
+    }
+
+    fn stopped(&mut self, ctx: &mut Self::Context) {
+        let reason = self.close_reason.take();
+        self.notify_owner(SocketEvent::Disconnected(reason), ctx);
+    }
+}
+
+impl<In, Out> StreamHandler<Result<Vec<u8>, CodecError>> for FramedConnection<In, Out>
+where
+    In: DeserializeOwned + Send + 'static,
+    Out: Serialize + Send + 'static,
+{
+    /// Deserializa cada frame recibido, adelanta el reloj y se lo reenvía al dueño. Todo frame se
+    /// registra en el detector de fallas; los heartbeats no se reenvían. Un error de framing cierra
+    /// la conexión.
+    fn handle(&mut self, msg: Result<Vec<u8>, CodecError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(frame) => match self.decode(&frame) {
+                Ok(Envelope {
+                    clock,
+                    payload: Payload::Message(message),
+                    ..
+                }) => {
+                    self.clock.receive(&clock);
+                    if let Some(detector) = &mut self.detector {
+                        detector.message(Instant::now());
+                    }
+                    self.notify_owner(SocketEvent::Received(message), ctx)
+                }
+                Ok(Envelope {
+                    payload: Payload::Heartbeat,
+                    ..
+                }) => {
+                    if let Some(detector) = &mut self.detector {
+                        detector.heartbeat(Instant::now());
+                    }
+                }
+                Err(error) => self.notify_owner(SocketEvent::Malformed(error), ctx),
+            },
+            Err(error) => {
+                self.close_reason = Some(error.to_string());
+                ctx.stop();
+            }
+        }
+    }
+}
+
+impl<In, Out> Handler<SendMessageThroughSocket<Out>> for FramedConnection<In, Out>
+where
+    In: DeserializeOwned + Send + 'static,
+    Out: Serialize + Send + 'static,
+{
+    type Result = Result<(), String>;
+
+    /// Encola un mensaje para escribirlo en el socket.
+    ///
+    /// # Retorna
+    ///
+    /// Un error si la conexión se está cerrando, si el mensaje no se pudo serializar
+    /// o si la cola de salida está llena.
+    fn handle(
+        &mut self,
+        msg: SendMessageThroughSocket<Out>,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        if self.closing {
+            return Err(format!("Connection with {} is closing", self.peer_addr));
+        }
+        if self.queue.len() >= self.queue_capacity {
+            return Err(format!(
+                "Outbound queue for {} is full ({} messages)",
+                self.peer_addr, self.queue_capacity
+            ));
+        }
+        let frame = self
+            .encode(&Envelope::new(
+                self.local_id,
+                self.clock.send(),
+                msg.message_to_send_through_socket,
+            ))
+            .map_err(|error| error.to_string())?;
+        self.queue.push_back(frame);
+        self.flush(ctx);
+        Ok(())
+    }
+}
+
+impl<In, Out> Handler<CloseConnection> for FramedConnection<In, Out>
+where
+    In: DeserializeOwned + Send + 'static,
+    Out: Serialize + Send + 'static,
+{
+    type Result = ();
+
+    fn handle(&mut self, _msg: CloseConnection, ctx: &mut Self::Context) -> Self::Result {
+        self.closing = true;
+        self.flush(ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::communication::{
+        auth::{Role, SecretKey},
+        codec::CodecKind,
+        handshake::NodeKind,
+        robot_to_robot_communication::RobotRequest,
+    };
+    use crate::{clock::Timestamp, config::DetectorKind};
+    use tokio::{
+        io::{duplex, split, AsyncReadExt, DuplexStream},
+        sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+        time::timeout,
+    };
+
+    type RobotConnection = FramedConnection<RobotRequest, RobotRequest>;
+
+    struct Owner {
+        events: UnboundedSender<SocketEvent<RobotRequest>>,
+    }
+
+    impl Actor for Owner {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<ConnectionEvent<RobotRequest, RobotRequest>> for Owner {
+        type Result = ();
+
+        fn handle(
+            &mut self,
+            msg: ConnectionEvent<RobotRequest, RobotRequest>,
+            _ctx: &mut Self::Context,
+        ) {
+            let _ = self.events.send(msg.event);
+        }
+    }
+
+    fn codec() -> FrameCodec {
+        FrameCodec::new(CodecKind::LengthPrefixed)
+    }
+
+    fn start_connection(
+        buffer: usize,
+        queue_capacity: usize,
+    ) -> (
+        Addr<RobotConnection>,
+        DuplexStream,
+        UnboundedReceiver<SocketEvent<RobotRequest>>,
+    ) {
+        let heartbeat = HeartbeatConfig {
+            interval: 0,
+            ..HeartbeatConfig::default()
+        };
+        start_connection_with_heartbeat(buffer, queue_capacity, heartbeat)
+    }
+
+    fn start_connection_with_heartbeat(
+        buffer: usize,
+        queue_capacity: usize,
+        heartbeat: HeartbeatConfig,
+    ) -> (
+        Addr<RobotConnection>,
+        DuplexStream,
+        UnboundedReceiver<SocketEvent<RobotRequest>>,
+    ) {
+        start_connection_with_codec(buffer, queue_capacity, heartbeat, codec(), local_clock())
+    }
+
+    fn local_clock() -> NodeClock {
+        NodeClock::new("robot-10000", false)
+    }
+
+    fn start_connection_with_codec(
+        buffer: usize,
+        queue_capacity: usize,
+        heartbeat: HeartbeatConfig,
+        codec: FrameCodec,
+        clock: NodeClock,
+    ) -> (
+        Addr<RobotConnection>,
+        DuplexStream,
+        UnboundedReceiver<SocketEvent<RobotRequest>>,
+    ) {
+        let (local, remote) = duplex(buffer);
+        let (reader, writer) = split(local);
+        let (events, received) = unbounded_channel();
+        let owner = Owner { events }.start();
+        let connection = RobotConnection::new(
+            writer,
+            codec,
+            10000,
+            Handshake::new(NodeKind::Robot, 10001),
+            "127.0.0.1:10001".parse().unwrap(),
+            owner.recipient(),
+        )
+        .with_queue_capacity(queue_capacity)
+        .with_heartbeat(heartbeat)
+        .with_clock(clock)
+        .start_with_reader(reader);
+        (connection, remote, received)
+    }
+
+    async fn next_event(
+        events: &mut UnboundedReceiver<SocketEvent<RobotRequest>>,
+    ) -> SocketEvent<RobotRequest> {
+        timeout(Duration::from_secs(1), events.recv())
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[actix::test]
+    async fn test_received_frames_are_forwarded_to_the_owner() {
+        let (_connection, mut remote, mut events) = start_connection(1024, 8);
+        let frame = codec()
+            .encode(&Envelope::new(
+                10001,
+                Timestamp::default(),
+                RobotRequest::StartElection {},
+            ))
+            .unwrap();
+        remote.write_all(&frame).await.unwrap();
+        remote.write_all(&[0, 0, 0, 1, 0xff]).await.unwrap();
+
+        assert_eq!(
+            next_event(&mut events).await,
+            SocketEvent::Received(RobotRequest::StartElection {})
+        );
+        assert!(matches!(
+            next_event(&mut events).await,
+            SocketEvent::Malformed(_)
+        ));
+
+        drop(remote);
+        assert_eq!(
+            next_event(&mut events).await,
+            SocketEvent::Disconnected(None)
+        );
+    }
+
+    #[actix::test]
+    async fn test_messages_are_written_in_order() {
+        let (connection, mut remote, _events) = start_connection(1024, 8);
+        let messages = vec![
+            RobotRequest::StartElection {},
+            RobotRequest::LeaderSelected {
+                robot_to_robot_leader_id: 10001,
+                robot_to_screen_leader_id: 9001,
+            },
+        ];
+        for message in &messages {
+            connection
+                .send(SendMessageThroughSocket {
+                    message_to_send_through_socket: message.clone(),
+                })
+                .await
+                .unwrap()
+                .unwrap();
+        }
+
+        let clock = local_clock();
+        let mut expected = Vec::new();
+        for message in messages {
+            let envelope = Envelope::new(10000, clock.send(), message);
+            expected.extend(codec().encode(&envelope).unwrap());
+        }
+        let mut written = vec![0; expected.len()];
+        timeout(Duration::from_secs(1), remote.read_exact(&mut written))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(written, expected);
+    }
+
+    #[actix::test]
+    async fn test_full_queue_rejects_new_messages() {
+        // El par nunca lee, así que la primera escritura queda bloqueada y el resto se encola.
+        let (connection, _remote, _events) = start_connection(4, 1);
+        let send = || {
+            connection.send(SendMessageThroughSocket {
+                message_to_send_through_socket: RobotRequest::StartElection {},
+            })
+        };
+
+        assert_eq!(send().await.unwrap(), Ok(()));
+        assert_eq!(send().await.unwrap(), Ok(()));
+        assert!(send().await.unwrap().is_err());
+    }
+
+    #[actix::test]
+    async fn test_heartbeats_are_sent_but_not_forwarded() {
+        let heartbeat = HeartbeatConfig {
+            interval: 20,
+            timeout: 1000,
+            ..HeartbeatConfig::default()
+        };
+        let (_connection, mut remote, mut events) =
+            start_connection_with_heartbeat(1024, 8, heartbeat);
+
+        let expected = codec()
+            .encode(&Envelope::<RobotRequest>::heartbeat(
+                10000,
+                Timestamp::default(),
+            ))
+            .unwrap();
+        let mut written = vec![0; expected.len()];
+        timeout(Duration::from_secs(1), remote.read_exact(&mut written))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(written, expected);
+
+        remote
+            .write_all(
+                &codec()
+                    .encode(&Envelope::<RobotRequest>::heartbeat(
+                        10001,
+                        Timestamp::default(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(timeout(Duration::from_millis(100), events.recv())
+            .await
+            .is_err());
+    }
+
+    #[actix::test]
+    async fn test_silent_peer_is_suspected_and_disconnected() {
+        for detector in [DetectorKind::Timeout, DetectorKind::PhiAccrual] {
+            let heartbeat = HeartbeatConfig {
+                interval: 20,
+                detector,
+                timeout: 100,
+                phi_threshold: 8.0,
+            };
+            let (_connection, _remote, mut events) =
+                start_connection_with_heartbeat(1024, 8, heartbeat);
+
+            match next_event(&mut events).await {
+                SocketEvent::Disconnected(Some(reason)) => {
+                    assert!(reason.starts_with("peer suspected dead"))
+                }
+                event => panic!("unexpected event {:?}", event),
+            }
+        }
+    }
+
+    #[actix::test]
+    async fn test_unauthenticated_frames_are_dropped() {
+        let (initiator, acceptor) = (SecretKey::challenge(), SecretKey::challenge());
+        let signed_by = |secret: &str, message: RobotRequest| {
+            let session = SecretKey::new(secret).session(Role::Initiator, &initiator, &acceptor);
+            let envelope = Envelope::new(10001, Timestamp::default(), message);
+            let body = codec().serialize(&envelope).unwrap();
+            codec()
+                .encode(&FrameAuthenticator::new(session).seal(body))
+                .unwrap()
+        };
+        let session = SecretKey::new("helado").session(Role::Acceptor, &initiator, &acceptor);
+        let heartbeat = HeartbeatConfig {
+            interval: 0,
+            ..HeartbeatConfig::default()
+        };
+        let (_connection, mut remote, mut events) = start_connection_with_codec(
+            1024,
+            8,
+            heartbeat,
+            codec().with_session(session),
+            local_clock(),
+        );
+
+        let unsigned = codec()
+            .encode(&Envelope::new(
+                10001,
+                Timestamp::default(),
+                RobotRequest::StartElection {},
+            ))
+            .unwrap();
+        remote.write_all(&unsigned).await.unwrap();
+        remote
+            .write_all(&signed_by("otro", RobotRequest::StartElection {}))
+            .await
+            .unwrap();
+        remote
+            .write_all(&signed_by("helado", RobotRequest::StartElection {}))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            next_event(&mut events).await,
+            SocketEvent::Malformed(_)
+        ));
+        assert!(matches!(
+            next_event(&mut events).await,
+            SocketEvent::Malformed(_)
+        ));
+        assert_eq!(
+            next_event(&mut events).await,
+            SocketEvent::Received(RobotRequest::StartElection {})
+        );
+    }
+
+    #[actix::test]
+    async fn test_received_messages_advance_the_clock() {
+        let clock = local_clock();
+        let heartbeat = HeartbeatConfig {
+            interval: 0,
+            ..HeartbeatConfig::default()
+        };
+        let (_connection, mut remote, mut events) =
+            start_connection_with_codec(1024, 8, heartbeat, codec(), clock.clone());
+
+        let remote_clock = Timestamp {
+            lamport: 41,
+            vector: None,
+        };
+        for envelope in [
+            Envelope::<RobotRequest>::heartbeat(10001, remote_clock.clone()),
+            Envelope::new(10001, remote_clock, RobotRequest::StartElection {}),
+        ] {
+            remote
+                .write_all(&codec().encode(&envelope).unwrap())
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            next_event(&mut events).await,
+            SocketEvent::Received(RobotRequest::StartElection {})
+        );
+        assert_eq!(clock.now().lamport, 42);
+    }
+
+    #[actix::test]
+    async fn test_close_reports_disconnection_to_the_owner() {
+        let (connection, _remote, mut events) = start_connection(1024, 8);
+        connection.do_send(CloseConnection);
+
+        assert_eq!(
+            next_event(&mut events).await,
+            SocketEvent::Disconnected(None)
+        );
+    }
+}
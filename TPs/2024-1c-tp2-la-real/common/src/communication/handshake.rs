@@ -0,0 +1,638 @@
+//! Handshake que se intercambia al abrir cualquier conexión entre nodos.
+//!
+//! Quien inicia la conexión envía su `Handshake`; quien la acepta lo valida y responde
+//! con un `HandshakeReply` que fija el codec de la conexión. Recién entonces ambos extremos
+//! empiezan a intercambiar `Envelope`s en ese codec. El handshake siempre viaja como JSON lines.
+//!
+//! Si el nodo tiene un secreto configurado, cada handshake lleva además un desafío aleatorio.
+//! Quien acepta responde con la prueba de conocer el secreto para ambos desafíos, y quien
+//! inició la verifica y envía la suya en un `HandshakeProof`. Una prueba inválida corta la
+//! conexión, y si ambas son válidas el codec negociado lleva las claves de sesión.
+//!
+//! Cada línea del handshake se espera como mucho `HANDSHAKE_TIMEOUT`, para que un par que abre
+//! el socket y no habla no retenga la conexión para siempre.
+
+use std::{fmt, time::Duration};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    time::timeout,
+};
+use uuid::Uuid;
+
+use super::{
+    auth::{Role, SecretKey},
+    codec::{CodecKind, FrameCodec, MAX_FRAME_SIZE},
+};
+
+/// Versión del protocolo de comunicación. Dos nodos solo se comunican si declaran la misma.
+pub const PROTOCOL_VERSION: u32 = 7;
+
+/// Tiempo máximo de espera de cada línea del handshake.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Codec preferido por defecto para las conexiones.
+pub const DEFAULT_CODEC: CodecKind = CodecKind::LengthPrefixed;
+
+/// Tipo de proceso que se encuentra del otro lado de una conexión.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    Shop,
+    Robot,
+    Screen,
+}
+
+impl fmt::Display for NodeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeKind::Shop => write!(f, "shop"),
+            NodeKind::Robot => write!(f, "robot"),
+            NodeKind::Screen => write!(f, "screen"),
+        }
+    }
+}
+
+/// Presentación que cada nodo envía al abrir una conexión.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handshake {
+    pub node_kind: NodeKind,
+    pub node_id: usize,
+    pub protocol_version: u32,
+    /// Codec que este nodo prefiere para la conexión.
+    pub codec: CodecKind,
+    /// Desafío de esta conexión, presente solo si el nodo exige autenticación.
+    pub challenge: Option<Uuid>,
+    /// Clave con la que este nodo se autentica. Nunca viaja por el socket.
+    #[serde(skip)]
+    secret: Option<SecretKey>,
+}
+
+/// Respuesta de quien acepta la conexión al `Handshake` recibido.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum HandshakeReply {
+    Accepted {
+        handshake: Handshake,
+        codec: CodecKind,
+        /// Prueba de quien acepta de conocer el secreto, si la conexión se autentica.
+        #[serde(with = "super::base64_bytes::option")]
+        proof: Option<Vec<u8>>,
+    },
+    Rejected {
+        reason: String,
+    },
+}
+
+/// Prueba de quien inició la conexión de conocer el secreto, en respuesta a un
+/// `HandshakeReply::Accepted` autenticado.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HandshakeProof {
+    #[serde(with = "super::base64_bytes")]
+    pub proof: Vec<u8>,
+}
+
+/// Motivos por los que un handshake puede fallar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandshakeError {
+    /// El socket se cerró antes de completar el handshake.
+    ConnectionClosed,
+    /// El par no envió su parte del handshake a tiempo.
+    TimedOut,
+    /// Error de entrada/salida sobre el socket.
+    Io(String),
+    /// Lo recibido no es un handshake válido.
+    Malformed(String),
+    /// El par habla otra versión del protocolo.
+    IncompatibleVersion { local: u32, remote: u32 },
+    /// El par no es del tipo de nodo esperado en esta conexión.
+    UnexpectedNodeKind {
+        expected: Vec<NodeKind>,
+        received: NodeKind,
+    },
+    /// El handshake superó el tamaño máximo de frame.
+    TooLarge,
+    /// El par rechazó nuestro handshake.
+    Rejected(String),
+    /// El par no pudo probar que conoce el secreto del cluster, o no lo exigimos ambos.
+    Unauthenticated(String),
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandshakeError::ConnectionClosed => write!(f, "connection closed during handshake"),
+            HandshakeError::TimedOut => write!(
+                f,
+                "no handshake received within {} ms",
+                HANDSHAKE_TIMEOUT.as_millis()
+            ),
+            HandshakeError::Io(error) => write!(f, "io error during handshake: {}", error),
+            HandshakeError::Malformed(error) => write!(f, "malformed handshake: {}", error),
+            HandshakeError::IncompatibleVersion { local, remote } => write!(
+                f,
+                "incompatible protocol version: local {}, remote {}",
+                local, remote
+            ),
+            HandshakeError::UnexpectedNodeKind { expected, received } => {
+                let expected: Vec<String> = expected.iter().map(|kind| kind.to_string()).collect();
+                write!(
+                    f,
+                    "unexpected peer {}, expected one of [{}]",
+                    received,
+                    expected.join(", ")
+                )
+            }
+            HandshakeError::TooLarge => {
+                write!(
+                    f,
+                    "handshake exceeds the maximum of {} bytes",
+                    MAX_FRAME_SIZE
+                )
+            }
+            HandshakeError::Rejected(reason) => write!(f, "handshake rejected: {}", reason),
+            HandshakeError::Unauthenticated(reason) => {
+                write!(f, "authentication failed: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HandshakeError {}
+
+impl Handshake {
+    /// Crea el handshake de este nodo con la versión actual del protocolo y el codec por defecto.
+    ///
+    /// # Argumentos
+    ///
+    /// * `node_kind` - Tipo de este nodo.
+    /// * `node_id` - Id de este nodo.
+    pub fn new(node_kind: NodeKind, node_id: usize) -> Self {
+        Self {
+            node_kind,
+            node_id,
+            protocol_version: PROTOCOL_VERSION,
+            codec: DEFAULT_CODEC,
+            challenge: None,
+            secret: None,
+        }
+    }
+
+    /// Cambia el codec preferido por este nodo.
+    ///
+    /// # Argumentos
+    ///
+    /// * `codec` - Codec preferido.
+    pub fn with_codec(mut self, codec: CodecKind) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Cambia la clave con la que este nodo autentica sus conexiones.
+    ///
+    /// # Argumentos
+    ///
+    /// * `secret` - Clave del cluster, o `None` para no autenticar las conexiones.
+    pub fn with_secret(mut self, secret: Option<SecretKey>) -> Self {
+        self.secret = secret;
+        self
+    }
+
+    /// Elige el codec de una conexión: el preferido si ambos extremos coinciden,
+    /// o JSON lines, que todos los nodos soportan, en caso contrario.
+    ///
+    /// # Argumentos
+    ///
+    /// * `remote` - Handshake del par.
+    pub fn negotiate_codec(&self, remote: &Handshake) -> CodecKind {
+        if self.codec == remote.codec {
+            self.codec
+        } else {
+            CodecKind::JsonLines
+        }
+    }
+
+    /// Verifica que el par que envió este handshake sea compatible con nosotros.
+    ///
+    /// # Argumentos
+    ///
+    /// * `expected` - Tipos de nodo aceptados en esta conexión.
+    ///
+    /// # Retorna
+    ///
+    /// `Ok(())` si el par es compatible, o el `HandshakeError` que lo descarta.
+    pub fn validate(&self, expected: &[NodeKind]) -> Result<(), HandshakeError> {
+        if self.protocol_version != PROTOCOL_VERSION {
+            return Err(HandshakeError::IncompatibleVersion {
+                local: PROTOCOL_VERSION,
+                remote: self.protocol_version,
+            });
+        }
+        if !expected.contains(&self.node_kind) {
+            return Err(HandshakeError::UnexpectedNodeKind {
+                expected: expected.to_vec(),
+                received: self.node_kind,
+            });
+        }
+        Ok(())
+    }
+
+    /// Copia de este handshake con un desafío nuevo si el nodo exige autenticación.
+    fn with_challenge(&self) -> Handshake {
+        Handshake {
+            challenge: self.secret.map(|_| SecretKey::challenge()),
+            ..*self
+        }
+    }
+
+    /// Verifica que este nodo y el par coincidan en exigir autenticación.
+    fn check_authentication(&self, remote: &Handshake) -> Result<(), HandshakeError> {
+        match (self.secret.is_some(), remote.challenge.is_some()) {
+            (true, false) => Err(HandshakeError::Unauthenticated(
+                "peer did not authenticate".to_string(),
+            )),
+            (false, true) => Err(HandshakeError::Unauthenticated(
+                "peer requires authentication but no secret is configured".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Realiza el handshake del lado de quien inició la conexión.
+///
+/// # Argumentos
+///
+/// * `reader` - Lector con buffer del socket. Al terminar queda posicionado en el primer frame.
+/// * `writer` - Mitad de escritura del socket.
+/// * `local` - Handshake de este nodo.
+/// * `expected` - Tipos de nodo aceptados del otro lado.
+///
+/// # Retorna
+///
+/// El handshake del par y el codec negociado, o el `HandshakeError` que impidió la conexión.
+pub async fn initiate_handshake<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    local: &Handshake,
+    expected: &[NodeKind],
+) -> Result<(Handshake, FrameCodec), HandshakeError>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let local = local.with_challenge();
+    write_line(writer, &local).await?;
+    match read_line::<_, HandshakeReply>(reader).await? {
+        HandshakeReply::Accepted {
+            handshake,
+            codec,
+            proof,
+        } => {
+            handshake.validate(expected)?;
+            local.check_authentication(&handshake)?;
+            let codec = FrameCodec::new(codec);
+            match (local.secret, local.challenge, handshake.challenge) {
+                (Some(secret), Some(initiator), Some(acceptor)) => {
+                    let proof = proof.unwrap_or_default();
+                    if !secret.verify_proof(Role::Acceptor, &initiator, &acceptor, &proof) {
+                        return Err(HandshakeError::Unauthenticated(
+                            "invalid proof from the acceptor".to_string(),
+                        ));
+                    }
+                    let proof = HandshakeProof {
+                        proof: secret.proof(Role::Initiator, &initiator, &acceptor),
+                    };
+                    write_line(writer, &proof).await?;
+                    let session = secret.session(Role::Initiator, &initiator, &acceptor);
+                    Ok((handshake, codec.with_session(session)))
+                }
+                _ => Ok((handshake, codec)),
+            }
+        }
+        HandshakeReply::Rejected { reason } => Err(HandshakeError::Rejected(reason)),
+    }
+}
+
+/// Realiza el handshake del lado de quien aceptó la conexión. Si el par no es compatible
+/// se le responde con el motivo del rechazo antes de devolver el error.
+///
+/// # Argumentos
+///
+/// * `reader` - Lector con buffer del socket. Al terminar queda posicionado en el primer frame.
+/// * `writer` - Mitad de escritura del socket.
+/// * `local` - Handshake de este nodo.
+/// * `expected` - Tipos de nodo aceptados del otro lado.
+///
+/// # Retorna
+///
+/// El handshake del par y el codec negociado, o el `HandshakeError` por el que fue rechazado.
+pub async fn accept_handshake<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    local: &Handshake,
+    expected: &[NodeKind],
+) -> Result<(Handshake, FrameCodec), HandshakeError>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let remote = read_line::<_, Handshake>(reader).await?;
+    let local = local.with_challenge();
+    if let Err(error) = remote
+        .validate(expected)
+        .and_then(|_| local.check_authentication(&remote))
+    {
+        let reply = HandshakeReply::Rejected {
+            reason: error.to_string(),
+        };
+        write_line(writer, &reply).await?;
+        return Err(error);
+    }
+    let codec = local.negotiate_codec(&remote);
+    match (local.secret, remote.challenge, local.challenge) {
+        (Some(secret), Some(initiator), Some(acceptor)) => {
+            let reply = HandshakeReply::Accepted {
+                handshake: local,
+                codec,
+                proof: Some(secret.proof(Role::Acceptor, &initiator, &acceptor)),
+            };
+            write_line(writer, &reply).await?;
+            let HandshakeProof { proof } = read_line(reader).await?;
+            if !secret.verify_proof(Role::Initiator, &initiator, &acceptor, &proof) {
+                return Err(HandshakeError::Unauthenticated(
+                    "invalid proof from the initiator".to_string(),
+                ));
+            }
+            let session = secret.session(Role::Acceptor, &initiator, &acceptor);
+            Ok((remote, FrameCodec::new(codec).with_session(session)))
+        }
+        _ => {
+            let reply = HandshakeReply::Accepted {
+                handshake: local,
+                codec,
+                proof: None,
+            };
+            write_line(writer, &reply).await?;
+            Ok((remote, FrameCodec::new(codec)))
+        }
+    }
+}
+
+async fn write_line<W, T>(writer: &mut W, message: &T) -> Result<(), HandshakeError>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let line = serde_json::to_string(message)
+        .map_err(|error| HandshakeError::Malformed(error.to_string()))?
+        + "\n";
+    writer
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|error| HandshakeError::Io(error.to_string()))
+}
+
+async fn read_line<R, T>(reader: &mut R) -> Result<T, HandshakeError>
+where
+    R: AsyncBufRead + Unpin,
+    T: DeserializeOwned,
+{
+    let mut line = Vec::new();
+    let read = timeout(
+        HANDSHAKE_TIMEOUT,
+        reader
+            .take(MAX_FRAME_SIZE as u64 + 1)
+            .read_until(b'\n', &mut line),
+    )
+    .await
+    .map_err(|_| HandshakeError::TimedOut)?
+    .map_err(|error| HandshakeError::Io(error.to_string()))?;
+    if line.pop() != Some(b'\n') {
+        return Err(if read > MAX_FRAME_SIZE {
+            HandshakeError::TooLarge
+        } else {
+            HandshakeError::ConnectionClosed
+        });
+    }
+    serde_json::from_slice(&line).map_err(|error| HandshakeError::Malformed(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::communication::auth::FrameAuthenticator;
+    use tokio::io::{duplex, split, BufReader};
+
+    type Outcome = Result<(Handshake, FrameCodec), HandshakeError>;
+
+    async fn run_handshake(
+        initiator: Handshake,
+        acceptor: Handshake,
+        initiator_expects: &[NodeKind],
+        acceptor_expects: &[NodeKind],
+    ) -> (Outcome, Outcome) {
+        let (left, right) = duplex(1024);
+
+        // Cada extremo suelta su socket al terminar, como al cortar una conexión real.
+        tokio::join!(
+            async move {
+                let (left_reader, mut left_writer) = split(left);
+                let mut left_reader = BufReader::new(left_reader);
+                initiate_handshake(
+                    &mut left_reader,
+                    &mut left_writer,
+                    &initiator,
+                    initiator_expects,
+                )
+                .await
+            },
+            async move {
+                let (right_reader, mut right_writer) = split(right);
+                let mut right_reader = BufReader::new(right_reader);
+                accept_handshake(
+                    &mut right_reader,
+                    &mut right_writer,
+                    &acceptor,
+                    acceptor_expects,
+                )
+                .await
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_compatible_peers_learn_each_other() {
+        let screen = Handshake::new(NodeKind::Screen, 42);
+        let robot = Handshake::new(NodeKind::Robot, 10000);
+
+        let (initiated, accepted) =
+            run_handshake(screen, robot, &[NodeKind::Robot], &[NodeKind::Screen]).await;
+
+        let codec = FrameCodec::new(DEFAULT_CODEC);
+        assert_eq!(initiated, Ok((robot, codec)));
+        assert_eq!(accepted, Ok((screen, codec)));
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_codecs_fall_back_to_json_lines() {
+        let screen = Handshake::new(NodeKind::Screen, 42).with_codec(CodecKind::JsonLines);
+        let robot = Handshake::new(NodeKind::Robot, 10000);
+
+        let (initiated, accepted) =
+            run_handshake(screen, robot, &[NodeKind::Robot], &[NodeKind::Screen]).await;
+
+        assert_eq!(initiated.unwrap().1.kind(), CodecKind::JsonLines);
+        assert_eq!(accepted.unwrap().1.kind(), CodecKind::JsonLines);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_handshake_is_rejected() {
+        let line = vec![b'a'; MAX_FRAME_SIZE + 10];
+        let mut reader = &line[..];
+
+        assert_eq!(
+            read_line::<_, Handshake>(&mut reader).await,
+            Err(HandshakeError::TooLarge)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unexpected_node_kind_is_rejected() {
+        let screen = Handshake::new(NodeKind::Screen, 42);
+        let shop = Handshake::new(NodeKind::Shop, 9999);
+
+        let (initiated, accepted) =
+            run_handshake(screen, shop, &[NodeKind::Shop], &[NodeKind::Robot]).await;
+
+        assert!(matches!(initiated, Err(HandshakeError::Rejected(_))));
+        assert_eq!(
+            accepted,
+            Err(HandshakeError::UnexpectedNodeKind {
+                expected: vec![NodeKind::Robot],
+                received: NodeKind::Screen,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_peers_with_the_same_secret_share_a_session() {
+        let secret = Some(SecretKey::new("helado"));
+        let screen = Handshake::new(NodeKind::Screen, 42).with_secret(secret);
+        let robot = Handshake::new(NodeKind::Robot, 10000).with_secret(secret);
+
+        let (initiated, accepted) =
+            run_handshake(screen, robot, &[NodeKind::Robot], &[NodeKind::Screen]).await;
+        let (robot_seen, initiator_codec) = initiated.unwrap();
+        let (screen_seen, acceptor_codec) = accepted.unwrap();
+
+        assert_eq!(robot_seen.node_id, 10000);
+        assert_eq!(screen_seen.node_id, 42);
+        let mut initiator = FrameAuthenticator::new(initiator_codec.session().unwrap());
+        let mut acceptor = FrameAuthenticator::new(acceptor_codec.session().unwrap());
+        let frame = initiator.seal(b"order".to_vec());
+        assert_eq!(acceptor.open(frame), Ok(b"order".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn test_wrong_or_missing_secret_is_rejected() {
+        let robot =
+            Handshake::new(NodeKind::Robot, 10000).with_secret(Some(SecretKey::new("helado")));
+        let wrong_secret =
+            Handshake::new(NodeKind::Robot, 10001).with_secret(Some(SecretKey::new("otro")));
+        let no_secret = Handshake::new(NodeKind::Robot, 10001);
+
+        let (initiated, accepted) =
+            run_handshake(wrong_secret, robot, &[NodeKind::Robot], &[NodeKind::Robot]).await;
+        assert!(matches!(initiated, Err(HandshakeError::Unauthenticated(_))));
+        assert_eq!(accepted, Err(HandshakeError::ConnectionClosed));
+
+        let (initiated, accepted) =
+            run_handshake(no_secret, robot, &[NodeKind::Robot], &[NodeKind::Robot]).await;
+        assert!(matches!(initiated, Err(HandshakeError::Rejected(_))));
+        assert!(matches!(accepted, Err(HandshakeError::Unauthenticated(_))));
+    }
+
+    #[tokio::test]
+    async fn test_incompatible_version_is_rejected() {
+        let mut newer_robot = Handshake::new(NodeKind::Robot, 10001);
+        newer_robot.protocol_version = PROTOCOL_VERSION + 1;
+        let robot = Handshake::new(NodeKind::Robot, 10000);
+
+        let (initiated, accepted) =
+            run_handshake(newer_robot, robot, &[NodeKind::Robot], &[NodeKind::Robot]).await;
+
+        assert!(matches!(initiated, Err(HandshakeError::Rejected(_))));
+        assert_eq!(
+            accepted,
+            Err(HandshakeError::IncompatibleVersion {
+                local: PROTOCOL_VERSION,
+                remote: PROTOCOL_VERSION + 1,
+            })
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_silent_peer_times_out() {
+        let robot = Handshake::new(NodeKind::Robot, 10000);
+        let (left, right) = duplex(1024);
+        let (right_reader, mut right_writer) = split(right);
+        let mut right_reader = BufReader::new(right_reader);
+
+        let accepted = accept_handshake(
+            &mut right_reader,
+            &mut right_writer,
+            &robot,
+            &[NodeKind::Robot],
+        )
+        .await;
+        assert_eq!(accepted, Err(HandshakeError::TimedOut));
+
+        let (left_reader, mut left_writer) = split(left);
+        let mut left_reader = BufReader::new(left_reader);
+        let initiated = initiate_handshake(
+            &mut left_reader,
+            &mut left_writer,
+            &robot,
+            &[NodeKind::Robot],
+        )
+        .await;
+        assert_eq!(initiated, Err(HandshakeError::TimedOut));
+    }
+
+    #[test]
+    fn test_proofs_travel_as_base64() {
+        let reply = HandshakeReply::Accepted {
+            handshake: Handshake::new(NodeKind::Robot, 10000),
+            codec: DEFAULT_CODEC,
+            proof: Some(b"proof".to_vec()),
+        };
+        let json = serde_json::to_value(&reply).unwrap();
+        assert_eq!(json["Accepted"]["proof"], "cHJvb2Y=");
+        assert_eq!(
+            serde_json::from_value::<HandshakeReply>(json).unwrap(),
+            reply
+        );
+
+        let unauthenticated = HandshakeReply::Accepted {
+            handshake: Handshake::new(NodeKind::Robot, 10000),
+            codec: DEFAULT_CODEC,
+            proof: None,
+        };
+        let json = serde_json::to_value(&unauthenticated).unwrap();
+        assert!(json["Accepted"]["proof"].is_null());
+        assert_eq!(
+            serde_json::from_value::<HandshakeReply>(json).unwrap(),
+            unauthenticated
+        );
+
+        let proof = HandshakeProof {
+            proof: b"proof".to_vec(),
+        };
+        let json = serde_json::to_value(&proof).unwrap();
+        assert_eq!(json["proof"], "cHJvb2Y=");
+        assert_eq!(
+            serde_json::from_value::<HandshakeProof>(json).unwrap(),
+            proof
+        );
+    }
+}
@@ -1,3 +1,13 @@
+pub mod auth;
+mod base64_bytes;
+pub mod codec;
+pub mod envelope;
+pub mod failure_detector;
+pub mod framed_connection;
+pub mod handshake;
+pub mod order_error;
+pub mod order_id;
+pub mod registry_communication;
 pub mod shop_requests;
 pub mod screen_robot_communication;
-pub mod robot_to_robot_communication;
\ No newline at end of file
+pub mod robot_to_robot_communication;
@@ -0,0 +1,95 @@
+//! Motivos por los que un pedido puede no completarse.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// `OrderError` es el motivo por el que un pedido no se completó. Lo generan la heladería,
+/// el robot o la propia pantalla, y viaja en `ShopResponse::OrderResult` y en
+/// `Response::RobotToScreenResult`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum OrderError {
+    /// No hay stock suficiente de un sabor.
+    OutOfStock {
+        flavor: String,
+        requested: u32,
+        available: u32,
+    },
+    /// La heladería no tiene el sabor pedido.
+    UnknownFlavor { flavor: String },
+    /// No hay conexión con la heladería, o esta dejó de atender pedidos.
+    ShopUnavailable,
+    /// Se perdió la conexión con el robot que atendía el pedido.
+    LeaderChanged,
+    /// No llegó una respuesta a tiempo.
+    Timeout,
+    /// No se pudo capturar el pago.
+    PaymentDeclined,
+    /// El pedido no es válido (por ejemplo, un tamaño desconocido).
+    InvalidOrder,
+}
+
+impl OrderError {
+    /// Indica si el error es transitorio y el mismo pedido puede reintentarse,
+    /// a diferencia de los errores propios del pedido (stock, sabor, pago).
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            OrderError::ShopUnavailable | OrderError::LeaderChanged | OrderError::Timeout
+        )
+    }
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderError::OutOfStock {
+                flavor,
+                requested,
+                available,
+            } => write!(
+                f,
+                "not enough {} ({} requested, {} available)",
+                flavor, requested, available
+            ),
+            OrderError::UnknownFlavor { flavor } => write!(f, "unknown flavor {}", flavor),
+            OrderError::ShopUnavailable => write!(f, "the ice cream shop is unavailable"),
+            OrderError::LeaderChanged => write!(f, "lost connection with the robot"),
+            OrderError::Timeout => write!(f, "timed out waiting for the result"),
+            OrderError::PaymentDeclined => write!(f, "payment was declined"),
+            OrderError::InvalidOrder => write!(f, "invalid order"),
+        }
+    }
+}
+
+impl std::error::Error for OrderError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_transient_errors_are_retryable() {
+        assert!(OrderError::ShopUnavailable.is_retryable());
+        assert!(OrderError::LeaderChanged.is_retryable());
+        assert!(OrderError::Timeout.is_retryable());
+        assert!(!OrderError::PaymentDeclined.is_retryable());
+        assert!(!OrderError::UnknownFlavor {
+            flavor: "PISTACHO".to_string()
+        }
+        .is_retryable());
+    }
+
+    #[test]
+    fn test_out_of_stock_display() {
+        let error = OrderError::OutOfStock {
+            flavor: "VAINILLA".to_string(),
+            requested: 15,
+            available: 10,
+        };
+        assert_eq!(
+            error.to_string(),
+            "not enough VAINILLA (15 requested, 10 available)"
+        );
+    }
+}
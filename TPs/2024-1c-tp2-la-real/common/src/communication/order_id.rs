@@ -0,0 +1,47 @@
+//! Identificador global de un pedido.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// `OrderId` identifica un pedido en todo el sistema. Lo genera la pantalla al crear el
+/// pedido y viaja sin cambios por el robot y la heladería, en el pedido y en su respuesta,
+/// de modo que cada componente puede correlacionar la respuesta y loguear el mismo id.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OrderId(Uuid);
+
+impl OrderId {
+    /// Genera un nuevo `OrderId` aleatorio.
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for OrderId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for OrderId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_ids_are_distinct() {
+        assert_ne!(OrderId::new(), OrderId::new());
+    }
+
+    #[test]
+    fn test_display_is_the_uuid() {
+        let id = OrderId::new();
+        assert_eq!(id.to_string(), id.0.to_string());
+    }
+}
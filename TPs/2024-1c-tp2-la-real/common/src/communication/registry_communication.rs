@@ -0,0 +1,67 @@
+//! Mensajes entre los nodos y el registro de descubrimiento.
+//!
+//! Cada conexión con el registro lleva un único `RegistryRequest` y su `RegistryResponse`,
+//! ambos como frames `JsonLines`, sin handshake previo. Viajan dentro de un `Envelope` para
+//! llevar el reloj lógico, con 0 como remitente porque el registro no identifica a sus clientes.
+//!
+//! Si el cluster configura `auth.secret`, el registro solo acepta un `Register` que pruebe
+//! conocer el secreto: el robot pide antes un desafío con `Challenge` y firma con él su
+//! anotación (ver `SecretKey::registration_proof`). Las consultas y las respuestas del registro
+//! no se autentican, así que un tercero en la red puede leer los robots anotados o falsear las
+//! respuestas que recibe un nodo; las conexiones que se abren después con esas direcciones sí
+//! se autentican en su handshake.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Datos con los que un robot se anota en el registro.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RobotRecord {
+    /// Dirección `host:port` en la que el robot escucha a los demás robots. Identifica al robot
+    /// en el registro, ya que robots en distintos hosts pueden usar el mismo puerto.
+    pub robot_address: String,
+    /// Dirección `host:port` en la que el robot escucha a las pantallas.
+    pub screen_address: String,
+    /// Si el robot se considera el líder del cluster.
+    pub is_leader: bool,
+}
+
+/// Prueba de que quien anota a un robot conoce el secreto del cluster.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RegistrationProof {
+    /// Desafío entregado por el registro. Sirve para una sola anotación.
+    pub challenge: Uuid,
+    /// Firma de la anotación con el desafío.
+    #[serde(with = "super::base64_bytes")]
+    pub proof: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum RegistryRequest {
+    /// Pide un desafío con el que firmar un `Register`.
+    Challenge,
+    /// Anota al robot, o renueva su anotación, hasta que venza el `registry_ttl`. La prueba
+    /// solo se exige si el registro tiene un secreto configurado.
+    Register {
+        robot: RobotRecord,
+        proof: Option<RegistrationProof>,
+    },
+    /// Pide los robots vivos.
+    Query,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum RegistryResponse {
+    /// Desafío para el próximo `Register`.
+    Challenge { challenge: Uuid },
+    /// Confirma un `Register`.
+    Done,
+    /// Rechaza un `Register` sin una prueba válida.
+    Rejected { reason: String },
+    /// Robots vivos, ordenados por dirección, y la dirección del líder si alguno se anunció
+    /// como tal.
+    Robots {
+        robots: Vec<RobotRecord>,
+        leader: Option<String>,
+    },
+}
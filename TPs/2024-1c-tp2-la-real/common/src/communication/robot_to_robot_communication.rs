@@ -1,11 +1,7 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum RobotRequest {
-    
-    GetMyInformation {
-        robot_to_robot_id: usize,
-    },
     StartElection {
 
     },
@@ -15,7 +11,7 @@ pub enum RobotRequest {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum RobotResponse {
     RobotToScreenResult {
         index: usize,
@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+use super::{order_error::OrderError, order_id::OrderId};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum Request {
     
     ScreenToRobotOrder {
+        order_id: OrderId,
         index: usize,
         flavors: Vec<String>,
         size: u32,
@@ -11,11 +14,12 @@ pub enum Request {
     ScreenToRobotAskLeader {}
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum Response {
     RobotToScreenResult {
+        order_id: OrderId,
         index: usize,
-        result: Result<(), String>
+        result: Result<(), OrderError>
     },
     RobotToScreenLeaderPort {
         leader_port: usize
@@ -2,17 +2,19 @@
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+use super::{order_error::OrderError, order_id::OrderId};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum ShopRequest {
     IceCreamOrder {
+        order_id: OrderId,
         flavors: Vec<String>,
         size: u32,
         screen_id: usize,
-        screen_address: String
     },
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum ShopResponse {
-    OrderResult { screen_id: usize, result: Result<(), String>, screen_address: String },
+    OrderResult { order_id: OrderId, screen_id: usize, result: Result<(), OrderError> },
 }
@@ -0,0 +1,478 @@
+//! Configuración compartida por la heladería, los robots y las pantallas.
+//!
+//! La configuración se arma en capas, donde cada una pisa a la anterior:
+//! 1. Los valores por defecto de `Config::default`.
+//! 2. El archivo TOML indicado con `--config`, o con `ICE_CREAM_CONFIG`, o `config.toml` si existe.
+//! 3. Las variables de entorno `ICE_CREAM_<CLAVE>`, por ejemplo `ICE_CREAM_SHOP_PORT=9998`.
+//! 4. Los argumentos `--set <clave>=<valor>`, por ejemplo `--set robots.first_port=11000`.
+
+use std::{env, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+
+use crate::communication::codec::CodecKind;
+
+/// Variable de entorno con la ruta del archivo de configuración.
+pub const CONFIG_FILE_ENV: &str = "ICE_CREAM_CONFIG";
+/// Archivo de configuración que se lee si no se indica otro y existe.
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+/// Prefijo de las variables de entorno que pisan claves de la configuración.
+pub const ENV_PREFIX: &str = "ICE_CREAM_";
+/// Secciones de la configuración, para separar la sección del campo en las variables de entorno.
+const SECTIONS: [&str; 7] = [
+    "shop",
+    "robots",
+    "screens",
+    "discovery",
+    "heartbeat",
+    "auth",
+    "clock",
+];
+
+/// Topología y parámetros de un cluster. Cada nodo lee la misma configuración, por lo que
+/// varios clusters pueden convivir en una máquina usando archivos con puertos distintos.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Host en el que escuchan y al que se conectan todos los nodos.
+    pub host: String,
+    /// Codec que cada nodo prefiere en el handshake (`JsonLines` o `LengthPrefixed`).
+    pub codec: CodecKind,
+    pub shop: ShopConfig,
+    pub robots: RobotsConfig,
+    pub screens: ScreensConfig,
+    pub discovery: DiscoveryConfig,
+    pub heartbeat: HeartbeatConfig,
+    pub auth: AuthConfig,
+    pub clock: ClockConfig,
+}
+
+/// Configuración de la heladería.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShopConfig {
+    /// Puerto en el que la heladería escucha a los robots.
+    pub port: usize,
+}
+
+/// Configuración de los robots.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RobotsConfig {
+    /// Primer puerto del rango en el que los robots se escuchan entre sí.
+    pub first_port: usize,
+    /// Último puerto, inclusive, del rango en el que los robots se escuchan entre sí.
+    pub last_port: usize,
+}
+
+/// Configuración de las pantallas.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScreensConfig {
+    /// Primer puerto del rango en el que los robots escuchan a las pantallas.
+    pub first_port: usize,
+    /// Último puerto, inclusive, del rango en el que los robots escuchan a las pantallas.
+    pub last_port: usize,
+    /// Milisegundos que espera una pantalla antes de volver a buscar robots.
+    pub leader_election_time: u64,
+    /// Probabilidad de que se acepte el pago de un pedido.
+    pub payment_acceptance_rate: f32,
+}
+
+/// Forma en la que los robots se encuentran entre sí y las pantallas encuentran a los robots.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscoveryMode {
+    /// Se prueba cada puerto de los rangos `robots` y `screens`.
+    PortRange,
+    /// Se prueban las direcciones de `robot_seeds` y `screen_seeds`.
+    Seeds,
+    /// Se consulta al registro, en el que cada robot se anota periódicamente.
+    Registry,
+}
+
+/// Configuración del descubrimiento de nodos.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiscoveryConfig {
+    pub mode: DiscoveryMode,
+    /// Direcciones `host:port` en las que los robots se escuchan entre sí, para el modo `Seeds`.
+    pub robot_seeds: Vec<String>,
+    /// Direcciones `host:port` en las que los robots escuchan a las pantallas, para el modo `Seeds`.
+    pub screen_seeds: Vec<String>,
+    /// Dirección `host:port` del registro, para el modo `Registry`.
+    pub registry: String,
+    /// Milisegundos tras los cuales el registro olvida a un robot que no volvió a anotarse.
+    pub registry_ttl: u64,
+}
+
+/// Detector con el que cada extremo de una conexión decide que el par dejó de responder.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectorKind {
+    /// Se sospecha del par si no llega nada de él durante `timeout` milisegundos.
+    Timeout,
+    /// Se sospecha del par cuando el valor phi del detector supera `phi_threshold`.
+    PhiAccrual,
+}
+
+/// Configuración de los heartbeats que se envían por cada conexión.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct HeartbeatConfig {
+    /// Milisegundos entre heartbeats. Con 0 no se envían ni se vigila al par.
+    pub interval: u64,
+    pub detector: DetectorKind,
+    /// Milisegundos sin recibir nada tras los cuales el detector `Timeout` sospecha del par.
+    pub timeout: u64,
+    /// Umbral del detector `PhiAccrual`: con 8 la probabilidad de una falsa sospecha es de 1e-8.
+    pub phi_threshold: f64,
+}
+
+/// Configuración de la autenticación entre nodos.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Secreto compartido por todos los nodos. Vacío, las conexiones no se autentican.
+    pub secret: String,
+}
+
+/// Configuración de los relojes lógicos de los nodos.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClockConfig {
+    /// Si además del reloj de Lamport cada nodo lleva un reloj vectorial.
+    pub vector: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            codec: CodecKind::LengthPrefixed,
+            shop: ShopConfig::default(),
+            robots: RobotsConfig::default(),
+            screens: ScreensConfig::default(),
+            discovery: DiscoveryConfig::default(),
+            heartbeat: HeartbeatConfig::default(),
+            auth: AuthConfig::default(),
+            clock: ClockConfig::default(),
+        }
+    }
+}
+
+impl Default for ShopConfig {
+    fn default() -> Self {
+        Self { port: 9999 }
+    }
+}
+
+impl Default for RobotsConfig {
+    fn default() -> Self {
+        Self {
+            first_port: 10000,
+            last_port: 10009,
+        }
+    }
+}
+
+impl Default for ScreensConfig {
+    fn default() -> Self {
+        Self {
+            first_port: 9000,
+            last_port: 9009,
+            leader_election_time: 20000,
+            payment_acceptance_rate: 0.95,
+        }
+    }
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            mode: DiscoveryMode::PortRange,
+            robot_seeds: Vec::new(),
+            screen_seeds: Vec::new(),
+            registry: "127.0.0.1:8999".to_string(),
+            registry_ttl: 6000,
+        }
+    }
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        Self {
+            interval: 1000,
+            detector: DetectorKind::Timeout,
+            timeout: 5000,
+            phi_threshold: 8.0,
+        }
+    }
+}
+
+impl Config {
+    /// Arma la configuración de un nodo a partir de sus argumentos de línea de comandos.
+    /// Se quitan de `args` los argumentos `--config <archivo>` y `--set <clave>=<valor>`,
+    /// dejando el resto para el parseo propio de cada binario.
+    ///
+    /// # Argumentos
+    ///
+    /// * `args` - Argumentos del programa, sin el nombre del ejecutable.
+    ///
+    /// # Retorna
+    ///
+    /// La configuración resultante, o un `String` describiendo el error.
+    pub fn from_args(args: &mut Vec<String>) -> Result<Config, String> {
+        let mut config_file = None;
+        let mut overrides = Vec::new();
+        let mut remaining = Vec::new();
+
+        let mut iter = std::mem::take(args).into_iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--config" => {
+                    config_file = Some(iter.next().ok_or("Missing file after --config")?);
+                }
+                "--set" => {
+                    let assignment = iter.next().ok_or("Missing key=value after --set")?;
+                    let (key, value) = assignment.split_once('=').ok_or(format!(
+                        "Invalid override {}, expected key=value",
+                        assignment
+                    ))?;
+                    overrides.push((key.to_string(), value.to_string()));
+                }
+                _ => remaining.push(arg),
+            }
+        }
+        *args = remaining;
+
+        let config_file = config_file.or_else(|| env::var(CONFIG_FILE_ENV).ok());
+        let mut config = match config_file {
+            Some(path) => Self::from_file(&path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::from_file(DEFAULT_CONFIG_FILE)?
+            }
+            None => Self::default(),
+        };
+        config.apply_env(env::vars())?;
+        for (key, value) in overrides {
+            config.set(&key, &value)?;
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Lee la configuración de un archivo TOML. Las claves ausentes toman su valor por defecto.
+    ///
+    /// # Argumentos
+    ///
+    /// * `path` - Ruta del archivo.
+    pub fn from_file(path: &str) -> Result<Config, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|error| format!("Couldn't read config file {}: {}", path, error))?;
+        toml::from_str(&contents)
+            .map_err(|error| format!("Invalid config file {}: {}", path, error))
+    }
+
+    /// Pisa una clave de la configuración.
+    ///
+    /// # Argumentos
+    ///
+    /// * `key` - Clave, con sus secciones separadas por puntos (por ejemplo `shop.port`).
+    /// * `value` - Valor en formato TOML. Si no es un valor TOML válido, o no sirve para la
+    ///   clave, se toma como texto: un secreto `12345` o un host `true` siguen siendo textos.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let table = Table::try_from(&*self).map_err(|error| error.to_string())?;
+
+        let parsed = parse_value(value);
+        let mut config = with_value(table.clone(), key, parsed.clone())?.try_into::<Config>();
+        if config.is_err() && !parsed.is_str() {
+            config = with_value(table, key, Value::String(value.to_string()))?.try_into();
+        }
+        *self = config.map_err(|error| {
+            // los secretos no se muestran en los mensajes de error
+            if key.starts_with("auth.") {
+                format!("Invalid value for {}", key)
+            } else {
+                format!("Invalid value {} for {}: {}", value, key, error)
+            }
+        })?;
+        Ok(())
+    }
+
+    /// Verifica que la configuración ya combinada sea coherente.
+    fn validate(&self) -> Result<(), String> {
+        if self.robots.first_port > self.robots.last_port {
+            return Err(format!(
+                "robots.first_port {} is greater than robots.last_port {}",
+                self.robots.first_port, self.robots.last_port
+            ));
+        }
+        if self.screens.first_port > self.screens.last_port {
+            return Err(format!(
+                "screens.first_port {} is greater than screens.last_port {}",
+                self.screens.first_port, self.screens.last_port
+            ));
+        }
+        Ok(())
+    }
+
+    /// Pisa la configuración con las variables de entorno que empiezan con `ENV_PREFIX`.
+    /// El resto del nombre, en minúsculas, es la clave: la primera palabra se toma como
+    /// sección si existe una con ese nombre (`ICE_CREAM_ROBOTS_FIRST_PORT` es `robots.first_port`).
+    fn apply_env<I: Iterator<Item = (String, String)>>(&mut self, vars: I) -> Result<(), String> {
+        for (name, value) in vars {
+            let key = match name.strip_prefix(ENV_PREFIX) {
+                Some(key) if name != CONFIG_FILE_ENV => key.to_lowercase(),
+                _ => continue,
+            };
+            let key = match key.split_once('_') {
+                Some((section, field)) if SECTIONS.contains(&section) => {
+                    format!("{}.{}", section, field)
+                }
+                _ => key,
+            };
+            self.set(&key, &value)?;
+        }
+        Ok(())
+    }
+
+    /// Dirección `host:port` de un puerto del cluster.
+    pub fn address(&self, port: usize) -> String {
+        format!("{}:{}", self.host, port)
+    }
+}
+
+/// Reemplaza una clave de la tabla de la configuración.
+///
+/// # Retorna
+///
+/// La tabla con el nuevo valor, o un `String` si la clave no existe.
+fn with_value(mut table: Table, key: &str, value: Value) -> Result<Table, String> {
+    let mut path: Vec<&str> = key.split('.').collect();
+    let field = path.pop().unwrap_or_default();
+    let mut section = &mut table;
+    for name in path {
+        section = match section.get_mut(name) {
+            Some(Value::Table(inner)) => inner,
+            _ => return Err(format!("Unknown configuration key {}", key)),
+        };
+    }
+    let current = section
+        .get_mut(field)
+        .ok_or(format!("Unknown configuration key {}", key))?;
+    *current = value;
+    Ok(table)
+}
+
+fn parse_value(value: &str) -> Value {
+    format!("value = {}", value)
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_keys_fall_back_to_defaults() {
+        let config: Config =
+            toml::from_str("codec = \"JsonLines\"\n[shop]\nport = 9998\n").unwrap();
+
+        assert_eq!(config.codec, CodecKind::JsonLines);
+        assert_eq!(config.shop.port, 9998);
+        assert_eq!(config.robots, RobotsConfig::default());
+        assert_eq!(config.host, "127.0.0.1");
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        assert!(toml::from_str::<Config>("[shop]\nprot = 9998\n").is_err());
+        assert!(Config::default().set("shop.prot", "9998").is_err());
+    }
+
+    #[test]
+    fn test_set_parses_values() {
+        let mut config = Config::default();
+        config.set("host", "0.0.0.0").unwrap();
+        config.set("robots.first_port", "11000").unwrap();
+        config
+            .set("screens.payment_acceptance_rate", "1.0")
+            .unwrap();
+
+        assert_eq!(config.host, "0.0.0.0");
+        assert_eq!(config.robots.first_port, 11000);
+        assert_eq!(config.screens.payment_acceptance_rate, 1.0);
+        assert!(config.set("shop.port", "not a port").is_err());
+    }
+
+    #[test]
+    fn test_set_keeps_text_that_looks_like_other_types() {
+        let mut config = Config::default();
+        config.set("auth.secret", "12345").unwrap();
+        assert_eq!(config.auth.secret, "12345");
+        config.set("auth.secret", "true").unwrap();
+        assert_eq!(config.auth.secret, "true");
+        config.set("host", "10").unwrap();
+        assert_eq!(config.host, "10");
+    }
+
+    #[test]
+    fn test_inverted_port_ranges_are_rejected() {
+        let mut config = Config::default();
+        assert!(config.validate().is_ok());
+        config.robots.first_port = config.robots.last_port + 1;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_env_vars_map_to_keys() {
+        let mut config = Config::default();
+        let vars = [
+            ("ICE_CREAM_SHOP_PORT", "9998"),
+            ("ICE_CREAM_ROBOTS_LAST_PORT", "10003"),
+            ("ICE_CREAM_CODEC", "JsonLines"),
+            ("ICE_CREAM_DISCOVERY_MODE", "Registry"),
+            ("ICE_CREAM_CONFIG", "ignored.toml"),
+            ("HOME", "/root"),
+        ];
+        config
+            .apply_env(vars.iter().map(|(k, v)| (k.to_string(), v.to_string())))
+            .unwrap();
+
+        assert_eq!(config.shop.port, 9998);
+        assert_eq!(config.robots.last_port, 10003);
+        assert_eq!(config.codec, CodecKind::JsonLines);
+        assert_eq!(config.discovery.mode, DiscoveryMode::Registry);
+    }
+
+    #[test]
+    fn test_set_parses_seed_lists() {
+        let mut config = Config::default();
+        config
+            .set(
+                "discovery.robot_seeds",
+                "[\"127.0.0.1:10000\", \"127.0.0.1:10001\"]",
+            )
+            .unwrap();
+
+        assert_eq!(
+            config.discovery.robot_seeds,
+            vec!["127.0.0.1:10000", "127.0.0.1:10001"]
+        );
+    }
+
+    #[test]
+    fn test_from_args_removes_config_flags() {
+        let mut args: Vec<String> = ["10000", "--set", "shop.port=9998", "9000"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let config = Config::from_args(&mut args).unwrap();
+
+        assert_eq!(args, vec!["10000", "9000"]);
+        assert_eq!(config.shop.port, 9998);
+    }
+}
@@ -0,0 +1,233 @@
+//! Descubrimiento de nodos: a qué direcciones se conecta un robot para encontrar a los demás
+//! robots y una pantalla para encontrar a los robots, según `DiscoveryMode`.
+
+use std::time::Duration;
+
+use tokio::{io::AsyncWriteExt, net::TcpStream, time::timeout};
+
+use crate::clock::NodeClock;
+use crate::communication::{
+    auth::SecretKey,
+    codec::{CodecKind, FrameCodec},
+    envelope::{Envelope, Payload},
+    registry_communication::{RegistrationProof, RegistryRequest, RegistryResponse, RobotRecord},
+};
+use crate::config::{Config, DiscoveryMode};
+
+/// Tiempo máximo para conectarse al registro y para recibir su respuesta.
+const REGISTRY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `Discovery` resuelve las direcciones de los robots a partir de la configuración del cluster.
+#[derive(Debug, Clone)]
+pub struct Discovery {
+    config: Config,
+}
+
+impl Discovery {
+    /// Crea un `Discovery` para la configuración dada.
+    ///
+    /// # Argumentos
+    ///
+    /// * `config` - Configuración del cluster.
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.clone(),
+        }
+    }
+
+    /// Indica si los robots deben anotarse en el registro.
+    pub fn uses_registry(&self) -> bool {
+        self.config.discovery.mode == DiscoveryMode::Registry
+    }
+
+    /// Cada cuánto un robot renueva su anotación: un tercio del `registry_ttl`, para que
+    /// perder una renovación no alcance para que el registro lo dé por muerto.
+    pub fn registry_refresh(&self) -> Duration {
+        Duration::from_millis(self.config.discovery.registry_ttl / 3)
+    }
+
+    /// Arma la anotación de un robot para el registro.
+    ///
+    /// # Argumentos
+    ///
+    /// * `robot_to_robot_port` - Puerto en el que el robot escucha a los demás robots.
+    /// * `screens_port` - Puerto en el que el robot escucha a las pantallas.
+    /// * `is_leader` - Si el robot se considera el líder.
+    pub fn robot_record(
+        &self,
+        robot_to_robot_port: usize,
+        screens_port: usize,
+        is_leader: bool,
+    ) -> RobotRecord {
+        RobotRecord {
+            robot_address: self.config.address(robot_to_robot_port),
+            screen_address: self.config.address(screens_port),
+            is_leader,
+        }
+    }
+
+    /// Direcciones en las que pueden estar escuchando los demás robots.
+    ///
+    /// # Retorna
+    ///
+    /// Las direcciones a probar, o un `String` describiendo el error al consultar el registro.
+    pub async fn robot_addresses(&self) -> Result<Vec<String>, String> {
+        match self.config.discovery.mode {
+            DiscoveryMode::PortRange => {
+                Ok(self.port_range(self.config.robots.first_port, self.config.robots.last_port))
+            }
+            DiscoveryMode::Seeds => Ok(self.config.discovery.robot_seeds.clone()),
+            DiscoveryMode::Registry => {
+                let (robots, _) = self.query_registry().await?;
+                Ok(robots
+                    .into_iter()
+                    .map(|robot| robot.robot_address)
+                    .collect())
+            }
+        }
+    }
+
+    /// Direcciones en las que pueden estar escuchando pantallas los robots. En el modo
+    /// `Registry`, si hay un líder anunciado, solo la del líder.
+    ///
+    /// # Retorna
+    ///
+    /// Las direcciones a probar, o un `String` describiendo el error al consultar el registro.
+    pub async fn screen_addresses(&self) -> Result<Vec<String>, String> {
+        match self.config.discovery.mode {
+            DiscoveryMode::PortRange => Ok(self.port_range(
+                self.config.screens.first_port,
+                self.config.screens.last_port,
+            )),
+            DiscoveryMode::Seeds => Ok(self.config.discovery.screen_seeds.clone()),
+            DiscoveryMode::Registry => {
+                let (robots, leader) = self.query_registry().await?;
+                Ok(robots
+                    .into_iter()
+                    .filter(|robot| {
+                        leader.is_none() || leader.as_ref() == Some(&robot.robot_address)
+                    })
+                    .map(|robot| robot.screen_address)
+                    .collect())
+            }
+        }
+    }
+
+    /// Anota al robot en el registro. Fuera del modo `Registry` no hace nada. Si el cluster
+    /// tiene un secreto configurado, firma la anotación con un desafío pedido al registro.
+    ///
+    /// # Argumentos
+    ///
+    /// * `robot` - Anotación del robot.
+    pub async fn register(&self, robot: RobotRecord) -> Result<(), String> {
+        if !self.uses_registry() {
+            return Ok(());
+        }
+        let proof = match SecretKey::from_config(&self.config.auth) {
+            Some(secret) => match self.request(RegistryRequest::Challenge).await? {
+                RegistryResponse::Challenge { challenge } => Some(RegistrationProof {
+                    challenge,
+                    proof: secret.registration_proof(&challenge, &robot),
+                }),
+                response => return Err(format!("Unexpected registry response {:?}", response)),
+            },
+            None => None,
+        };
+        match self
+            .request(RegistryRequest::Register { robot, proof })
+            .await?
+        {
+            RegistryResponse::Done => Ok(()),
+            RegistryResponse::Rejected { reason } => Err(format!(
+                "The registry rejected the registration: {}",
+                reason
+            )),
+            response => Err(format!("Unexpected registry response {:?}", response)),
+        }
+    }
+
+    /// Consulta los robots vivos y la dirección del líder anunciado.
+    pub async fn query_registry(&self) -> Result<(Vec<RobotRecord>, Option<String>), String> {
+        match self.request(RegistryRequest::Query).await? {
+            RegistryResponse::Robots { robots, leader } => Ok((robots, leader)),
+            response => Err(format!("Unexpected registry response {:?}", response)),
+        }
+    }
+
+    /// Envía un pedido al registro por una conexión nueva y espera su respuesta, como mucho
+    /// `REGISTRY_TIMEOUT` para conectarse y otro tanto para la respuesta.
+    async fn request(&self, request: RegistryRequest) -> Result<RegistryResponse, String> {
+        let registry = &self.config.discovery.registry;
+        let codec = FrameCodec::new(CodecKind::JsonLines);
+        let stream = match timeout(REGISTRY_TIMEOUT, TcpStream::connect(registry)).await {
+            Ok(stream) => stream.map_err(|error| {
+                format!("Couldn't reach the registry at {}: {}", registry, error)
+            })?,
+            Err(_) => {
+                return Err(format!(
+                    "Timed out connecting to the registry at {}",
+                    registry
+                ))
+            }
+        };
+        let (reader, mut writer) = stream.into_split();
+
+        let clock = NodeClock::global();
+        let request = Envelope::new(0, clock.send(), request);
+        let frame = codec.encode(&request).map_err(|error| error.to_string())?;
+        writer
+            .write_all(&frame)
+            .await
+            .map_err(|error| error.to_string())?;
+
+        let mut frames = codec.framed_read(reader);
+        let frame = FrameCodec::next_frame(&mut frames, REGISTRY_TIMEOUT)
+            .await
+            .map_err(|error| error.to_string())?
+            .ok_or("The registry closed the connection without answering")?;
+        let response: Envelope<RegistryResponse> =
+            codec.decode(&frame).map_err(|error| error.to_string())?;
+        clock.receive(&response.clock);
+        match response.payload {
+            Payload::Message(response) => Ok(response),
+            Payload::Heartbeat => Err("Unexpected heartbeat from the registry".to_string()),
+        }
+    }
+
+    fn port_range(&self, first_port: usize, last_port: usize) -> Vec<String> {
+        (first_port..=last_port)
+            .map(|port| self.config.address(port))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_port_range_and_seeds() {
+        let mut config = Config::default();
+        config.robots.last_port = config.robots.first_port + 1;
+        assert_eq!(
+            Discovery::new(&config).robot_addresses().await.unwrap(),
+            vec!["127.0.0.1:10000", "127.0.0.1:10001"]
+        );
+
+        config.discovery.mode = DiscoveryMode::Seeds;
+        config.discovery.screen_seeds = vec!["10.0.0.2:9000".to_string()];
+        assert_eq!(
+            Discovery::new(&config).screen_addresses().await.unwrap(),
+            vec!["10.0.0.2:9000"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_register_without_registry_is_a_no_op() {
+        let discovery = Discovery::new(&Config::default());
+        let robot = discovery.robot_record(10000, 9000, true);
+
+        assert_eq!(robot.screen_address, "127.0.0.1:9000");
+        assert!(discovery.register(robot).await.is_ok());
+    }
+}
@@ -1 +1,4 @@
+pub mod clock;
 pub mod communication;
+pub mod config;
+pub mod discovery;
@@ -1,37 +1,43 @@
-use std::sync::{mpsc, Arc};
+use std::{net::SocketAddr, sync::mpsc};
 
-use super::stock_requester::StockRequester;
-use crate::shop_connection::robot_communicator::RobotCommunicator;
+use super::stock_requester::{RobotConnection, StockRequester};
 use crate::shop_stock::stock_manager::{BackupShop, StockManager};
+use crate::utils::arg_parsing::Args;
 use crate::utils::command_interpreter::{BACKUP, EXIT};
 use actix::prelude::*;
+use common::communication::handshake::{accept_handshake, Handshake, NodeKind};
+use common::config::HeartbeatConfig;
 use tokio::{
-    io::{AsyncBufReadExt, BufReader},
-    net::TcpListener,
-    sync::Mutex,
+    io::BufReader,
+    net::{TcpListener, TcpStream},
     task::JoinHandle,
 };
-use tokio_stream::wrappers::LinesStream;
-use tracing::info;
+use tracing::{info, warn};
 
-/// Starts a new async task for listening to incoming connections in the given port.
-/// Any new connection is automatically handled by the responsible actors.
+/// Starts a new async task for listening to incoming connections in the host and port of the
+/// given arguments. Any new connection is automatically handled by the responsible actors,
+/// proposing the configured codec during the handshake, authenticating the robot with the
+/// shared secret if one is configured, and watching the robot with the configured heartbeats.
 pub fn handle_incoming_connections(
-    port: &str,
-    stock_file: &str,
-    backup_prefix: String,
+    args: Args,
     interpreter_listener_recv: mpsc::Receiver<String>,
-    max_orders_before_backup: usize,
 ) -> JoinHandle<()> {
-    let addr = format!("127.0.0.1:{}", port);
+    let addr = format!("{}:{}", args.host, args.port);
+    let handshake = Handshake::new(NodeKind::Shop, args.port.parse().unwrap_or_default())
+        .with_codec(args.codec)
+        .with_secret(args.secret);
 
-    let stock_manager = StockManager::new(stock_file, max_orders_before_backup).start();
+    let stock_manager = StockManager::new(&args.input_file, args.max_orders_before_backup).start();
+    let requester = StockRequester::new(stock_manager.clone()).start();
 
     actix::spawn(async move {
         let _ = accept_connections(
             addr,
+            handshake,
+            args.heartbeat,
             &stock_manager,
-            &backup_prefix,
+            &requester,
+            &args.backup_file_prefix,
             interpreter_listener_recv,
         )
         .await;
@@ -43,7 +49,10 @@ pub fn handle_incoming_connections(
 /// Will run until a signal from the command interpreter is received
 async fn accept_connections(
     addr: String,
+    handshake: Handshake,
+    heartbeat: HeartbeatConfig,
     stock: &Addr<StockManager>,
+    requester: &Addr<StockRequester>,
     backup_prefix: &str,
     interpreter_listener_recv: mpsc::Receiver<String>,
 ) -> Result<(), String> {
@@ -53,28 +62,59 @@ async fn accept_connections(
     info!("Listening for connections on: {}", addr);
 
     loop {
-        if let Ok((stream, _)) = listener.accept().await {
+        if let Ok((stream, peer_addr)) = listener.accept().await {
             if let Some(cmd) = execute_input(&interpreter_listener_recv, stock, backup_prefix) {
                 if cmd == EXIT {
                     return Ok(());
                 }
                 continue;
             }
-            let (reader, writer) = tokio::io::split(stream);
-            let writer = Arc::new(Mutex::new(writer));
-            let requester = StockRequester::new(stock.clone()).start();
-            RobotCommunicator::create(|ctx| {
-                RobotCommunicator::add_stream(
-                    LinesStream::new(BufReader::new(reader).lines()),
-                    ctx,
-                );
-                info!("Established new connection with robot.");
-                RobotCommunicator::new(writer.clone(), requester.clone())
+            let requester = requester.clone();
+            actix::spawn(async move {
+                if let Err(err) =
+                    connect_robot(stream, peer_addr, handshake, heartbeat, requester).await
+                {
+                    warn!("Rejected connection: {}", err);
+                }
             });
         }
     }
 }
 
+/// Performs the handshake with a newly accepted connection and, if the peer is a
+/// compatible robot, creates the connection actor that will serve it. Every robot connection
+/// forwards its orders to the same `StockRequester`.
+async fn connect_robot(
+    stream: TcpStream,
+    peer_addr: SocketAddr,
+    handshake: Handshake,
+    heartbeat: HeartbeatConfig,
+    requester: Addr<StockRequester>,
+) -> Result<(), String> {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+    let (robot, codec) = accept_handshake(&mut reader, &mut writer, &handshake, &[NodeKind::Robot])
+        .await
+        .map_err(|err| err.to_string())?;
+
+    RobotConnection::new(
+        writer,
+        codec,
+        handshake.node_id,
+        robot,
+        peer_addr,
+        requester.recipient(),
+    )
+    .with_heartbeat(heartbeat)
+    .start_with_reader(reader);
+    info!(
+        "Established new connection with robot {} using {}.",
+        robot.node_id,
+        codec.kind()
+    );
+    Ok(())
+}
+
 fn execute_input(
     interpreter_listener_recv: &mpsc::Receiver<String>,
     stock: &Addr<StockManager>,
@@ -1,24 +1,34 @@
 use std::collections::HashMap;
 
 use actix::prelude::*;
-use tracing::{debug, info, trace};
-use uuid::Uuid;
+use tracing::{debug, info, trace, warn};
 
-use crate::shop_connection::robot_communicator::SendMessage;
 use crate::shop_stock::active_order::ActiveOrder;
 use crate::shop_stock::stock_manager::{
     CancelReserve, ConfirmReserve, ReserveIceCream, StockManager,
 };
-use common::communication::shop_requests::ShopResponse;
+use common::communication::framed_connection::{
+    ConnectionEvent, FramedConnection, SendMessageThroughSocket, SocketEvent,
+};
+use common::communication::order_error::OrderError;
+use common::communication::order_id::OrderId;
+use common::communication::shop_requests::{ShopRequest, ShopResponse};
+
+/// Connection with a single robot: receives `ShopRequest`s and sends `ShopResponse`s.
+pub type RobotConnection = FramedConnection<ShopRequest, ShopResponse>;
 
-use super::robot_communicator::RobotCommunicator;
+/// Where the response to an order is sent: the robot connection it arrived through.
+pub type ResponseRecipient = Recipient<SendMessageThroughSocket<ShopResponse>>;
 
 /// Actor responsible for mediation between the stock manager and the robots.
-/// Responsible for forwarding messages both ways
+/// Responsible for forwarding messages both ways.
+/// A single requester serves every robot connection, so an order resent through another
+/// connection (e.g. by a new leader robot) is recognized by its `OrderId`.
 pub struct StockRequester {
     stock_manager: Addr<StockManager>,
-    connections: HashMap<Uuid, Addr<RobotCommunicator>>,
-    active_orders: HashMap<Uuid, ActiveOrder>,
+    active_orders: HashMap<OrderId, ActiveOrder>,
+    /// Results of the orders already answered, to answer resends without processing them again
+    finished_orders: HashMap<OrderId, Result<(), OrderError>>,
 }
 
 impl Actor for StockRequester {
@@ -27,11 +37,11 @@ impl Actor for StockRequester {
     fn stopped(&mut self, _ctx: &mut Self::Context) {
         info!("Stock requester stopped.");
         for (_, order) in self.active_orders.iter() {
-            order.requester.do_send(SendMessage {
-                message: ShopResponse::OrderResult {
+            order.requester.do_send(SendMessageThroughSocket {
+                message_to_send_through_socket: ShopResponse::OrderResult {
+                    order_id: order.id,
                     screen_id: order.screen_index,
-                    result: Err("Stock requester stopped".to_string()),
-                    screen_address: order.screen_address.clone(),
+                    result: Err(OrderError::ShopUnavailable),
                 },
             });
         }
@@ -43,8 +53,8 @@ impl StockRequester {
     pub fn new(stock_manager: Addr<StockManager>) -> Self {
         StockRequester {
             stock_manager,
-            connections: HashMap::new(),
             active_orders: HashMap::new(),
+            finished_orders: HashMap::new(),
         }
     }
 }
@@ -52,11 +62,11 @@ impl StockRequester {
 #[derive(Message)]
 #[rtype(result = "Result<(), String>")]
 pub struct MakeOrder {
-    pub return_addr: Addr<RobotCommunicator>,
+    pub return_addr: ResponseRecipient,
+    pub order_id: OrderId,
     pub size: u32,
     pub flavors: Vec<String>,
     pub screen_index: usize,
-    pub screen_address: String,
 }
 
 impl Handler<MakeOrder> for StockRequester {
@@ -65,11 +75,32 @@ impl Handler<MakeOrder> for StockRequester {
     /// Places an order for the given flavors and the given size with the manager.
     /// The rest of the message fields are identification info used by the rest of the system
     /// to keep track of orders and will be forwarded as needed.
+    /// An order whose id was already received, through any connection, is rejected without
+    /// touching the stock: if it is still being processed its result will be sent through the
+    /// new connection, and if it is finished its result is sent again.
     fn handle(&mut self, msg: MakeOrder, ctx: &mut Self::Context) -> Self::Result {
+        if let Some(order) = self.active_orders.get_mut(&msg.order_id) {
+            warn!("Order {} is already being processed", msg.order_id);
+            order.requester = msg.return_addr;
+            return Err(format!("Order {} is already being processed", msg.order_id));
+        }
+        if let Some(result) = self.finished_orders.get(&msg.order_id) {
+            warn!(
+                "Order {} was already processed, resending its result",
+                msg.order_id
+            );
+            msg.return_addr.do_send(SendMessageThroughSocket {
+                message_to_send_through_socket: ShopResponse::OrderResult {
+                    order_id: msg.order_id,
+                    screen_id: msg.screen_index,
+                    result: result.clone(),
+                },
+            });
+            return Err(format!("Order {} was already processed", msg.order_id));
+        }
         let order = ActiveOrder::from_message(&msg);
         debug!("Received order: {:?}", order);
         let id = order.id;
-        self.connections.insert(id, msg.return_addr);
         self.active_orders.insert(id, order);
 
         for flavor in &msg.flavors {
@@ -89,8 +120,8 @@ impl Handler<MakeOrder> for StockRequester {
 #[derive(Message, PartialEq, Eq, Debug)]
 #[rtype(result = "Result<(), String>")]
 pub struct StockResult {
-    pub requester: Uuid,
-    pub result: Option<(String, u32)>,
+    pub requester: OrderId,
+    pub result: Result<(String, u32), OrderError>,
 }
 
 impl Handler<StockResult> for StockRequester {
@@ -103,21 +134,19 @@ impl Handler<StockResult> for StockRequester {
             .active_orders
             .get_mut(&msg.requester)
             .ok_or("Order not found")?;
-        let mut msg_to_send = None;
+        let mut order_result = None;
         match msg.result {
-            Some(flavor) => {
+            Ok(flavor) => {
                 debug!(
                     "Successfully ordered {} {} for order ID {}",
-                    flavor.1,
-                    flavor.0,
-                    msg.requester.as_fields().0
+                    flavor.1, flavor.0, msg.requester
                 );
                 order.flavors_ordered.push(flavor.clone());
             }
-            None => {
+            Err(error) => {
                 debug!(
-                    "Couldn't get all flavors for order ID {}",
-                    msg.requester.as_fields().0
+                    "Couldn't get all flavors for order ID {}: {}",
+                    msg.requester, error
                 );
                 self.stock_manager
                     .try_send(CancelReserve {
@@ -129,16 +158,11 @@ impl Handler<StockResult> for StockRequester {
                     })
                     .map_err(|err| err.to_string())?;
 
-                msg_to_send = Some(ShopResponse::OrderResult {
-                    screen_id: order.screen_index,
-                    result: Err("Couldn't get all flavors".to_string()),
-                    screen_address: order.screen_address.clone(),
-                })
+                order_result = Some(Err(error));
             }
         };
         if order.is_done() {
-            info!("Fulfilled an order.");
-            debug!("Order {} finished.", msg.requester.as_fields().0);
+            info!("Fulfilled order {}.", msg.requester);
             self.stock_manager
                 .try_send(ConfirmReserve {
                     reserves: order
@@ -148,23 +172,163 @@ impl Handler<StockResult> for StockRequester {
                         .collect(),
                 })
                 .map_err(|err| err.to_string())?;
-            msg_to_send = Some(ShopResponse::OrderResult {
-                screen_id: order.screen_index,
-                result: Ok(()),
-                screen_address: order.screen_address.clone(),
-            });
+            order_result = Some(Ok(()));
         }
-        if let Some(msg_to_send) = msg_to_send {
+        if let Some(result) = order_result {
+            self.finished_orders.insert(msg.requester, result.clone());
+            let msg_to_send = ShopResponse::OrderResult {
+                order_id: order.id,
+                screen_id: order.screen_index,
+                result,
+            };
             trace!("Sending message to robot: {:?}", msg_to_send);
-            let conn = self
-                .connections
-                .get(&msg.requester)
-                .ok_or("Connection not found")?;
-            conn.do_send(SendMessage {
-                message: msg_to_send,
+            order.requester.do_send(SendMessageThroughSocket {
+                message_to_send_through_socket: msg_to_send,
             });
             self.active_orders.remove(&msg.requester);
         }
         Ok(())
     }
 }
+
+impl Handler<ConnectionEvent<ShopRequest, ShopResponse>> for StockRequester {
+    type Result = ();
+
+    /// Handles events from the robot connection, turning incoming orders into `MakeOrder`s.
+    fn handle(
+        &mut self,
+        msg: ConnectionEvent<ShopRequest, ShopResponse>,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        match msg.event {
+            SocketEvent::Received(ShopRequest::IceCreamOrder {
+                order_id,
+                flavors,
+                size,
+                screen_id,
+            }) => {
+                trace!(
+                    "Received order {} from robot {}: size {}, no. of flavors {} (idx {})",
+                    order_id,
+                    msg.peer.node_id,
+                    size,
+                    flavors.len(),
+                    screen_id
+                );
+                ctx.address().do_send(MakeOrder {
+                    return_addr: msg.connection.recipient(),
+                    order_id,
+                    size,
+                    flavors,
+                    screen_index: screen_id,
+                });
+            }
+            SocketEvent::Malformed(err) => {
+                warn!("Discarding malformed message from robot: {}", err)
+            }
+            SocketEvent::WriteFailed(err) => {
+                warn!("Error sending message to robot: {}", err)
+            }
+            SocketEvent::Disconnected(reason) => match reason {
+                Some(reason) => info!("Robot {} disconnected: {}", msg.peer.node_id, reason),
+                None => info!("Robot {} disconnected.", msg.peer.node_id),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    /// Stands in for a robot connection, keeping every response sent through it.
+    struct MockConnection {
+        responses: Vec<ShopResponse>,
+    }
+
+    impl Actor for MockConnection {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<SendMessageThroughSocket<ShopResponse>> for MockConnection {
+        type Result = Result<(), String>;
+
+        fn handle(
+            &mut self,
+            msg: SendMessageThroughSocket<ShopResponse>,
+            _: &mut Self::Context,
+        ) -> Self::Result {
+            self.responses.push(msg.message_to_send_through_socket);
+            Ok(())
+        }
+    }
+
+    #[derive(Message)]
+    #[rtype(result = "Vec<ShopResponse>")]
+    struct TakeResponses;
+
+    impl Handler<TakeResponses> for MockConnection {
+        type Result = Vec<ShopResponse>;
+
+        fn handle(&mut self, _: TakeResponses, _: &mut Self::Context) -> Self::Result {
+            std::mem::take(&mut self.responses)
+        }
+    }
+
+    fn order(connection: &Addr<MockConnection>, order_id: OrderId) -> MakeOrder {
+        MakeOrder {
+            return_addr: connection.clone().recipient(),
+            order_id,
+            size: 10,
+            flavors: vec!["VAINILLA".to_string()],
+            screen_index: 0,
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_same_order_over_two_connections_takes_stock_once() {
+        let stock_file = std::env::temp_dir().join(format!("stock-{}.csv", OrderId::new()));
+        std::fs::write(&stock_file, "VAINILLA,10\n").unwrap();
+        let stock = StockManager::new(stock_file.to_str().unwrap(), 100).start();
+        let requester = StockRequester::new(stock).start();
+        let first = MockConnection { responses: vec![] }.start();
+        let second = MockConnection { responses: vec![] }.start();
+        let order_id = OrderId::new();
+
+        assert!(requester
+            .send(order(&first, order_id))
+            .await
+            .unwrap()
+            .is_ok());
+        // Reenviado por otro robot mientras se procesa: la respuesta va por la nueva conexión.
+        assert!(requester
+            .send(order(&second, order_id))
+            .await
+            .unwrap()
+            .is_err());
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let answered = ShopResponse::OrderResult {
+            order_id,
+            screen_id: 0,
+            result: Ok(()),
+        };
+        assert!(first.send(TakeResponses).await.unwrap().is_empty());
+        assert_eq!(
+            second.send(TakeResponses).await.unwrap(),
+            vec![answered.clone()]
+        );
+
+        // Reenviado después de terminar: se repite el resultado sin volver a pedir el stock,
+        // que ya no alcanzaría para otro pedido igual.
+        assert!(requester
+            .send(order(&first, order_id))
+            .await
+            .unwrap()
+            .is_err());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(first.send(TakeResponses).await.unwrap(), vec![answered]);
+
+        std::fs::remove_file(stock_file).unwrap();
+    }
+}
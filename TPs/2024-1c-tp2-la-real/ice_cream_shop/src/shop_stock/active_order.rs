@@ -1,34 +1,30 @@
-use crate::shop_connection::{robot_communicator::RobotCommunicator, stock_requester::MakeOrder};
-use actix::Addr;
-use uuid::Uuid;
+use crate::shop_connection::stock_requester::{MakeOrder, ResponseRecipient};
+use common::communication::order_id::OrderId;
 
 /// Struct encapsulating all information related to an order.
 #[derive(Debug)]
 pub struct ActiveOrder {
-    /// Unique identifier for this order
-    pub id: Uuid,
-    /// Address to send the response to
-    pub requester: Addr<RobotCommunicator>,
+    /// Unique identifier for this order, generated by the screen
+    pub id: OrderId,
+    /// Connection to send the response to: the one the order last arrived through
+    pub requester: ResponseRecipient,
     /// Number of flavors to order
     pub flavors_to_order: u32,
     /// Flavors that have been ordered and their amounts
     pub flavors_ordered: Vec<(String, u32)>,
-    /// Index of the screen that made the order
+    /// Index of the order in the screen that made it
     pub screen_index: usize,
-    /// Address of the screen that made the order
-    pub screen_address: String,
 }
 
 impl ActiveOrder {
     /// Creates a new active order from a `MakeOrder` message.
     pub fn from_message(msg: &MakeOrder) -> Self {
         ActiveOrder {
-            id: Uuid::new_v4(),
+            id: msg.order_id,
             requester: msg.return_addr.clone(),
             flavors_to_order: msg.flavors.len() as u32,
             flavors_ordered: Vec::new(),
             screen_index: msg.screen_index,
-            screen_address: msg.screen_address.clone(),
         }
     }
 
@@ -1,5 +1,6 @@
 use actix::prelude::*;
 use actix::{Actor, Context, Handler, Message};
+use common::communication::{order_error::OrderError, order_id::OrderId};
 use fut::wrap_future;
 use std::{
     collections::HashMap,
@@ -11,7 +12,6 @@ use std::{
 use tokio::task::JoinHandle;
 use tokio::{fs::File, io::AsyncWriteExt, sync::Mutex};
 use tracing::{debug, error, info, trace, warn};
-use uuid::Uuid;
 
 use crate::shop_connection::stock_requester::StockResult;
 use tokio::fs;
@@ -133,7 +133,7 @@ impl Actor for StockManager {
 #[rtype(result = "Result<(), String>")]
 pub struct ReserveIceCream {
     pub requester_addr: Recipient<StockResult>,
-    pub request_id: Uuid,
+    pub request_id: OrderId,
     pub flavor: String,
     pub amount: u32,
 }
@@ -151,7 +151,7 @@ impl Handler<ReserveIceCream> for StockManager {
             "Received request of {} {} for {}",
             msg.amount,
             msg.flavor,
-            msg.request_id.as_fields().0
+            msg.request_id
         );
         let flavor = msg.flavor.to_ascii_uppercase();
         let amount = msg.amount;
@@ -193,11 +193,11 @@ impl Handler<ReserveIceCream> for StockManager {
                             "Could reserve {} {} for {}",
                             msg.amount,
                             msg.flavor,
-                            msg.request_id.as_fields().0
+                            msg.request_id
                         );
                         req_addr.do_send(StockResult {
                             requester: req_idx,
-                            result: Some((flavor, amount)),
+                            result: Ok((flavor, amount)),
                         });
                         Ok(())
                     } else {
@@ -205,11 +205,15 @@ impl Handler<ReserveIceCream> for StockManager {
                             "Could not get {} {} for {}",
                             msg.amount,
                             msg.flavor,
-                            msg.request_id.as_fields().0
+                            msg.request_id
                         );
                         req_addr.do_send(StockResult {
                             requester: req_idx,
-                            result: None,
+                            result: Err(OrderError::OutOfStock {
+                                flavor,
+                                requested: amount,
+                                available: total,
+                            }),
                         });
                         Err(NOT_ENOUGH_STOCK_ERR.to_string())
                     }
@@ -218,12 +222,11 @@ impl Handler<ReserveIceCream> for StockManager {
             None => {
                 info!(
                     "Received request for non-existent flavor: {}, for: {}",
-                    msg.flavor,
-                    msg.request_id.as_fields().0
+                    msg.flavor, msg.request_id
                 );
                 req_addr.do_send(StockResult {
                     requester: req_idx,
-                    result: None,
+                    result: Err(OrderError::UnknownFlavor { flavor: msg.flavor }),
                 });
                 Box::pin(async { Err(NOT_AVAILABLE_FLAVOR_ERR.to_string()) })
             }
@@ -510,7 +513,7 @@ mod test {
         let result = stock
             .send(ReserveIceCream {
                 requester_addr: requester.clone().recipient(),
-                request_id: Uuid::nil(),
+                request_id: OrderId::new(),
                 flavor: "VAINILLA".to_string(),
                 amount: 15,
             })
@@ -528,7 +531,7 @@ mod test {
         let result = stock
             .send(ReserveIceCream {
                 requester_addr: requester.clone().recipient(),
-                request_id: Uuid::nil(),
+                request_id: OrderId::new(),
                 flavor: "VAINILLA".to_string(),
                 amount: 5,
             })
@@ -546,7 +549,7 @@ mod test {
         let result_1 = stock
             .send(ReserveIceCream {
                 requester_addr: requester.clone().recipient(),
-                request_id: Uuid::nil(),
+                request_id: OrderId::new(),
                 flavor: "VAINILLA".to_string(),
                 amount: 9,
             })
@@ -556,7 +559,7 @@ mod test {
         let result_2 = stock
             .send(ReserveIceCream {
                 requester_addr: requester.clone().recipient(),
-                request_id: Uuid::nil(),
+                request_id: OrderId::new(),
                 flavor: "VAINILLA".to_string(),
                 amount: 5,
             })
@@ -575,7 +578,7 @@ mod test {
         let result = stock
             .send(ReserveIceCream {
                 requester_addr: requester.clone().recipient(),
-                request_id: Uuid::nil(),
+                request_id: OrderId::new(),
                 flavor: "PISTACHO".to_string(),
                 amount: 5,
             })
@@ -593,7 +596,7 @@ mod test {
         let _ = stock
             .send(ReserveIceCream {
                 requester_addr: requester.clone().recipient(),
-                request_id: Uuid::nil(),
+                request_id: OrderId::new(),
                 flavor: "VAINILLA".to_string(),
                 amount: 10,
             })
@@ -614,7 +617,7 @@ mod test {
         let reserve_result = stock
             .send(ReserveIceCream {
                 requester_addr: requester.clone().recipient(),
-                request_id: Uuid::nil(),
+                request_id: OrderId::new(),
                 flavor: "VAINILLA".to_string(),
                 amount: 5,
             })
@@ -651,7 +654,7 @@ mod test {
         let _ = stock
             .send(ReserveIceCream {
                 requester_addr: requester.clone().recipient(),
-                request_id: Uuid::nil(),
+                request_id: OrderId::new(),
                 flavor: "VAINILLA".to_string(),
                 amount: 10,
             })
@@ -698,7 +701,7 @@ mod test {
         let _ = stock
             .send(ReserveIceCream {
                 requester_addr: requester.clone().recipient(),
-                request_id: Uuid::nil(),
+                request_id: OrderId::new(),
                 flavor: "VAINILLA".to_string(),
                 amount: 10,
             })
@@ -731,10 +734,11 @@ mod test {
         let stock = testing_manager().start();
         let requester = MockRequester::new().start();
 
+        let order_id = OrderId::new();
         let _ = stock
             .send(ReserveIceCream {
                 requester_addr: requester.clone().recipient(),
-                request_id: Uuid::nil(),
+                request_id: order_id,
                 flavor: "VAINILLA".to_string(),
                 amount: 10,
             })
@@ -744,8 +748,8 @@ mod test {
         let result = requester
             .send(GotResult {
                 result: StockResult {
-                    requester: Uuid::nil(),
-                    result: Some(("VAINILLA".to_string(), 10)),
+                    requester: order_id,
+                    result: Ok(("VAINILLA".to_string(), 10)),
                 },
             })
             .await
@@ -759,10 +763,11 @@ mod test {
         let stock = testing_manager().start();
         let requester = MockRequester::new().start();
 
+        let order_id = OrderId::new();
         let _ = stock
             .send(ReserveIceCream {
                 requester_addr: requester.clone().recipient(),
-                request_id: Uuid::nil(),
+                request_id: order_id,
                 flavor: "VAINILLA".to_string(),
                 amount: 15,
             })
@@ -772,8 +777,12 @@ mod test {
         let result = requester
             .send(GotResult {
                 result: StockResult {
-                    requester: Uuid::nil(),
-                    result: None,
+                    requester: order_id,
+                    result: Err(OrderError::OutOfStock {
+                        flavor: "VAINILLA".to_string(),
+                        requested: 15,
+                        available: 10,
+                    }),
                 },
             })
             .await
@@ -1,9 +1,17 @@
+use common::communication::auth::SecretKey;
+use common::communication::codec::CodecKind;
+use common::config::{Config, HeartbeatConfig};
 use std::env;
 use tracing::Level;
 
 #[derive(Debug)]
 pub struct Args {
+    pub host: String,
     pub port: String,
+    pub codec: CodecKind,
+    pub heartbeat: HeartbeatConfig,
+    pub secret: Option<SecretKey>,
+    pub vector_clock: bool,
     pub tracing_level: Level,
     pub input_file: String,
     pub backup_file_prefix: String,
@@ -12,15 +20,24 @@ pub struct Args {
 
 impl Args {
     /// Parse command line args. Return parsed arguments or defaults if some or all were not given.
+    /// Host, port, codec, heartbeats, the auth secret and the clock kind come from the shared
+    /// cluster configuration.
     pub fn parse_args() -> Option<Args> {
-        let port = "9999".to_string();
+        let mut args: Vec<String> = env::args().skip(1).collect();
+        let config = match Config::from_args(&mut args) {
+            Ok(config) => config,
+            Err(err) => {
+                println!("Invalid configuration: {}", err);
+                return None;
+            }
+        };
+        let port = config.shop.port.to_string();
         let mut tracing_level = Level::INFO;
         let mut input_file = "data/stock_files/test_stock.csv".to_string();
         let mut backup_file_prefix = "backup".to_string();
         let mut max_orders_before_backup = 25;
 
-        let args: Vec<String> = env::args().collect();
-        let mut i = 1;
+        let mut i = 0;
         while i < args.len() {
             match args[i].as_str() {
                 "-d" | "--debug-level" => {
@@ -82,7 +99,12 @@ impl Args {
         }
 
         Some(Args {
+            host: config.host,
             port,
+            codec: config.codec,
+            heartbeat: config.heartbeat,
+            secret: SecretKey::from_config(&config.auth),
+            vector_clock: config.clock.vector,
             tracing_level,
             input_file,
             backup_file_prefix,
@@ -99,5 +121,7 @@ fn print_help() {
     println!(
         "  -f, --backup-frequency   FREQ  Number of orders before creating a backup (default: 10)"
     );
+    println!("  --config FILE            Cluster configuration file (default: config.toml, or $ICE_CREAM_CONFIG)");
+    println!("  --set KEY=VALUE          Override a configuration key, e.g. shop.port=9998");
     println!("  -h, --help               Print this help message");
 }
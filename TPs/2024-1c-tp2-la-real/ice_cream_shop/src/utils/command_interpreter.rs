@@ -11,7 +11,7 @@ pub const BACKUP: &str = "b";
 /// the rest of the system of the commands received.
 pub fn handle_commands(
     setup_interpreter_recv: mpsc::Receiver<mpsc::Sender<String>>,
-    shop_port: String,
+    shop_address: String,
 ) -> JoinHandle<Result<(), String>> {
     std::thread::spawn(move || -> Result<(), String> {
         info!("Command interpreter started");
@@ -26,7 +26,7 @@ pub fn handle_commands(
                 EXIT => {
                     info!("Interpreter received exit command");
                     let _ = interpreter_listener_send.send(EXIT.to_string());
-                    let _ = TcpStream::connect(format!("127.0.0.1:{}", shop_port));
+                    let _ = TcpStream::connect(&shop_address);
 
                     if let Some(system) = System::try_current() {
                         info!("Stopping system");
@@ -37,7 +37,7 @@ pub fn handle_commands(
                 BACKUP => {
                     info!("Interpreter received backup command");
                     let _ = interpreter_listener_send.send(BACKUP.to_string());
-                    let _ = TcpStream::connect(format!("127.0.0.1:{}", shop_port));
+                    let _ = TcpStream::connect(&shop_address);
                 }
                 _ => {
                     warn!("Unknown command. Available commands: {}, {}.", EXIT, BACKUP);
@@ -1,32 +1,46 @@
 use actix::prelude::*;
+use common::clock::NodeClock;
+use common::communication::handshake::NodeKind;
+use std::fmt;
 use std::sync::mpsc::{self, channel};
 use tracing::{debug, info};
+use tracing_subscriber::fmt::{format::Writer, time::FormatTime};
 
 use crate::{
     shop_connection,
     utils::{arg_parsing::Args, command_interpreter},
 };
 
+/// Tracing timer that prints the shop's logical clock instead of the wall clock.
+struct LogicalTime;
+
+impl FormatTime for LogicalTime {
+    fn format_time(&self, w: &mut Writer<'_>) -> fmt::Result {
+        write!(w, "{}", NodeClock::global().stamp())
+    }
+}
+
 /// Main function of the program. Sets up the shop actors and starts the command interpreter thread.
 pub fn setup_shop(args: Args) -> Result<(), String> {
+    NodeClock::init_global(
+        &NodeClock::node_name(NodeKind::Shop, args.port.parse().unwrap_or_default()),
+        args.vector_clock,
+    );
     tracing_subscriber::fmt()
         .with_max_level(args.tracing_level)
+        .with_timer(LogicalTime)
         .init();
     info!("Setting up everything...");
     debug!("Arguments received: {:?}", args);
 
     let (setup_interpreter_send, setup_interpreter_recv) = channel::<mpsc::Sender<String>>();
 
-    let interpreter_handle =
-        command_interpreter::handle_commands(setup_interpreter_recv, args.port.clone());
+    let interpreter_handle = command_interpreter::handle_commands(
+        setup_interpreter_recv,
+        format!("{}:{}", args.host, args.port),
+    );
 
-    System::new().block_on(run_shop(
-        setup_interpreter_send,
-        args.port,
-        args.input_file,
-        args.backup_file_prefix,
-        args.max_orders_before_backup,
-    ))?;
+    System::new().block_on(run_shop(setup_interpreter_send, args))?;
 
     interpreter_handle
         .join()
@@ -37,19 +51,13 @@ pub fn setup_shop(args: Args) -> Result<(), String> {
 /// Async main of the program.
 async fn run_shop(
     setup_interpreter_send: mpsc::Sender<mpsc::Sender<String>>,
-    port: String,
-    input_file: String,
-    backup_prefix: String,
-    max_orders_before_backup: usize,
+    args: Args,
 ) -> Result<(), String> {
     let (interpreter_listener_send, interpreter_listener_recv) = channel::<String>();
 
     let shop_handle = shop_connection::robot_listener::handle_incoming_connections(
-        &port,
-        &input_file,
-        backup_prefix,
+        args,
         interpreter_listener_recv,
-        max_orders_before_backup,
     );
 
     setup_interpreter_send
@@ -0,0 +1,32 @@
+use std::error::Error;
+
+use actix::{Actor, System};
+use common::clock::NodeClock;
+use common::communication::auth::SecretKey;
+use common::config::Config;
+use registry_server::{serve, RobotRegistry};
+
+pub mod registry_server;
+
+pub fn start() -> Result<(), Box<dyn Error>> {
+    let mut initial_arguments: Vec<String> = std::env::args().collect();
+    initial_arguments.remove(0); // no necesito el nombre del programa
+
+    let config = Config::from_args(&mut initial_arguments)?;
+    if !initial_arguments.is_empty() {
+        return Err(format!("Unexpected arguments {:?}", initial_arguments).into());
+    }
+
+    NodeClock::init_global("registry", config.clock.vector);
+    let address = config.discovery.registry.clone();
+    System::new().block_on(async move {
+        let registry = RobotRegistry::new(
+            config.discovery.registry_ttl,
+            SecretKey::from_config(&config.auth),
+        )
+        .start();
+        serve(&address, registry).await
+    })?;
+
+    Ok(())
+}
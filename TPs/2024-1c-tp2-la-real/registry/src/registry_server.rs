@@ -0,0 +1,443 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use actix::{Actor, Addr, Context, Handler, Message, MessageResult};
+use common::clock::NodeClock;
+use common::clocked_println;
+use common::communication::{
+    auth::SecretKey,
+    codec::{CodecKind, FrameCodec},
+    envelope::{Envelope, Payload},
+    registry_communication::{RegistrationProof, RegistryRequest, RegistryResponse, RobotRecord},
+};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+};
+use uuid::Uuid;
+
+/// Tiempo máximo de espera de cada pedido de una conexión. Una conexión que no envía nada
+/// durante ese tiempo se cierra.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `RobotRegistry` es un actor que guarda los robots anotados, identificados por su dirección.
+/// Un robot que no renueva su anotación dentro del `ttl` se da por muerto y deja de informarse.
+///
+/// Con un secreto configurado, solo acepta anotaciones firmadas con un desafío que haya
+/// entregado dentro del `ttl` y que no se haya usado antes.
+pub struct RobotRegistry {
+    robots: HashMap<String, (RobotRecord, Instant)>,
+    ttl: Duration,
+    secret: Option<SecretKey>,
+    challenges: HashMap<Uuid, Instant>,
+}
+
+impl Actor for RobotRegistry {
+    type Context = Context<Self>;
+}
+
+impl RobotRegistry {
+    /// Crea un registro vacío.
+    ///
+    /// # Argumentos
+    ///
+    /// * `ttl` - Milisegundos que dura una anotación sin renovar.
+    /// * `secret` - Secreto del cluster, o `None` si las anotaciones no se autentican.
+    pub fn new(ttl: u64, secret: Option<SecretKey>) -> Self {
+        Self {
+            robots: HashMap::new(),
+            ttl: Duration::from_millis(ttl),
+            secret,
+            challenges: HashMap::new(),
+        }
+    }
+
+    /// Atiende un pedido al registro.
+    ///
+    /// # Argumentos
+    ///
+    /// * `request` - Pedido recibido.
+    /// * `now` - Momento en el que se atiende, para el vencimiento de las anotaciones.
+    ///
+    /// # Retorna
+    ///
+    /// La respuesta para quien hizo el pedido.
+    fn answer(&mut self, request: RegistryRequest, now: Instant) -> RegistryResponse {
+        let ttl = self.ttl;
+        self.robots
+            .retain(|_, (_, registered_at)| now.duration_since(*registered_at) < ttl);
+        self.challenges
+            .retain(|_, issued_at| now.duration_since(*issued_at) < ttl);
+
+        match request {
+            RegistryRequest::Challenge => {
+                let challenge = SecretKey::challenge();
+                self.challenges.insert(challenge, now);
+                RegistryResponse::Challenge { challenge }
+            }
+            RegistryRequest::Register { robot, proof } => {
+                if let Err(reason) = self.authenticate(&robot, proof) {
+                    clocked_println!(
+                        "[Registry] Rejected the registration of robot at {}: {}",
+                        robot.robot_address,
+                        reason
+                    );
+                    return RegistryResponse::Rejected { reason };
+                }
+                if !self.robots.contains_key(&robot.robot_address) {
+                    clocked_println!("[Registry] Robot registered at {}", robot.robot_address);
+                }
+                self.robots
+                    .insert(robot.robot_address.clone(), (robot, now));
+                RegistryResponse::Done
+            }
+            RegistryRequest::Query => {
+                let mut robots: Vec<RobotRecord> = self
+                    .robots
+                    .values()
+                    .map(|(robot, _)| robot.clone())
+                    .collect();
+                robots.sort_by(|a, b| a.robot_address.cmp(&b.robot_address));
+                let leader = robots
+                    .iter()
+                    .filter(|robot| robot.is_leader)
+                    .map(|robot| robot.robot_address.clone())
+                    .max();
+                RegistryResponse::Robots { robots, leader }
+            }
+        }
+    }
+
+    /// Verifica la prueba de una anotación, consumiendo su desafío. Sin secreto configurado,
+    /// toda anotación es válida.
+    ///
+    /// # Retorna
+    ///
+    /// Un `String` con el motivo si la anotación no se acepta.
+    fn authenticate(
+        &mut self,
+        robot: &RobotRecord,
+        proof: Option<RegistrationProof>,
+    ) -> Result<(), String> {
+        let secret = match &self.secret {
+            Some(secret) => secret,
+            None => return Ok(()),
+        };
+        let proof = proof.ok_or("missing proof of the cluster secret")?;
+        if self.challenges.remove(&proof.challenge).is_none() {
+            return Err("unknown or already used challenge".to_string());
+        }
+        if !secret.verify_registration(&proof.challenge, robot, &proof.proof) {
+            return Err("invalid proof of the cluster secret".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "RegistryResponse")]
+/// Mensaje con un pedido recibido por el socket del registro.
+pub struct RegistryMessage {
+    pub request: RegistryRequest,
+}
+
+impl Handler<RegistryMessage> for RobotRegistry {
+    type Result = MessageResult<RegistryMessage>;
+
+    fn handle(&mut self, msg: RegistryMessage, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.answer(msg.request, Instant::now()))
+    }
+}
+
+/// Escucha pedidos al registro hasta que se cierre el programa.
+///
+/// # Argumentos
+///
+/// * `address` - Dirección `host:port` en la que escuchar.
+/// * `registry` - Dirección del actor `RobotRegistry`.
+///
+/// # Retorna
+///
+/// Un `String` describiendo el error si no se pudo escuchar en la dirección.
+pub async fn serve(address: &str, registry: Addr<RobotRegistry>) -> Result<(), String> {
+    let listener = TcpListener::bind(address)
+        .await
+        .map_err(|error| error.to_string())?;
+    clocked_println!("[Registry] Listening on {}", address);
+    serve_listener(listener, registry).await;
+    Ok(())
+}
+
+/// Atiende las conexiones que lleguen a un socket ya abierto, hasta que se cierre el programa.
+///
+/// # Argumentos
+///
+/// * `listener` - Socket en el que se aceptan las conexiones.
+/// * `registry` - Dirección del actor `RobotRegistry`.
+pub async fn serve_listener(listener: TcpListener, registry: Addr<RobotRegistry>) {
+    loop {
+        if let Ok((stream, peer_addr)) = listener.accept().await {
+            let registry = registry.clone();
+            actix::spawn(async move {
+                if let Err(error) = answer_connection(stream, registry).await {
+                    clocked_println!("[Registry] Error answering {}: {}", peer_addr, error);
+                }
+            });
+        }
+    }
+}
+
+/// Responde los pedidos de una conexión, uno por frame, hasta que el otro extremo la cierre
+/// o pase `REQUEST_TIMEOUT` sin recibir un pedido completo.
+async fn answer_connection(stream: TcpStream, registry: Addr<RobotRegistry>) -> Result<(), String> {
+    let codec = FrameCodec::new(CodecKind::JsonLines);
+    let clock = NodeClock::global();
+    let (reader, mut writer) = stream.into_split();
+    let mut frames = codec.framed_read(reader);
+
+    while let Some(frame) = FrameCodec::next_frame(&mut frames, REQUEST_TIMEOUT)
+        .await
+        .map_err(|error| error.to_string())?
+    {
+        let request: Envelope<RegistryRequest> =
+            codec.decode(&frame).map_err(|error| error.to_string())?;
+        clock.receive(&request.clock);
+        let request = match request.payload {
+            Payload::Message(request) => request,
+            Payload::Heartbeat => continue,
+        };
+        let response = registry
+            .send(RegistryMessage { request })
+            .await
+            .map_err(|error| error.to_string())?;
+        let response = Envelope::new(0, clock.send(), response);
+        let frame = codec.encode(&response).map_err(|error| error.to_string())?;
+        writer
+            .write_all(&frame)
+            .await
+            .map_err(|error| error.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{config::Config, config::DiscoveryMode, discovery::Discovery};
+
+    fn robot(port: usize, is_leader: bool) -> RobotRecord {
+        robot_on("127.0.0.1", port, is_leader)
+    }
+
+    fn robot_on(host: &str, port: usize, is_leader: bool) -> RobotRecord {
+        RobotRecord {
+            robot_address: format!("{}:{}", host, port),
+            screen_address: format!("{}:{}", host, port - 1000),
+            is_leader,
+        }
+    }
+
+    #[test]
+    fn test_query_returns_live_robots_and_leader() {
+        let mut registry = RobotRegistry::new(1000, None);
+        let now = Instant::now();
+        registry.answer(
+            RegistryRequest::Register {
+                robot: robot(10001, true),
+                proof: None,
+            },
+            now,
+        );
+        registry.answer(
+            RegistryRequest::Register {
+                robot: robot(10000, false),
+                proof: None,
+            },
+            now,
+        );
+
+        assert_eq!(
+            registry.answer(RegistryRequest::Query, now),
+            RegistryResponse::Robots {
+                robots: vec![robot(10000, false), robot(10001, true)],
+                leader: Some("127.0.0.1:10001".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_robots_on_different_hosts_may_share_a_port() {
+        let mut registry = RobotRegistry::new(1000, None);
+        let now = Instant::now();
+        for robot in [
+            robot_on("10.0.0.2", 10000, true),
+            robot_on("10.0.0.1", 10000, false),
+        ] {
+            registry.answer(RegistryRequest::Register { robot, proof: None }, now);
+        }
+
+        assert_eq!(
+            registry.answer(RegistryRequest::Query, now),
+            RegistryResponse::Robots {
+                robots: vec![
+                    robot_on("10.0.0.1", 10000, false),
+                    robot_on("10.0.0.2", 10000, true)
+                ],
+                leader: Some("10.0.0.2:10000".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_registrations_expire_unless_renewed() {
+        let mut registry = RobotRegistry::new(1000, None);
+        let start = Instant::now();
+        for port in [10000, 10001] {
+            registry.answer(
+                RegistryRequest::Register {
+                    robot: robot(port, false),
+                    proof: None,
+                },
+                start,
+            );
+        }
+        registry.answer(
+            RegistryRequest::Register {
+                robot: robot(10001, false),
+                proof: None,
+            },
+            start + Duration::from_millis(800),
+        );
+
+        assert_eq!(
+            registry.answer(RegistryRequest::Query, start + Duration::from_millis(1500)),
+            RegistryResponse::Robots {
+                robots: vec![robot(10001, false)],
+                leader: None,
+            }
+        );
+    }
+
+    fn challenge(registry: &mut RobotRegistry, now: Instant) -> Uuid {
+        match registry.answer(RegistryRequest::Challenge, now) {
+            RegistryResponse::Challenge { challenge } => challenge,
+            response => panic!("unexpected response {:?}", response),
+        }
+    }
+
+    fn register(robot: RobotRecord, secret: &SecretKey, challenge: Uuid) -> RegistryRequest {
+        RegistryRequest::Register {
+            proof: Some(RegistrationProof {
+                challenge,
+                proof: secret.registration_proof(&challenge, &robot),
+            }),
+            robot,
+        }
+    }
+
+    #[test]
+    fn test_registration_proofs_travel_as_base64() {
+        let secret = SecretKey::new("helado");
+        let challenge = SecretKey::challenge();
+        let request = register(robot(10000, false), &secret, challenge);
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert!(json["Register"]["proof"]["proof"].is_string());
+        assert_eq!(
+            serde_json::from_value::<RegistryRequest>(json).unwrap(),
+            request
+        );
+    }
+
+    #[test]
+    fn test_registrations_need_a_fresh_signed_challenge_if_there_is_a_secret() {
+        let secret = SecretKey::new("helado");
+        let mut registry = RobotRegistry::new(1000, Some(secret));
+        let now = Instant::now();
+        let rejected = |response| matches!(response, RegistryResponse::Rejected { .. });
+
+        let unsigned = RegistryRequest::Register {
+            robot: robot(10001, true),
+            proof: None,
+        };
+        assert!(rejected(registry.answer(unsigned, now)));
+        let challenge_1 = challenge(&mut registry, now);
+        let wrong_secret = register(robot(10001, true), &SecretKey::new("otro"), challenge_1);
+        assert!(rejected(registry.answer(wrong_secret, now)));
+
+        // La firma cubre la anotación: no sirve para anunciarse como líder.
+        let challenge_2 = challenge(&mut registry, now);
+        let mut promoted = register(robot(10001, false), &secret, challenge_2);
+        if let RegistryRequest::Register { robot, .. } = &mut promoted {
+            robot.is_leader = true;
+        }
+        assert!(rejected(registry.answer(promoted, now)));
+
+        let challenge_3 = challenge(&mut registry, now);
+        let signed = register(robot(10001, true), &secret, challenge_3);
+        assert_eq!(registry.answer(signed.clone(), now), RegistryResponse::Done);
+        // Cada desafío sirve para una sola anotación.
+        assert!(rejected(registry.answer(signed, now)));
+    }
+
+    /// Levanta un registro en un puerto libre y devuelve la configuración para usarlo.
+    async fn serve_on_free_port(secret: &str) -> Config {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut config = Config::default();
+        config.discovery.mode = DiscoveryMode::Registry;
+        config.discovery.registry = listener.local_addr().unwrap().to_string();
+        config.auth.secret = secret.to_string();
+        let registry = RobotRegistry::new(1000, SecretKey::from_config(&config.auth)).start();
+        actix::spawn(serve_listener(listener, registry));
+        config
+    }
+
+    #[actix_rt::test]
+    async fn test_serve_answers_discovery_queries() {
+        let config = serve_on_free_port("").await;
+
+        let discovery = Discovery::new(&config);
+        discovery
+            .register(discovery.robot_record(10000, 9000, false))
+            .await
+            .unwrap();
+        discovery
+            .register(discovery.robot_record(10001, 9001, true))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            discovery.robot_addresses().await.unwrap(),
+            vec!["127.0.0.1:10000", "127.0.0.1:10001"]
+        );
+        assert_eq!(
+            discovery.screen_addresses().await.unwrap(),
+            vec!["127.0.0.1:9001"]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_serve_only_registers_robots_that_know_the_secret() {
+        let config = serve_on_free_port("helado").await;
+        let discovery = Discovery::new(&config);
+        discovery
+            .register(discovery.robot_record(10000, 9000, false))
+            .await
+            .unwrap();
+
+        let mut intruder = config.clone();
+        intruder.auth.secret = "otro".to_string();
+        let intruder = Discovery::new(&intruder);
+        assert!(intruder
+            .register(intruder.robot_record(10001, 9001, true))
+            .await
+            .is_err());
+
+        assert_eq!(
+            discovery.query_registry().await.unwrap(),
+            (vec![discovery.robot_record(10000, 9000, false)], None)
+        );
+    }
+}
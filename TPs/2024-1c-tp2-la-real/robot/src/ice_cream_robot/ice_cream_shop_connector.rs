@@ -1,18 +1,13 @@
-use std::sync::Arc;
-
-use actix::{Actor, Addr, AsyncContext};
+use actix::Addr;
+use common::clocked_println;
+use common::communication::handshake::{initiate_handshake, Handshake, NodeKind};
+use common::config::Config;
 use tokio::{
-    io::{split, AsyncBufReadExt, BufReader},
+    io::{split, BufReader},
     net::TcpStream,
-    sync::Mutex,
 };
-use tokio_stream::wrappers::LinesStream;
 
-use super::{
-    ice_cream_shop_connection::IceCreamShopConnection,
-    manage_robot_connection::ManageRobotConnection,
-    robot_constants::{CONNECTION_HOST, ICE_CREAM_SHOP_PORT},
-};
+use super::manage_robot_connection::{IceCreamShopConnection, ManageRobotConnection};
 
 pub struct IceCreamShopConnector {}
 
@@ -22,25 +17,47 @@ impl IceCreamShopConnector {
     /// # Argumentos
     ///
     /// * `manage_robot_conection` - Dirección del actor `ManageRobotConnection`.
+    /// * `handshake` - Handshake con el que el robot se presenta ante la heladería.
+    /// * `config` - Configuración del cluster, con la dirección de la heladería.
     ///
     /// # Retorna
     ///
     /// Un `Result` con la dirección del actor `IceCreamShopConnection` si la conexión se configuró correctamente, o un `String` en caso de error.
     pub async fn setup_connection(
         manage_robot_conection: Addr<ManageRobotConnection>,
+        handshake: Handshake,
+        config: &Config,
     ) -> Result<Addr<IceCreamShopConnection>, String> {
-        let address = format!("{}:{}", CONNECTION_HOST, ICE_CREAM_SHOP_PORT);
+        let address = config.address(config.shop.port);
 
         let stream = TcpStream::connect(address.clone())
             .await
             .map_err(|error| error.to_string())?;
+        let peer_addr = stream.peer_addr().map_err(|error| error.to_string())?;
+
+        let (reader, mut writer) = split(stream);
+        let mut reader = BufReader::new(reader);
 
-        let (reader, writer) = split(stream);
+        let (ice_cream_shop, codec) =
+            initiate_handshake(&mut reader, &mut writer, &handshake, &[NodeKind::Shop])
+                .await
+                .map_err(|error| error.to_string())?;
+        clocked_println!(
+            "Connected to ice cream shop {} using {}",
+            ice_cream_shop.node_id,
+            codec.kind()
+        );
 
-        let ice_cream_shop_connection = IceCreamShopConnection::create(|ctx| {
-            ctx.add_stream(LinesStream::new(BufReader::new(reader).lines()));
-            IceCreamShopConnection::new(manage_robot_conection, Arc::new(Mutex::new(writer)))
-        });
+        let ice_cream_shop_connection = IceCreamShopConnection::new(
+            writer,
+            codec,
+            handshake.node_id,
+            ice_cream_shop,
+            peer_addr,
+            manage_robot_conection.recipient(),
+        )
+        .with_heartbeat(config.heartbeat)
+        .start_with_reader(reader);
 
         Ok(ice_cream_shop_connection)
     }
@@ -1,21 +1,26 @@
-use std::{collections::HashMap, net::SocketAddr};
+use std::collections::HashMap;
 
 use actix::{Actor, Addr, AsyncContext, Context, Handler, Message};
 
-use crate::ice_cream_robot::robot_to_robot_connection::{SendLeaderSelected, SendStartElections};
-
-use super::{
-    ice_cream_shop_connection::{IceCreamShopConnection, RequestIceCreamFlavor},
-    robot_to_robot_connection::{RobotToRobotConnection, SendMessageThroughSocketToRobot},
-    robot_to_screen_connection::{
-        CloseConnectionFromScreen, RobotToScreenConnection, SendMessageThroughSocketToScreen,
-        SuccessForScreenMessage,
-    },
+use common::clocked_println;
+use common::communication::framed_connection::{
+    CloseConnection, ConnectionEvent, FramedConnection, SendMessageThroughSocket, SocketEvent,
 };
-use common::communication::shop_requests::ShopResponse;
+use common::communication::shop_requests::{ShopRequest, ShopResponse};
 use common::communication::{
-    robot_to_robot_communication::RobotRequest, screen_robot_communication::Response,
+    order_error::OrderError,
+    order_id::OrderId,
+    robot_to_robot_communication::RobotRequest,
+    screen_robot_communication::{Request, Response},
 };
+use common::discovery::Discovery;
+
+/// Conexión con la tienda de helados: recibe `ShopResponse` y envía `ShopRequest`.
+pub type IceCreamShopConnection = FramedConnection<ShopResponse, ShopRequest>;
+/// Conexión con una pantalla: recibe `Request` y envía `Response`.
+pub type RobotToScreenConnection = FramedConnection<Request, Response>;
+/// Conexión con otro robot: ambos extremos intercambian `RobotRequest`.
+pub type RobotToRobotConnection = FramedConnection<RobotRequest, RobotRequest>;
 
 /// `ManageRobotConnection` es un actor que gestiona las conexiones entre el robot, la tienda de helados y las pantallas.
 ///
@@ -23,7 +28,10 @@ use common::communication::{
 /// También se encarga de la gestión de las órdenes entre la tienda de helados y las pantallas.
 pub struct ManageRobotConnection {
     robot_to_ice_cream_shop_connection: Option<Addr<IceCreamShopConnection>>,
-    robot_to_screen_connections: HashMap<String, Addr<RobotToScreenConnection>>,
+    robot_to_screen_connections: HashMap<usize, Addr<RobotToScreenConnection>>,
+    /// Pantalla que originó cada orden enviada a la tienda y aún sin resultado, junto con el
+    /// índice de la orden en esa pantalla.
+    pending_orders: HashMap<OrderId, (usize, usize)>,
     robot_to_robot_connections: HashMap<usize, Addr<RobotToRobotConnection>>,
 
     robot_to_robot_id: usize,
@@ -31,24 +39,27 @@ pub struct ManageRobotConnection {
 
     robot_to_screen_id: usize,
     robot_to_screen_leader_id: Option<usize>,
+
+    discovery: Discovery,
 }
 
 impl ManageRobotConnection {
-
     /// Crea una nueva instancia de `ManageRobotConnection`.
     ///
     /// # Argumentos
     ///
     /// * `robot_to_robot_id` - ID del robot actual en la red de robots.
     /// * `robot_to_screen_id` - ID de la pantalla actual con la que el robot está conectado.
+    /// * `discovery` - Descubrimiento del cluster, en el que el robot se anota.
     ///
     /// # Retorna
     ///
     /// Una nueva instancia de `ManageRobotConnection` con las conexiones vacías y sin líder asignado.
-    pub fn new(robot_to_robot_id: usize, robot_to_screen_id: usize) -> Self {
+    pub fn new(robot_to_robot_id: usize, robot_to_screen_id: usize, discovery: Discovery) -> Self {
         Self {
             robot_to_ice_cream_shop_connection: None,
             robot_to_screen_connections: HashMap::new(),
+            pending_orders: HashMap::new(),
 
             robot_to_screen_id,
             robot_to_screen_leader_id: None,
@@ -56,8 +67,29 @@ impl ManageRobotConnection {
             robot_to_robot_id,
             robot_to_robot_leader_id: None,
             robot_to_robot_connections: HashMap::new(),
+
+            discovery,
         }
     }
+
+    /// Anota al robot en el registro, informando si se considera el líder. Fuera del modo
+    /// de descubrimiento por registro no hace nada.
+    fn register_in_discovery(&self) {
+        if !self.discovery.uses_registry() {
+            return;
+        }
+        let robot = self.discovery.robot_record(
+            self.robot_to_robot_id,
+            self.robot_to_screen_id,
+            self.robot_to_robot_leader_id == Some(self.robot_to_robot_id),
+        );
+        let discovery = self.discovery.clone();
+        actix::spawn(async move {
+            if let Err(error) = discovery.register(robot).await {
+                clocked_println!("Couldn't register in the registry: {}", error);
+            }
+        });
+    }
 }
 
 #[derive(Message)]
@@ -92,20 +124,30 @@ impl Handler<AddIceCreamShopConnectionMessage> for ManageRobotConnection {
 
 impl Actor for ManageRobotConnection {
     type Context = Context<Self>;
+
+    /// Anota al robot en el registro y renueva la anotación periódicamente.
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if self.discovery.uses_registry() {
+            self.register_in_discovery();
+            ctx.run_interval(self.discovery.registry_refresh(), |robot, _ctx| {
+                robot.register_in_discovery()
+            });
+        }
+    }
 }
 
 #[derive(Message)]
 #[rtype(result = "Result<(), String>")]
 /// Mensaje utilizado para informar al `ManageRobotConnection` sobre una orden resuelta de la tienda de helados.
 pub struct SolvedOrderFromIceCreamShop {
-    pub message: String,
+    pub message: ShopResponse,
 }
 
-
 impl Handler<SolvedOrderFromIceCreamShop> for ManageRobotConnection {
     type Result = Result<(), String>;
 
-    /// Procesa el resultado de una orden de la tienda de helados y lo envía a la pantalla correspondiente.
+    /// Procesa el resultado de una orden de la tienda de helados y lo envía a la pantalla que la originó,
+    /// identificada a partir del `OrderId`.
     ///
     /// # Argumentos
     ///
@@ -116,22 +158,30 @@ impl Handler<SolvedOrderFromIceCreamShop> for ManageRobotConnection {
     ///
     /// Un `Result` que indica si la operación fue exitosa o no.
     fn handle(&mut self, msg: SolvedOrderFromIceCreamShop, ctx: &mut Self::Context) -> Self::Result {
-        match serde_json::from_str(&msg.message).map_err(|error| error.to_string())? {
+        match msg.message {
             ShopResponse::OrderResult {
+                order_id,
                 screen_id,
                 result,
-                screen_address,
             } => {
-                let message = serde_json::to_string(&Response::RobotToScreenResult {
+                let (screen, _) = match self.pending_orders.remove(&order_id) {
+                    Some(pending_order) => pending_order,
+                    None => {
+                        clocked_println!("Received result for unknown order {}", order_id);
+                        return Ok(());
+                    }
+                };
+                clocked_println!(
+                    "Order {} finished at the ice cream shop: {:?}",
+                    order_id, result
+                );
+                let message = Response::RobotToScreenResult {
+                    order_id,
                     index: screen_id,
                     result,
-                })
-                .map_err(|error| error.to_string())?;
+                };
                 ctx.address()
-                    .try_send(SuccessInProcessingOrder {
-                        screen_address,
-                        message,
-                    })
+                    .try_send(SuccessInProcessingOrder { screen, message })
                     .map_err(|error| error.to_string())
             }
         }
@@ -142,31 +192,47 @@ impl Handler<SolvedOrderFromIceCreamShop> for ManageRobotConnection {
 #[rtype(result = "Result<(), String>")]
 /// Mensaje utilizado para informar al `ManageRobotConnection` sobre una orden recibida de una pantalla.
 pub struct ReceivedOrderFromScreen {
-    pub message: String,
+    pub message: ShopRequest,
 }
 
 impl Handler<ReceivedOrderFromScreen> for ManageRobotConnection {
     type Result = Result<(), String>;
 
     /// Envía una solicitud a la tienda de helados para obtener el sabor del helado basado en la orden recibida.
+    /// Si no hay conexión con la tienda, la orden se resuelve con `OrderError::ShopUnavailable`.
     ///
     /// # Argumentos
     ///
     /// * `msg` - El mensaje `ReceivedOrderFromScreen` que contiene la orden de la pantalla.
-    /// * `_ctx` - El contexto del actor `ManageRobotConnection`.
+    /// * `ctx` - El contexto del actor `ManageRobotConnection`.
     ///
     /// # Retorna
     ///
     /// Un `Result` que indica si la operación fue exitosa o no.
-    fn handle(&mut self, msg: ReceivedOrderFromScreen, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: ReceivedOrderFromScreen, ctx: &mut Self::Context) -> Self::Result {
         if let Some(robot_to_ice_cream_shop_connection) = &self.robot_to_ice_cream_shop_connection {
             robot_to_ice_cream_shop_connection
-                .try_send(RequestIceCreamFlavor {
-                    message: msg.message,
+                .try_send(SendMessageThroughSocket {
+                    message_to_send_through_socket: msg.message,
                 })
                 .map_err(|error| error.to_string())
         } else {
-            Ok(())
+            match msg.message {
+                ShopRequest::IceCreamOrder {
+                    order_id,
+                    screen_id,
+                    ..
+                } => ctx
+                    .address()
+                    .try_send(SolvedOrderFromIceCreamShop {
+                        message: ShopResponse::OrderResult {
+                            order_id,
+                            screen_id,
+                            result: Err(OrderError::ShopUnavailable),
+                        },
+                    })
+                    .map_err(|error| error.to_string()),
+            }
         }
     }
 }
@@ -175,14 +241,15 @@ impl Handler<ReceivedOrderFromScreen> for ManageRobotConnection {
 #[rtype(result = "Result<(), String>")]
 /// Mensaje utilizado para informar al `ManageRobotConnection` sobre el éxito en el procesamiento de una orden.
 pub struct SuccessInProcessingOrder {
-    message: String,
-    screen_address: String,
+    message: Response,
+    screen: usize,
 }
 
 impl Handler<SuccessInProcessingOrder> for ManageRobotConnection {
     type Result = Result<(), String>;
 
-    /// Si el robot es el líder, envía un mensaje de éxito a la pantalla correspondiente.
+    /// Si el robot es el líder, envía un mensaje de éxito a la pantalla correspondiente. Durante
+    /// una elección, sin líder, la respuesta se descarta y la pantalla reintenta la orden.
     ///
     /// # Argumentos
     ///
@@ -193,10 +260,10 @@ impl Handler<SuccessInProcessingOrder> for ManageRobotConnection {
     ///
     /// Un `Result` que indica si la operación fue exitosa o no.
     fn handle(&mut self, msg: SuccessInProcessingOrder, _: &mut Self::Context) -> Self::Result {
-        if self.robot_to_robot_id == self.robot_to_robot_leader_id.unwrap() {
-            if let Some(screen) = self.robot_to_screen_connections.get(&msg.screen_address) {
+        if self.robot_to_robot_leader_id == Some(self.robot_to_robot_id) {
+            if let Some(screen) = self.robot_to_screen_connections.get(&msg.screen) {
                 return screen
-                    .try_send(SuccessForScreenMessage {
+                    .try_send(SendMessageThroughSocket {
                         message_to_send_through_socket: msg.message,
                     })
                     .map_err(|error| error.to_string());
@@ -206,12 +273,11 @@ impl Handler<SuccessInProcessingOrder> for ManageRobotConnection {
     }
 }
 
-
 #[derive(Message)]
 #[rtype(result = "Result<(), String>")]
 /// Mensaje para agregar una nueva conexión entre el robot y una pantalla.
 pub struct AddRobotToScreenConnectionMessage {
-    pub address: SocketAddr,
+    pub screen_id: usize,
     pub robot_to_screen_connection: Addr<RobotToScreenConnection>,
 }
 
@@ -222,7 +288,8 @@ impl Handler<AddRobotToScreenConnectionMessage> for ManageRobotConnection {
     ///
     /// # Argumentos
     ///
-    /// * `msg` - El mensaje `AddRobotToScreenConnectionMessage` que contiene la dirección de la pantalla y la conexión.
+    /// * `msg` - El mensaje `AddRobotToScreenConnectionMessage` que contiene el id de la pantalla y la conexión.
+    ///   Si la pantalla ya estaba conectada, la nueva conexión reemplaza a la anterior.
     /// * `_ctx` - El contexto del actor `ManageRobotConnection`.
     ///
     /// # Retorna
@@ -233,10 +300,8 @@ impl Handler<AddRobotToScreenConnectionMessage> for ManageRobotConnection {
         msg: AddRobotToScreenConnectionMessage,
         _: &mut Self::Context,
     ) -> Self::Result {
-        self.robot_to_screen_connections.insert(
-            msg.address.to_string(),
-            msg.robot_to_screen_connection.clone(),
-        );
+        self.robot_to_screen_connections
+            .insert(msg.screen_id, msg.robot_to_screen_connection);
         Ok(())
     }
 }
@@ -245,18 +310,19 @@ impl Handler<AddRobotToScreenConnectionMessage> for ManageRobotConnection {
 #[rtype(result = "Result<(), String>")]
 /// Mensaje para eliminar una conexión entre el robot y una pantalla.
 pub struct RemoveRobotToScreenConnection {
-    pub stream_addr: SocketAddr,
+    pub screen_id: usize,
+    pub robot_to_screen_connection: Addr<RobotToScreenConnection>,
 }
 
-
 impl Handler<RemoveRobotToScreenConnection> for ManageRobotConnection {
     type Result = Result<(), String>;
 
-    /// Este método elimina una conexión de pantalla del mapa de conexiones de pantalla del robot.
+    /// Este método elimina una conexión de pantalla del mapa de conexiones de pantalla del robot,
+    /// salvo que la pantalla ya se haya reconectado con una conexión nueva.
     ///
     /// # Argumentos
     ///
-    /// * `msg` - El mensaje `RemoveRobotToScreenConnection` que contiene la dirección de la pantalla a eliminar.
+    /// * `msg` - El mensaje `RemoveRobotToScreenConnection` que contiene el id de la pantalla y la conexión a eliminar.
     /// * `_ctx` - El contexto del actor `ManageRobotConnection`.
     ///
     /// # Retorna
@@ -267,8 +333,11 @@ impl Handler<RemoveRobotToScreenConnection> for ManageRobotConnection {
         msg: RemoveRobotToScreenConnection,
         _: &mut Self::Context,
     ) -> Self::Result {
-        self.robot_to_screen_connections
-            .remove(&msg.stream_addr.to_string());
+        if self.robot_to_screen_connections.get(&msg.screen_id)
+            == Some(&msg.robot_to_screen_connection)
+        {
+            self.robot_to_screen_connections.remove(&msg.screen_id);
+        }
         Ok(())
     }
 }
@@ -295,13 +364,11 @@ impl Handler<AskLeaderMessage> for ManageRobotConnection {
     /// Un `Result` que indica si la solicitud fue enviada exitosamente.
     fn handle(&mut self, msg: AskLeaderMessage, _ctx: &mut Self::Context) -> Self::Result {
         if let Some(robot_to_screen_leader_id) = self.robot_to_screen_leader_id {
-            let message = serde_json::to_string(&Response::RobotToScreenLeaderPort {
-                leader_port: robot_to_screen_leader_id,
-            })
-            .map_err(|error| error.to_string())?;
             msg.robot_to_screen_connection_address
-                .try_send(SendMessageThroughSocketToScreen {
-                    message_to_send_through_socket: message,
+                .try_send(SendMessageThroughSocket {
+                    message_to_send_through_socket: Response::RobotToScreenLeaderPort {
+                        leader_port: robot_to_screen_leader_id,
+                    },
                 })
                 .map_err(|error| error.to_string())?;
         }
@@ -317,7 +384,6 @@ pub struct StartLeaderElection {}
 impl Handler<StartLeaderElection> for ManageRobotConnection {
     type Result = Result<(), String>;
 
-
     /// Este método inicia una elección de líder entre los robots, seleccionando al robot con el ID más alto como el nuevo líder.
     ///
     /// # Argumentos
@@ -329,7 +395,7 @@ impl Handler<StartLeaderElection> for ManageRobotConnection {
     ///
     /// Un `Result` que indica si la elección fue iniciada exitosamente.
     fn handle(&mut self, _msg: StartLeaderElection, _ctx: &mut Self::Context) -> Self::Result {
-        println!("Let the elections for a leader begin!");
+        clocked_println!("Let the elections for a leader begin!");
         if let Some(max_robot_to_robot_id) = self.robot_to_robot_connections.keys().max() {
             if max_robot_to_robot_id > &self.robot_to_robot_id {
                 let robot_to_robot_connection = self
@@ -340,20 +406,28 @@ impl Handler<StartLeaderElection> for ManageRobotConnection {
                         max_robot_to_robot_id
                     ))?;
                 return robot_to_robot_connection
-                    .try_send(SendStartElections {})
+                    .try_send(SendMessageThroughSocket {
+                        message_to_send_through_socket: RobotRequest::StartElection {},
+                    })
                     .map_err(|error| error.to_string());
             }
         };
 
-        println!("L'État, c'est moi. I'm the new robot leader {}", self.robot_to_robot_id);
+        clocked_println!(
+            "L'État, c'est moi. I'm the new robot leader {}",
+            self.robot_to_robot_id
+        );
 
         self.robot_to_robot_leader_id = Some(self.robot_to_robot_id);
         self.robot_to_screen_leader_id = Some(self.robot_to_screen_id);
+        self.register_in_discovery();
         for (_, robot_to_robot_connection) in self.robot_to_robot_connections.iter() {
             robot_to_robot_connection
-                .try_send(SendLeaderSelected {
-                    robot_to_robot_id: self.robot_to_robot_id,
-                    robot_to_screen_id: self.robot_to_screen_id,
+                .try_send(SendMessageThroughSocket {
+                    message_to_send_through_socket: RobotRequest::LeaderSelected {
+                        robot_to_robot_leader_id: self.robot_to_robot_id,
+                        robot_to_screen_leader_id: self.robot_to_screen_id,
+                    },
                 })
                 .map_err(|error| error.to_string())?;
         }
@@ -386,10 +460,11 @@ impl Handler<LeaderSelected> for ManageRobotConnection {
     fn handle(&mut self, msg: LeaderSelected, _ctx: &mut Self::Context) -> Self::Result {
         self.robot_to_robot_leader_id = Some(msg.robot_to_robot_leader_id);
         self.robot_to_screen_leader_id = Some(msg.robot_to_screen_leader_id);
+        self.register_in_discovery();
 
         for robot_to_screen_connection in self.robot_to_screen_connections.values() {
             robot_to_screen_connection
-                .try_send(CloseConnectionFromScreen {})
+                .try_send(CloseConnection)
                 .map_err(|error| error.to_string())?;
         }
 
@@ -402,6 +477,7 @@ impl Handler<LeaderSelected> for ManageRobotConnection {
 ///Mensaje para notificar al `ManageRobotConnection` sobre la muerte de un robot.
 pub struct ElectionForDeadRobot {
     pub closed_server_id: usize,
+    pub robot_to_robot_connection: Addr<RobotToRobotConnection>,
 }
 
 impl Handler<ElectionForDeadRobot> for ManageRobotConnection {
@@ -409,36 +485,42 @@ impl Handler<ElectionForDeadRobot> for ManageRobotConnection {
 
     /// Este método actualiza la lista de conexiones de robots para eliminar la conexión del robot que ha cerrado el servidor.
     /// Si el robot que ha muerto era el líder, se inicia un proceso para elegir un nuevo líder.
+    /// Si el robot ya se reconectó con una conexión nueva, el cierre de la anterior se ignora.
     ///
     /// # Argumentos
     ///
-    /// * `msg` - El mensaje `ElectionForDeadRobot` que contiene el ID del robot muerto.
+    /// * `msg` - El mensaje `ElectionForDeadRobot` que contiene el ID del robot muerto y la conexión que se cerró.
     /// * `_ctx` - El contexto del actor `ManageRobotConnection`.
     ///
     /// # Retorna
     ///
     /// Un `Result` que indica si la eliminación del robot y el posible inicio de una nueva elección de líder fueron exitosos.
     fn handle(&mut self, msg: ElectionForDeadRobot, _ctx: &mut Self::Context) -> Self::Result {
-        self.robot_to_robot_connections
-            .remove(&msg.closed_server_id)
-            .ok_or(format!("A not existing robot died??? {}", msg.closed_server_id))?;
+        if self.robot_to_robot_connections.get(&msg.closed_server_id)
+            != Some(&msg.robot_to_robot_connection)
+        {
+            clocked_println!(
+                "Ignoring the close of an old connection with robot {}",
+                msg.closed_server_id
+            );
+            return Ok(());
+        }
+        self.robot_to_robot_connections.remove(&msg.closed_server_id);
 
         if let Some(leader_id) = self.robot_to_robot_leader_id {
             if leader_id != msg.closed_server_id {
                 return Ok(());
             }
-            println!("The King died! Don't worry, long live the King! Starting elections for a dead robot leader ");
+            clocked_println!("The King died! Don't worry, long live the King! Starting elections for a dead robot leader ");
             self.robot_to_robot_leader_id = None;
             if let Some(max_robot_to_robot_id) = self.robot_to_robot_connections.keys().max() {
                 if let Some(min_robot_to_robot_id) = self.robot_to_robot_connections.keys().min() {
                     if &self.robot_to_robot_id < min_robot_to_robot_id {
-                        let message = serde_json::to_string(&RobotRequest::StartElection {})
-                            .map_err(|error| error.to_string())?;
                         self.robot_to_robot_connections
                             .get(max_robot_to_robot_id)
                             .ok_or(format!("The max leader didn't exist? {}", max_robot_to_robot_id))?
-                            .try_send(SendMessageThroughSocketToRobot {
-                                message_to_send_through_socket: message,
+                            .try_send(SendMessageThroughSocket {
+                                message_to_send_through_socket: RobotRequest::StartElection {},
                             })
                             .map_err(|error| error.to_string())?;
                     }
@@ -446,6 +528,7 @@ impl Handler<ElectionForDeadRobot> for ManageRobotConnection {
                 return Ok(());
             };
             self.robot_to_robot_leader_id = Some(self.robot_to_robot_id);
+            self.register_in_discovery();
         }
         Ok(())
     }
@@ -453,79 +536,219 @@ impl Handler<ElectionForDeadRobot> for ManageRobotConnection {
 
 #[derive(Message)]
 #[rtype(result = "Result<(), String>")]
-/// Mensaje para agregar una nueva conexión entre robots.
+/// Mensaje para agregar una nueva conexión entre robots, una vez completado el handshake.
 pub struct AddRobotToRobotConnectionMessage {
-    pub robot_to_robot_id: Option<usize>,
+    pub robot_to_robot_id: usize,
     pub robot_to_robot_connection_address: Addr<RobotToRobotConnection>,
 }
 
 impl Handler<AddRobotToRobotConnectionMessage> for ManageRobotConnection {
     type Result = Result<(), String>;
 
-    /// Este método agrega una nueva conexión entre robots al mapa de conexiones de robots del robot actual y envía una solicitud para obtener información del robot.
+    /// Este método agrega una nueva conexión entre robots al mapa de conexiones de robots del robot actual.
     ///
     /// # Argumentos
     ///
-    /// * `msg` - El mensaje `AddRobotToRobotConnectionMessage` que contiene el ID del robot y la dirección de la conexión.
+    /// * `msg` - El mensaje `AddRobotToRobotConnectionMessage` que contiene el ID del robot, declarado en su handshake, y la dirección de la conexión.
     /// * `_ctx` - El contexto del actor `ManageRobotConnection`.
     ///
     /// # Retorna
     ///
-    /// Un `Result` que indica si la operación de agregar la conexión y enviar la solicitud de información fue exitosa.
+    /// Un `Result` que indica si la operación de agregar la conexión fue exitosa.
     fn handle(
         &mut self,
         msg: AddRobotToRobotConnectionMessage,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        if let Some(robot_to_robot_id) = msg.robot_to_robot_id {
-            self.robot_to_robot_connections.insert(
-                robot_to_robot_id,
-                msg.robot_to_robot_connection_address.clone(),
-            );
-        }
-
-        let message = serde_json::to_string(&RobotRequest::GetMyInformation {
-            robot_to_robot_id: self.robot_to_robot_id,
-        })
-        .map_err(|error| error.to_string())?;
-
-        msg.robot_to_robot_connection_address
-            .try_send(SendMessageThroughSocketToRobot {
-                message_to_send_through_socket: message,
-            })
-            .map_err(|error| error.to_string())
+        self.robot_to_robot_connections
+            .insert(msg.robot_to_robot_id, msg.robot_to_robot_connection_address);
+        Ok(())
     }
 }
 
-#[derive(Message)]
-#[rtype(result = "Result<(), String>")]
-/// Mensaje para registrar una nueva conexión entre robots.
-pub struct RegisterRobotToRobotConnectionMessage {
-    pub robot_to_robot_id: usize,
-    pub robot_to_robot_connection: Addr<RobotToRobotConnection>,
+impl Handler<ConnectionEvent<ShopResponse, ShopRequest>> for ManageRobotConnection {
+    type Result = ();
+
+    /// Procesa los eventos de la conexión con la tienda de helados.
+    ///
+    /// # Argumentos
+    ///
+    /// * `msg` - El evento recibido desde `IceCreamShopConnection`.
+    /// * `ctx` - El contexto del actor `ManageRobotConnection`.
+    fn handle(
+        &mut self,
+        msg: ConnectionEvent<ShopResponse, ShopRequest>,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        match msg.event {
+            SocketEvent::Received(message) => {
+                if ctx
+                    .address()
+                    .try_send(SolvedOrderFromIceCreamShop { message })
+                    .is_err()
+                {
+                    clocked_println!("Error receiving message from ice cream shop")
+                }
+            }
+            SocketEvent::Malformed(error) => clocked_println!(
+                "Discarding malformed message from ice cream shop: {}",
+                error
+            ),
+            SocketEvent::WriteFailed(error) => {
+                clocked_println!("Error sending message to ice cream shop: {}", error)
+            }
+            SocketEvent::Disconnected(reason) => {
+                match reason {
+                    Some(reason) => clocked_println!(
+                        "Lost connection with ice cream shop {}: {}",
+                        msg.peer.node_id, reason
+                    ),
+                    None => {
+                        clocked_println!("Lost connection with ice cream shop {}", msg.peer.node_id)
+                    }
+                }
+                self.robot_to_ice_cream_shop_connection = None;
+                for (order_id, (screen, index)) in self.pending_orders.drain() {
+                    let message = Response::RobotToScreenResult {
+                        order_id,
+                        index,
+                        result: Err(OrderError::ShopUnavailable),
+                    };
+                    ctx.address()
+                        .do_send(SuccessInProcessingOrder { screen, message });
+                }
+            }
+        }
+    }
 }
 
-impl Handler<RegisterRobotToRobotConnectionMessage> for ManageRobotConnection {
-    type Result = Result<(), String>;
+impl Handler<ConnectionEvent<Request, Response>> for ManageRobotConnection {
+    type Result = ();
 
-    /// Este método agrega una nueva conexión entre robots al mapa de conexiones de robots del robot actual.
+    /// Procesa los eventos de la conexión con una pantalla.
     ///
     /// # Argumentos
     ///
-    /// * `msg` - El mensaje `RegisterRobotToRobotConnectionMessage` que contiene el ID del robot y la dirección de la conexión.
-    /// * `_ctx` - El contexto del actor `ManageRobotConnection`.
+    /// * `msg` - El evento recibido desde `RobotToScreenConnection`.
+    /// * `ctx` - El contexto del actor `ManageRobotConnection`.
+    fn handle(
+        &mut self,
+        msg: ConnectionEvent<Request, Response>,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let result = match msg.event {
+            SocketEvent::Received(Request::ScreenToRobotOrder {
+                order_id,
+                index,
+                flavors,
+                size,
+            }) => {
+                clocked_println!(
+                    "Order {} received from screen {}",
+                    order_id, msg.peer.node_id
+                );
+                self.pending_orders
+                    .insert(order_id, (msg.peer.node_id, index));
+                let message = ShopRequest::IceCreamOrder {
+                    order_id,
+                    flavors,
+                    size,
+                    screen_id: index,
+                };
+                ctx.address()
+                    .try_send(ReceivedOrderFromScreen { message })
+                    .map_err(|error| error.to_string())
+            }
+            SocketEvent::Received(Request::ScreenToRobotAskLeader {}) => ctx
+                .address()
+                .try_send(AskLeaderMessage {
+                    robot_to_screen_connection_address: msg.connection,
+                })
+                .map_err(|error| error.to_string()),
+            SocketEvent::Malformed(error) => {
+                clocked_println!("Discarding malformed message from screen: {}", error);
+                Ok(())
+            }
+            SocketEvent::WriteFailed(error) => {
+                clocked_println!("Error sending message to screen: {}", error);
+                Ok(())
+            }
+            SocketEvent::Disconnected(_) => ctx
+                .address()
+                .try_send(RemoveRobotToScreenConnection {
+                    screen_id: msg.peer.node_id,
+                    robot_to_screen_connection: msg.connection,
+                })
+                .map_err(|error| error.to_string()),
+        };
+        if let Err(error) = result {
+            clocked_println!("Error receiving message from screen: {}", error);
+        }
+    }
+}
+
+impl Handler<ConnectionEvent<RobotRequest, RobotRequest>> for ManageRobotConnection {
+    type Result = ();
+
+    /// Procesa los eventos de la conexión con otro robot. Si la conexión se cierra,
+    /// se considera que el robot murió.
     ///
-    /// # Retorna
+    /// # Argumentos
     ///
-    /// Un `Result` que indica si la operación de registrar la conexión fue exitosa.
+    /// * `msg` - El evento recibido desde `RobotToRobotConnection`.
+    /// * `ctx` - El contexto del actor `ManageRobotConnection`.
     fn handle(
         &mut self,
-        msg: RegisterRobotToRobotConnectionMessage,
-        _ctx: &mut Self::Context,
+        msg: ConnectionEvent<RobotRequest, RobotRequest>,
+        ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.robot_to_robot_connections
-            .insert(msg.robot_to_robot_id, msg.robot_to_robot_connection.clone());
-
-        Ok(())
+        let result = match msg.event {
+            SocketEvent::Received(RobotRequest::StartElection {}) => ctx
+                .address()
+                .try_send(StartLeaderElection {})
+                .map_err(|error| error.to_string()),
+            SocketEvent::Received(RobotRequest::LeaderSelected {
+                robot_to_robot_leader_id,
+                robot_to_screen_leader_id,
+            }) => ctx
+                .address()
+                .try_send(LeaderSelected {
+                    robot_to_robot_leader_id,
+                    robot_to_screen_leader_id,
+                })
+                .map_err(|error| error.to_string()),
+            SocketEvent::Malformed(error) => {
+                clocked_println!("Discarding malformed message from robot: {}", error);
+                Ok(())
+            }
+            SocketEvent::WriteFailed(error) => {
+                clocked_println!(
+                    "Error sending message to robot {}: {}",
+                    msg.peer.node_id, error
+                );
+                Ok(())
+            }
+            SocketEvent::Disconnected(reason) => {
+                clocked_println!(
+                    "A robot with id <{}> died! we need an election. I love democracy",
+                    msg.peer.node_id
+                );
+                if let Some(reason) = reason {
+                    clocked_println!(
+                        "Connection with robot {} closed: {}",
+                        msg.peer.node_id, reason
+                    );
+                }
+                ctx.address()
+                    .try_send(ElectionForDeadRobot {
+                        closed_server_id: msg.peer.node_id,
+                        robot_to_robot_connection: msg.connection,
+                    })
+                    .map_err(|error| error.to_string())
+            }
+        };
+        if let Err(error) = result {
+            clocked_println!("Error receiving message from another robot: {}", error);
+        }
     }
 }
@@ -1,10 +1,6 @@
-pub mod ice_cream_shop_connection;
 pub mod ice_cream_shop_connector;
 pub mod manage_robot_connection;
-pub mod robot_constants;
 pub mod robot_handler;
-pub mod robot_to_robot_connection;
 pub mod robot_to_robot_connector;
-pub mod robot_to_screen_connection;
 pub mod robot_to_screen_connector;
 pub mod user_input;
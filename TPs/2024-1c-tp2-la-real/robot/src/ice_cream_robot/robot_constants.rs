@@ -1,4 +0,0 @@
-pub const INITIAL_CURRENT_PORT: usize = 10000;
-pub const MAX_PORT: usize = 10009;
-pub const CONNECTION_HOST: &str = "127.0.0.1";
-pub const ICE_CREAM_SHOP_PORT: &str = "9999";
@@ -3,6 +3,11 @@ use std::error::Error;
 use actix::prelude::*;
 use actix::Addr;
 
+use common::clock::NodeClock;
+use common::communication::auth::SecretKey;
+use common::communication::handshake::{Handshake, NodeKind};
+use common::config::Config;
+use common::discovery::Discovery;
 use tokio::join;
 
 use super::robot_to_robot_connector::RobotToRobotConnector;
@@ -19,6 +24,7 @@ use super::{
 ///
 /// * `robot_to_robot_port` - Puerto para la conexión entre robots.
 /// * `screens_port` - Puerto para la conexión con las pantallas.
+/// * `config` - Configuración del cluster.
 ///
 /// # Retorna
 ///
@@ -26,10 +32,19 @@ use super::{
 pub fn start_serving(
     robot_to_robot_port: usize,
     screens_port: usize,
+    config: Config,
 ) -> Result<(), Box<dyn Error>> {
+    NodeClock::init_global(
+        &NodeClock::node_name(NodeKind::Robot, robot_to_robot_port),
+        config.clock.vector,
+    );
     let user_input = UserInput::start_user_input();
 
-    System::new().block_on(start_serving_async(robot_to_robot_port, screens_port))?;
+    System::new().block_on(start_serving_async(
+        robot_to_robot_port,
+        screens_port,
+        config,
+    ))?;
 
     user_input.join().map_err(|_| "Error in user_input")??;
 
@@ -42,6 +57,7 @@ pub fn start_serving(
 ///
 /// * `robot_to_robot_port` - Puerto para la conexión entre robots.
 /// * `screens_port` - Puerto para la conexión con las pantallas.
+/// * `config` - Configuración del cluster.
 ///
 /// # Retorna
 ///
@@ -49,15 +65,27 @@ pub fn start_serving(
 async fn start_serving_async(
     robot_to_robot_port: usize,
     screens_port: usize,
+    config: Config,
 ) -> Result<(), Box<dyn Error>> {
-    let manage_robot_conection = start_actors(robot_to_robot_port, screens_port).await?;
+    let handshake = Handshake::new(NodeKind::Robot, robot_to_robot_port)
+        .with_codec(config.codec)
+        .with_secret(SecretKey::from_config(&config.auth));
 
-    let robot_to_screen_connection =
-        RobotToScreenConnector::setup_connection(manage_robot_conection.clone(), screens_port);
+    let manage_robot_conection =
+        start_actors(robot_to_robot_port, screens_port, handshake, &config).await?;
+
+    let robot_to_screen_connection = RobotToScreenConnector::setup_connection(
+        manage_robot_conection.clone(),
+        screens_port,
+        handshake,
+        config.clone(),
+    );
 
     let robot_to_robot_connection = RobotToRobotConnector::setup_connection(
         manage_robot_conection.clone(),
         robot_to_robot_port,
+        handshake,
+        config,
     );
 
     let (join_result_screen, join_result_robot) =
@@ -75,6 +103,8 @@ async fn start_serving_async(
 ///
 /// * `robot_to_robot_id` - ID del robot para la conexión entre robots.
 /// * `robot_to_screen_id` - ID del robot para la conexión con las pantallas.
+/// * `handshake` - Handshake con el que el robot se presenta ante la heladería.
+/// * `config` - Configuración del cluster.
 ///
 /// # Retorna
 ///
@@ -82,12 +112,19 @@ async fn start_serving_async(
 async fn start_actors(
     robot_to_robot_id: usize,
     robot_to_screen_id: usize,
+    handshake: Handshake,
+    config: &Config,
 ) -> Result<Addr<ManageRobotConnection>, Box<dyn Error>> {
-    let manage_robot_conection =
-        ManageRobotConnection::new(robot_to_robot_id, robot_to_screen_id).start();
+    let manage_robot_conection = ManageRobotConnection::new(
+        robot_to_robot_id,
+        robot_to_screen_id,
+        Discovery::new(config),
+    )
+    .start();
 
     let robot_to_ice_cream_shop_connection =
-        IceCreamShopConnector::setup_connection(manage_robot_conection.clone()).await?;
+        IceCreamShopConnector::setup_connection(manage_robot_conection.clone(), handshake, config)
+            .await?;
 
     manage_robot_conection
         .send(AddIceCreamShopConnectionMessage {
@@ -1,22 +1,28 @@
-use std::sync::Arc;
+use std::{net::SocketAddr, time::Duration};
 
-use actix::{Actor, Addr, AsyncContext};
+use actix::Addr;
+use common::clocked_println;
+use common::communication::{
+    codec::FrameCodec,
+    handshake::{accept_handshake, initiate_handshake, Handshake, NodeKind},
+};
+use common::config::{Config, HeartbeatConfig};
+use common::discovery::Discovery;
 use tokio::{
-    io::{split, AsyncBufReadExt, BufReader},
+    io::{split, BufReader, ReadHalf, WriteHalf},
     net::{TcpListener, TcpStream},
-    sync::Mutex,
     task::JoinHandle,
+    time::timeout,
 };
-use tokio_stream::wrappers::LinesStream;
-
-use super::{
-    manage_robot_connection::{
-        AddRobotToRobotConnectionMessage, StartLeaderElection, ManageRobotConnection,
-    },
-    robot_constants::{CONNECTION_HOST, INITIAL_CURRENT_PORT, MAX_PORT},
-    robot_to_robot_connection::RobotToRobotConnection,
+
+use super::manage_robot_connection::{
+    AddRobotToRobotConnectionMessage, ManageRobotConnection, RobotToRobotConnection,
+    StartLeaderElection,
 };
 
+/// Tiempo máximo para conectarse a otro robot y otro tanto para completar el handshake.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// `RobotToRobotConnector` es una estructura responsable de gestionar las conexiones entre robots.
 pub struct RobotToRobotConnector {}
 
@@ -27,6 +33,8 @@ impl RobotToRobotConnector {
     ///
     /// * `manage_robot_connection` - Dirección del actor `ManageRobotConnection`.
     /// * `robot_to_robot_port` - Puerto para la conexión entre robots.
+    /// * `handshake` - Handshake con el que el robot se presenta ante los demás robots.
+    /// * `config` - Configuración del cluster, con el rango de puertos de los robots.
     ///
     /// # Retorna
     ///
@@ -34,9 +42,17 @@ impl RobotToRobotConnector {
     pub fn setup_connection(
         manage_robot_connection: Addr<ManageRobotConnection>,
         robot_to_robot_port: usize,
+        handshake: Handshake,
+        config: Config,
     ) -> JoinHandle<Result<(), String>> {
         actix::spawn(async move {
-            Self::manage_connection(manage_robot_connection, robot_to_robot_port).await
+            Self::manage_connection(
+                manage_robot_connection,
+                robot_to_robot_port,
+                handshake,
+                config,
+            )
+            .await
         })
     }
 
@@ -46,6 +62,8 @@ impl RobotToRobotConnector {
     ///
     /// * `manage_robot_connection` - Dirección del actor `ManageRobotConnection`.
     /// * `robot_to_robot_port` - Puerto para la conexión entre robots.
+    /// * `handshake` - Handshake con el que el robot se presenta ante los demás robots.
+    /// * `config` - Configuración del cluster.
     ///
     /// # Retorna
     ///
@@ -53,57 +71,110 @@ impl RobotToRobotConnector {
     async fn manage_connection(
         manage_robot_connection: Addr<ManageRobotConnection>,
         robot_to_robot_port: usize,
+        handshake: Handshake,
+        config: Config,
     ) -> Result<(), String> {
-        Self::try_connection(manage_robot_connection.clone()).await?;
+        Self::try_connection(
+            manage_robot_connection.clone(),
+            handshake,
+            &config.address(robot_to_robot_port),
+            &config,
+        )
+        .await?;
 
         manage_robot_connection
             .try_send(StartLeaderElection {})
             .map_err(|error| error.to_string())?;
 
-        let listener = TcpListener::bind(format!("{}:{}", CONNECTION_HOST, robot_to_robot_port))
+        let listener = TcpListener::bind(config.address(robot_to_robot_port))
             .await
             .map_err(|error| error.to_string())?;
 
         loop {
-            if let Ok((stream, _)) = listener.accept().await {
-                Self::manage_connected_robot(stream, &manage_robot_connection)?;
+            if let Ok((stream, peer_addr)) = listener.accept().await {
+                let manage_robot_connection = manage_robot_connection.clone();
+                actix::spawn(async move {
+                    if let Err(error) = Self::manage_connected_robot(
+                        stream,
+                        peer_addr,
+                        &manage_robot_connection,
+                        handshake,
+                        config.heartbeat,
+                    )
+                    .await
+                    {
+                        clocked_println!("Rejected connection from robot: {}", error);
+                    }
+                });
             };
         }
     }
 
-    /// Intenta establecer una conexión entre robots.
+    /// Intenta conectarse con cada robot encontrado por el descubrimiento del cluster. Un robot
+    /// que no acepta la conexión o no completa el handshake en `CONNECT_TIMEOUT` se saltea.
     ///
     /// # Argumentos
     ///
     /// * `manage_robot_connection` - Dirección del actor `ManageRobotConnection`.
+    /// * `handshake` - Handshake con el que el robot se presenta ante los demás robots.
+    /// * `own_address` - Dirección en la que escucha este robot, que no se prueba.
+    /// * `config` - Configuración del cluster, con el modo de descubrimiento.
     ///
     /// # Retorna
     ///
     /// Un `Result<(), String>` indicando el resultado de la operación.
     async fn try_connection(
         manage_robot_connection: Addr<ManageRobotConnection>,
+        handshake: Handshake,
+        own_address: &str,
+        config: &Config,
     ) -> Result<(), String> {
-        let mut current_port = INITIAL_CURRENT_PORT;
-        while current_port <= MAX_PORT {
-            let address = format!("{}:{}", CONNECTION_HOST, current_port);
-
-            if let Ok(stream) = TcpStream::connect(address.clone()).await {
-                let (reader, writer) = split(stream);
-                let robot_to_robot_connection = RobotToRobotConnection::create(|ctx| {
-                    ctx.add_stream(LinesStream::new(BufReader::new(reader).lines()));
-                    RobotToRobotConnection::new(
-                        manage_robot_connection.clone(),
-                        Arc::new(Mutex::new(writer)),
-                    )
-                });
-                manage_robot_connection
-                    .try_send(AddRobotToRobotConnectionMessage {
-                        robot_to_robot_id: Some(current_port),
-                        robot_to_robot_connection_address: robot_to_robot_connection,
-                    })
-                    .map_err(|error| error.to_string())?;
+        let addresses = match Discovery::new(config).robot_addresses().await {
+            Ok(addresses) => addresses,
+            Err(error) => {
+                clocked_println!("Couldn't discover other robots: {}", error);
+                Vec::new()
+            }
+        };
+
+        for address in addresses
+            .into_iter()
+            .filter(|address| address != own_address)
+        {
+            let stream = match timeout(CONNECT_TIMEOUT, TcpStream::connect(&address)).await {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(_)) => continue,
+                Err(_) => {
+                    clocked_println!("Timed out connecting to robot at {}", address);
+                    continue;
+                }
+            };
+            let peer_addr = stream.peer_addr().map_err(|error| error.to_string())?;
+            let (reader, mut writer) = split(stream);
+            let mut reader = BufReader::new(reader);
+            let handshake_result = timeout(
+                CONNECT_TIMEOUT,
+                initiate_handshake(&mut reader, &mut writer, &handshake, &[NodeKind::Robot]),
+            )
+            .await;
+            match handshake_result {
+                Ok(Ok((robot, codec))) => Self::add_robot_connection(
+                    reader,
+                    writer,
+                    codec,
+                    &manage_robot_connection,
+                    handshake,
+                    robot,
+                    peer_addr,
+                    config.heartbeat,
+                )?,
+                Ok(Err(error)) => {
+                    clocked_println!("Couldn't connect to robot at {}: {}", address, error)
+                }
+                Err(_) => {
+                    clocked_println!("Timed out in the handshake with robot at {}", address)
+                }
             }
-            current_port += 1;
         }
 
         Ok(())
@@ -114,28 +185,83 @@ impl RobotToRobotConnector {
     /// # Argumentos
     ///
     /// * `async_stream` - Stream asincrónico del robot conectado.
+    /// * `peer_addr` - Dirección del socket del robot conectado.
     /// * `manage_robot_connection` - Dirección del actor `ManageRobotConnection`.
+    /// * `handshake` - Handshake con el que el robot se presenta ante los demás robots.
+    /// * `heartbeat` - Configuración de los heartbeats de la conexión.
     ///
     /// # Retorna
     ///
     /// Un `Result<(), String>` indicando el resultado de la operación.
-    fn manage_connected_robot(
+    async fn manage_connected_robot(
         async_stream: TcpStream,
+        peer_addr: SocketAddr,
         manage_robot_connection: &Addr<ManageRobotConnection>,
+        handshake: Handshake,
+        heartbeat: HeartbeatConfig,
     ) -> Result<(), String> {
-        let (reader, writer) = split(async_stream);
+        let (reader, mut writer) = split(async_stream);
+        let mut reader = BufReader::new(reader);
 
-        let robot_to_robot_connection = RobotToRobotConnection::create(|ctx| {
-            ctx.add_stream(LinesStream::new(BufReader::new(reader).lines()));
-            RobotToRobotConnection::new(
-                manage_robot_connection.clone(),
-                Arc::new(Mutex::new(writer)),
-            )
-        });
+        let (robot, codec) =
+            accept_handshake(&mut reader, &mut writer, &handshake, &[NodeKind::Robot])
+                .await
+                .map_err(|error| error.to_string())?;
+
+        Self::add_robot_connection(
+            reader,
+            writer,
+            codec,
+            manage_robot_connection,
+            handshake,
+            robot,
+            peer_addr,
+            heartbeat,
+        )
+    }
+
+    /// Crea el actor `RobotToRobotConnection` para un robot que ya completó el handshake
+    /// y lo registra en el `ManageRobotConnection`.
+    ///
+    /// # Argumentos
+    ///
+    /// * `reader` - Lector del socket, posicionado después del handshake.
+    /// * `writer` - Mitad de escritura del socket.
+    /// * `codec` - Codec negociado en el handshake.
+    /// * `manage_robot_connection` - Dirección del actor `ManageRobotConnection`.
+    /// * `handshake` - Handshake de este robot.
+    /// * `robot` - Handshake del robot conectado.
+    /// * `peer_addr` - Dirección del socket del robot conectado.
+    /// * `heartbeat` - Configuración de los heartbeats de la conexión.
+    ///
+    /// # Retorna
+    ///
+    /// Un `Result<(), String>` indicando el resultado de la operación.
+    #[allow(clippy::too_many_arguments)]
+    fn add_robot_connection(
+        reader: BufReader<ReadHalf<TcpStream>>,
+        writer: WriteHalf<TcpStream>,
+        codec: FrameCodec,
+        manage_robot_connection: &Addr<ManageRobotConnection>,
+        handshake: Handshake,
+        robot: Handshake,
+        peer_addr: SocketAddr,
+        heartbeat: HeartbeatConfig,
+    ) -> Result<(), String> {
+        let robot_to_robot_connection = RobotToRobotConnection::new(
+            writer,
+            codec,
+            handshake.node_id,
+            robot,
+            peer_addr,
+            manage_robot_connection.clone().recipient(),
+        )
+        .with_heartbeat(heartbeat)
+        .start_with_reader(reader);
 
         manage_robot_connection
             .try_send(AddRobotToRobotConnectionMessage {
-                robot_to_robot_id: None,
+                robot_to_robot_id: robot.node_id,
                 robot_to_robot_connection_address: robot_to_robot_connection,
             })
             .map_err(|error| error.to_string())
@@ -1,20 +1,18 @@
-use actix::{Actor, Addr, AsyncContext};
+use actix::Addr;
+use common::clocked_println;
+use common::communication::handshake::{accept_handshake, Handshake, NodeKind};
+use common::config::{Config, HeartbeatConfig};
 use tokio::{
-    io::{split, AsyncBufReadExt, BufReader},
+    io::{split, BufReader},
     net::{TcpListener, TcpStream},
-    sync::Mutex,
     task::JoinHandle,
 };
-use tokio_stream::wrappers::LinesStream;
 
 use crate::ice_cream_robot::manage_robot_connection::AddRobotToScreenConnectionMessage;
 
-use super::{
-    manage_robot_connection::ManageRobotConnection, robot_constants::CONNECTION_HOST,
-    robot_to_screen_connection::RobotToScreenConnection,
-};
+use super::manage_robot_connection::{ManageRobotConnection, RobotToScreenConnection};
 
-use std::{net::SocketAddr, sync::Arc};
+use std::net::SocketAddr;
 
 /// `RobotToScreenConnector` es una estructura responsable de establecer y gestionar conexiones entre robots y pantallas.
 pub struct RobotToScreenConnector {}
@@ -26,7 +24,8 @@ impl RobotToScreenConnector {
     ///
     /// * `manage_robot_conection` - Dirección del actor `ManageRobotConnection`.
     /// * `screens_port` - Puerto donde se escucharán las conexiones de las pantallas.
-    ///
+    /// * `handshake` - Handshake con el que el robot se presenta ante las pantallas.
+    /// * `config` - Configuración del cluster.
     ///
     /// # Retorna
     ///
@@ -34,10 +33,12 @@ impl RobotToScreenConnector {
     pub fn setup_connection(
         manage_robot_conection: Addr<ManageRobotConnection>,
         screens_port: usize,
+        handshake: Handshake,
+        config: Config,
     ) -> JoinHandle<Result<(), String>> {
-        actix::spawn(
-            async move { Self::manage_connection(manage_robot_conection, screens_port).await },
-        )
+        actix::spawn(async move {
+            Self::manage_connection(manage_robot_conection, screens_port, handshake, config).await
+        })
     }
 
     /// Gestiona todas las conexion recibidas de las pantallas
@@ -46,6 +47,8 @@ impl RobotToScreenConnector {
     ///
     /// * `manage_robot_conection` - Dirección del actor `ManageRobotConnection`.
     /// * `screens_port` - Puerto donde se escucharán las conexiones de las pantallas.
+    /// * `handshake` - Handshake con el que el robot se presenta ante las pantallas.
+    /// * `config` - Configuración del cluster.
     ///
     /// # Retorna
     ///
@@ -53,15 +56,30 @@ impl RobotToScreenConnector {
     async fn manage_connection(
         manage_robot_conection: Addr<ManageRobotConnection>,
         screens_port: usize,
+        handshake: Handshake,
+        config: Config,
     ) -> Result<(), String> {
         loop {
-            let listener = TcpListener::bind(format!("{}:{}", CONNECTION_HOST, screens_port))
+            let listener = TcpListener::bind(config.address(screens_port))
                 .await
                 .map_err(|error| error.to_string())?;
 
             match listener.accept().await {
                 Ok((stream, stream_addr)) => {
-                    Self::manage_connected_screen(stream, stream_addr, &manage_robot_conection)?;
+                    let manage_robot_conection = manage_robot_conection.clone();
+                    actix::spawn(async move {
+                        if let Err(error) = Self::manage_connected_screen(
+                            stream,
+                            stream_addr,
+                            &manage_robot_conection,
+                            handshake,
+                            config.heartbeat,
+                        )
+                        .await
+                        {
+                            clocked_println!("Rejected connection from {}: {}", stream_addr, error);
+                        }
+                    });
                 }
                 Err(error) => {
                     return Err(error.to_string());
@@ -77,31 +95,54 @@ impl RobotToScreenConnector {
     /// * `stream` - Stream de la conexión TCP de la pantalla.
     /// * `stream_addr` - Dirección del socket de la pantalla.
     /// * `manage_robot_conection` - Referencia a la dirección del actor `ManageRobotConnection`.
+    /// * `handshake` - Handshake con el que el robot se presenta ante las pantallas.
+    /// * `heartbeat` - Configuración de los heartbeats de la conexión.
     ///
     /// # Retorna
     ///
     /// Un `Result` que contiene `Ok(())` en caso de éxito o un `String` describiendo el error en caso de fallo.
-    fn manage_connected_screen(
+    async fn manage_connected_screen(
         stream: TcpStream,
         stream_addr: SocketAddr,
         manage_robot_conection: &Addr<ManageRobotConnection>,
+        handshake: Handshake,
+        heartbeat: HeartbeatConfig,
     ) -> Result<(), String> {
-        let (read_half, write_half) = split(stream);
+        let (read_half, mut write_half) = split(stream);
+        let mut reader = BufReader::new(read_half);
 
-        RobotToScreenConnection::create(|ctx| {
-            manage_robot_conection.do_send(AddRobotToScreenConnectionMessage {
-                address: stream_addr,
-                robot_to_screen_connection: ctx.address(),
-            });
+        let (screen, codec) = accept_handshake(
+            &mut reader,
+            &mut write_half,
+            &handshake,
+            &[NodeKind::Screen],
+        )
+        .await
+        .map_err(|error| error.to_string())?;
+        clocked_println!(
+            "Screen {} connected from {} using {}",
+            screen.node_id,
+            stream_addr,
+            codec.kind()
+        );
 
-            ctx.add_stream(LinesStream::new(BufReader::new(read_half).lines()));
-            RobotToScreenConnection::new(
-                Arc::new(Mutex::new(write_half)),
-                manage_robot_conection.clone(),
-                stream_addr,
-            )
-        });
+        let screen_id = screen.node_id;
+        let robot_to_screen_connection = RobotToScreenConnection::new(
+            write_half,
+            codec,
+            handshake.node_id,
+            screen,
+            stream_addr,
+            manage_robot_conection.clone().recipient(),
+        )
+        .with_heartbeat(heartbeat)
+        .start_with_reader(reader);
 
-        Ok(())
+        manage_robot_conection
+            .try_send(AddRobotToScreenConnectionMessage {
+                screen_id,
+                robot_to_screen_connection,
+            })
+            .map_err(|error| error.to_string())
     }
 }
@@ -1,5 +1,7 @@
 use std::{process::exit, thread::JoinHandle};
 
+use common::clocked_println;
+
 /// `UserInput` es una estructura que maneja la entrada del usuario desde la terminal.
 pub struct UserInput {}
 
@@ -11,14 +13,14 @@ impl UserInput {
     /// Devuelve un `JoinHandle` que puede ser usado para esperar la finalización del hilo.
     pub fn start_user_input() -> JoinHandle<Result<(), String>> {
         std::thread::spawn(move || -> Result<(), String> {
-            println!("UserInput started");
+            clocked_println!("UserInput started");
             let mut reader = std::io::stdin().lines();
 
             while let Some(Ok(line)) = reader.next() {
                 if line == "q" {
                     exit(0);
                 } else {
-                    println!("Unknown command. use q");
+                    clocked_println!("Unknown command. use q");
                 }
             }
             Ok(())
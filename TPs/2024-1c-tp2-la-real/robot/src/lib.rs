@@ -1,5 +1,6 @@
 use std::error::Error;
 
+use common::config::Config;
 use ice_cream_robot::robot_handler::start_serving;
 
 pub mod ice_cream_robot;
@@ -8,6 +9,8 @@ pub fn start() -> Result<(), Box<dyn Error>> {
     let mut initial_arguments: Vec<String> = std::env::args().collect();
     initial_arguments.remove(0); // no necesito el nombre del programa
 
+    let config = Config::from_args(&mut initial_arguments)?;
+
     let arguments_length = initial_arguments.len();
 
     if arguments_length < 2 {
@@ -19,7 +22,7 @@ pub fn start() -> Result<(), Box<dyn Error>> {
         Ok(robot_to_robot_port) => robot_to_robot_port,
         Err(_) => {
             println!("Couldn't convert, using default port for robot");
-            10000
+            config.robots.first_port
         }
     };
 
@@ -27,10 +30,9 @@ pub fn start() -> Result<(), Box<dyn Error>> {
         Ok(screens_port) => screens_port,
         Err(_) => {
             println!("Couldn't convert, using default port for screen");
-            9000
+            config.screens.first_port
         }
     };
 
-    let result = start_serving(robot_to_robot_port, screens_port);
-    result
+    start_serving(robot_to_robot_port, screens_port, config)
 }
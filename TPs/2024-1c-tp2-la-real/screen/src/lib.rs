@@ -1,13 +1,18 @@
 pub mod screen_implementation;
 use std::error::Error;
 
+use common::config::Config;
+
 const DEFAULT_ORDERS_PATH: &str = "helados.csv";
 
 /// Funcion que se ocupa de parsear la entrada de terminal para tomar en consideracion la entrada. Devuelve un result y permite iniciar la conexion de pantalla
-fn parse_args_for_screen() -> Result<String, String> {
+/// junto con la configuracion del cluster
+fn parse_args_for_screen() -> Result<(String, Config), String> {
     let mut args: Vec<String> = std::env::args().collect();
     args.remove(0);
 
+    let config = Config::from_args(&mut args)?;
+
     let mut orders_path = DEFAULT_ORDERS_PATH.to_string();
 
     match args.len() {
@@ -22,11 +27,11 @@ fn parse_args_for_screen() -> Result<String, String> {
             return Err("[Screen] Too many arguments".to_string());
         }
     }
-    Ok(orders_path)
+    Ok((orders_path, config))
 }
 
 pub fn run() -> Result<(), Box<dyn Error>> {
-    let orders_path = parse_args_for_screen()?;
-    screen_implementation::screen_connection_handler::start(orders_path)?;
+    let (orders_path, config) = parse_args_for_screen()?;
+    screen_implementation::screen_connection_handler::start(orders_path, config)?;
     Ok(())
 }
@@ -4,20 +4,30 @@ use crate::screen_implementation::screen_to_robot_connector::ScreenConnector;
 use crate::screen_implementation::user_input::UserInput;
 use actix::{Actor, Addr};
 use actix_rt::System;
+use common::clock::NodeClock;
+use common::communication::auth::SecretKey;
+use common::communication::handshake::{Handshake, NodeKind};
+use common::config::Config;
 
 use super::screen_constants::RELATIVE_PATH;
+use super::screen_payment_system::ScreenPaymentSystem;
 
 /// Función principal de arranque. Se encarga de iniciar en orden las cosas necesarias para poder procesar las órdenes.
 ///
 /// # Argumentos
 ///
 /// * `orders_path` - Ruta relativa al archivo CSV que contiene las órdenes a procesar.
+/// * `config` - Configuración del cluster.
 ///
 /// # Errores
 ///
 /// Retorna un `Result<(), String>` indicando éxito o error durante el proceso de inicio.
 ///
-pub fn start(orders_path: String) -> Result<(), String> {
+pub fn start(orders_path: String, config: Config) -> Result<(), String> {
+    NodeClock::init_global(
+        &NodeClock::node_name(NodeKind::Screen, std::process::id() as usize),
+        config.clock.vector,
+    );
     let relative_orders_path = RELATIVE_PATH.to_owned() + &orders_path;
 
     if !std::path::Path::new(&relative_orders_path).exists() {
@@ -33,7 +43,7 @@ pub fn start(orders_path: String) -> Result<(), String> {
     let user_input_handler = UserInput::start_user_input();
     let system = System::new();
 
-    system.block_on(start_async_orders(local_orders))?;
+    system.block_on(start_async_orders(local_orders, config))?;
 
     system.run().map_err(|_| "Error while running")?;
     user_input_handler
@@ -48,15 +58,21 @@ pub fn start(orders_path: String) -> Result<(), String> {
 /// # Argumentos
 ///
 /// * `local_orders` - Vectores de cadenas que representan las órdenes a procesar.
+/// * `config` - Configuración del cluster.
 ///
 /// # Errores
 ///
 /// Retorna un `Result<(), String>` indicando éxito o error durante el proceso de inicio asíncrono.
 ///
-async fn start_async_orders(local_orders: Vec<Vec<String>>) -> Result<(), String> {
-    let connection_handler = start_connection_handler(local_orders)?;
+async fn start_async_orders(local_orders: Vec<Vec<String>>, config: Config) -> Result<(), String> {
+    let payment_system =
+        ScreenPaymentSystem::with_acceptance_rate(config.screens.payment_acceptance_rate);
+    let connection_handler = start_connection_handler(local_orders, payment_system)?;
 
-    ScreenConnector::handle_connection_with_robot(connection_handler);
+    let handshake = Handshake::new(NodeKind::Screen, std::process::id() as usize)
+        .with_codec(config.codec)
+        .with_secret(SecretKey::from_config(&config.auth));
+    ScreenConnector::handle_connection_with_robot(connection_handler, handshake, config);
 
     Ok(())
 }
@@ -66,13 +82,18 @@ async fn start_async_orders(local_orders: Vec<Vec<String>>) -> Result<(), String
 /// # Argumentos
 ///
 /// * `orders` - Vectores de cadenas que representan las órdenes a procesar.
+/// * `payment_system` - Sistema con el que se capturan los pagos.
 ///
 /// # Errores
 ///
 /// Retorna un `Result<Addr<ScreenOrderManager>, String>` indicando éxito o error durante el proceso de inicio del manejador de conexiones.
 ///
-fn start_connection_handler(orders: Vec<Vec<String>>) -> Result<Addr<ScreenOrderManager>, String> {
-    let connection_handler = ScreenOrderManager::new_manager_with_orders(orders).start();
+fn start_connection_handler(
+    orders: Vec<Vec<String>>,
+    payment_system: ScreenPaymentSystem,
+) -> Result<Addr<ScreenOrderManager>, String> {
+    let connection_handler =
+        ScreenOrderManager::new_manager_with_orders(orders, payment_system).start();
 
     Ok(connection_handler)
 }
@@ -84,7 +105,7 @@ mod tests {
     #[test]
     fn test_start_with_invalid_orders_path() {
         let orders_path = "invalid_path.csv".to_string();
-        let result = start(orders_path);
+        let result = start(orders_path, Config::default());
         assert!(result.is_err());
     }
 }
@@ -1,6 +1,4 @@
 pub const RELATIVE_PATH: &str = "./data/orders/";
-pub const PAYMENT_ACCEPTANCE_RATE: f32 = 0.95;
-pub const INITIAL_PORT: usize = 9000;
-pub const MAX_PORT: usize = 9009;
-pub const LEADER_ELECTION_TIME: u64 = 20000;
-pub const CONNECTION_HOST: &str = "127.0.0.1";
\ No newline at end of file
+pub const ORDER_TIMEOUT: u64 = 10000;
+pub const ORDER_RETRY_DELAY: u64 = 1000;
+pub const MAX_ORDER_RETRIES: usize = 2;
\ No newline at end of file
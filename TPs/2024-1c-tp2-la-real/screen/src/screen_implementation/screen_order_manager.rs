@@ -1,11 +1,21 @@
+use crate::screen_implementation::screen_constants::{
+    MAX_ORDER_RETRIES, ORDER_RETRY_DELAY, ORDER_TIMEOUT,
+};
 use crate::screen_implementation::screen_payment_system::ScreenPaymentSystem;
-use crate::screen_implementation::screen_to_robot_connection::*;
 use crate::screen_implementation::screen_working_order::ScreenWorkingOrder;
 use actix::prelude::*;
-use common::communication::screen_robot_communication::Request;
+use common::clocked_println;
+use common::communication::framed_connection::{
+    ConnectionEvent, FramedConnection, SendMessageThroughSocket, SocketEvent,
+};
+use common::communication::order_error::OrderError;
+use common::communication::order_id::OrderId;
+use common::communication::screen_robot_communication::{Request, Response};
 use std::collections::HashMap;
+use std::time::Duration;
 
-use super::screen_to_robot_connection::ScreenRobotConnection;
+/// Conexión con un robot: recibe `Response` y envía `Request`.
+pub type ScreenRobotConnection = FramedConnection<Response, Request>;
 
 /// Actor principal de las pantallas. Se ocupa de gestionar las ordenes desde que son recibidas hasta que se terminan.
 pub struct ScreenOrderManager {
@@ -28,59 +38,71 @@ impl ScreenOrderManager {
     ///
     /// # Parámetros
     ///
-    /// * `screen_connection`: Dirección del `ScreenRobotConnection`.
     /// * `orders`: Vector de órdenes a procesar.
+    /// * `payment_system`: Sistema con el que se capturan los pagos.
     ///
-    pub fn new_manager_with_orders(orders: Vec<Vec<String>>) -> Self {
-        println!("[ScreenOrderManager] Created!");
+    pub fn new_manager_with_orders(
+        orders: Vec<Vec<String>>,
+        payment_system: ScreenPaymentSystem,
+    ) -> Self {
+        clocked_println!("[ScreenOrderManager] Created!");
         ScreenOrderManager {
             sent_orders: HashMap::new(),
             total_order_count: orders.len(),
             orders,
             sent_indexes: Vec::new(),
             screen_connection: None,
-            payment_system: ScreenPaymentSystem::new(),
+            payment_system,
             currently_connected_port: None,
             sent_all_orders: false,
         }
     }
 
-    /// Cierra una orden que pudo ser completada.
+    /// Busca la orden enviada a la que corresponde una respuesta. Se exige que coincida el
+    /// `OrderId`, de modo que una respuesta a un envío anterior con el mismo índice se ignore.
     ///
     /// # Parámetros
     ///
     /// * `index`: Índice de la orden.
-    /// * `recived_order`: Información de la orden recibida para mostrar en caso de error.
-    fn validate_completed_order(&mut self, index: &str) {
-        if let Ok(index) = index.parse::<usize>() {
-            if let Some(working_order) = self.sent_orders.get_mut(&index) {
-                working_order.succesfull_order();
-            }
-        } else {
-            println!(
-                "[ScreenOrderManager] The received order ({}) is not in my records",
-                index
-            );
-        }
+    /// * `order_id`: Identificador de la orden recibido en la respuesta.
+    fn get_sent_order(
+        &mut self,
+        index: usize,
+        order_id: OrderId,
+    ) -> Option<&mut ScreenWorkingOrder> {
+        self.sent_orders
+            .get_mut(&index)
+            .filter(|working_order| working_order.get_order_id() == order_id)
     }
 
-    /// Cierra una orden que no pudo ser completada.
+    /// Envía una orden al robot y programa el `OrderTimedOut` de este envío.
     ///
     /// # Parámetros
     ///
+    /// * `screen_connection`: Conexión con el robot.
     /// * `index`: Índice de la orden.
-    /// * `recived_order`: Información de la orden recibida para mostrar en caso de error.
-    fn validate_rejected_order(&mut self, index: &str) {
-        if let Ok(index) = index.parse::<usize>() {
-            if let Some(working_order) = self.sent_orders.get_mut(&index) {
-                working_order.unsuccesfull_order();
-            }
-        } else {
-            println!(
-                "[ScreenOrderManager] The received order ({}) is not in my records",
-                index
-            );
-        }
+    /// * `working_order`: Orden a enviar.
+    /// * `ctx`: Contexto del `ScreenOrderManager`.
+    fn dispatch_order(
+        screen_connection: &Addr<ScreenRobotConnection>,
+        index: usize,
+        working_order: &mut ScreenWorkingOrder,
+        ctx: &mut Context<Self>,
+    ) {
+        let _ = screen_connection
+            .try_send(SendMessageThroughSocket {
+                message_to_send_through_socket: working_order.get_order(),
+            })
+            .map_err(|err| err.to_string());
+        let attempt = working_order.new_attempt();
+        ctx.notify_later(
+            OrderTimedOut {
+                index,
+                order_id: working_order.get_order_id(),
+                attempt,
+            },
+            Duration::from_millis(ORDER_TIMEOUT),
+        );
     }
 
     fn get_current_order(&mut self) -> usize{
@@ -131,6 +153,10 @@ impl Handler<SendOrder> for ScreenOrderManager {
             self.sent_all_orders = true;
             return;
         }
+        // la orden ya fue despachada: si sigue pendiente se reenvía al reconectarse
+        if self.sent_orders.contains_key(&msg.numero_de_orden) {
+            return;
+        }
         let mut working_order = ScreenWorkingOrder::new(
             msg.numero_de_orden,
             self.orders[msg.numero_de_orden][0].clone(),
@@ -139,26 +165,38 @@ impl Handler<SendOrder> for ScreenOrderManager {
         self.sent_indexes.push(msg.numero_de_orden);
         if working_order.valid() {
             if self.payment_system.attempt_payment_capture() {
-                if let Some(screen_connection) = &self.screen_connection {
-                    let _ = screen_connection
-                    .try_send(SendMessageThroughSocket {
-                        message_to_send_through_socket: working_order
-                        .get_serialized_order_information(),
-                    })
-                    .map_err(|err| err.to_string());
-                println!("[ScreenOrderManager] SendOrder #{}", msg.numero_de_orden);
-                println!(
-                    "Sent order: {}",
-                    working_order.get_serialized_order_information()
-                );
-                self.sent_orders.insert(msg.numero_de_orden, working_order);
+                match &self.screen_connection {
+                    Some(screen_connection) => {
+                        Self::dispatch_order(
+                            screen_connection,
+                            msg.numero_de_orden,
+                            &mut working_order,
+                            ctx,
+                        );
+                        clocked_println!(
+                            "[ScreenOrderManager] SendOrder #{} ({})",
+                            msg.numero_de_orden,
+                            working_order.get_order_id()
+                        );
+                        clocked_println!(
+                            "Sent order: {}",
+                            working_order.get_serialized_order_information()
+                        );
+                    }
+                    // sin conexión la orden queda pendiente y se envía al conectarse
+                    None => clocked_println!(
+                        "[ScreenOrderManager] Order #{} ({}) will be sent once connected",
+                        msg.numero_de_orden,
+                        working_order.get_order_id()
+                    ),
                 }
+                self.sent_orders.insert(msg.numero_de_orden, working_order);
             } else {
-                println!(
+                clocked_println!(
                     "The order: [{}], was rejected due to payment capture failure.",
                     working_order.get_serialized_order_information()
                 );
-                working_order.unsuccesfull_order();
+                working_order.unsuccesfull_order(&OrderError::PaymentDeclined);
                 let _ = ctx
                 .address()
                 .try_send(SendOrder {
@@ -167,7 +205,8 @@ impl Handler<SendOrder> for ScreenOrderManager {
                 .map_err(|error| error.to_string());
             }
         } else {
-            println!("The order attempted was invalid");
+            clocked_println!("The order attempted was invalid");
+            working_order.unsuccesfull_order(&OrderError::InvalidOrder);
             let _ = ctx
                 .address()
                 .try_send(SendOrder {
@@ -178,30 +217,76 @@ impl Handler<SendOrder> for ScreenOrderManager {
     }
 }
 
-/// Mensaje recibido del robot una vez que se ha procesado una orden. La orden se cierra según corresponda.
+/// Mensaje recibido del robot una vez que se ha procesado una orden. La orden se cierra según corresponda:
+/// los errores transitorios (`OrderError::is_retryable`) se reintentan hasta `MAX_ORDER_RETRIES` veces,
+/// quedando pendientes hasta reconectarse si no hay conexión con un robot, y el resto cierra la orden
+/// como fallida.
 ///
 /// # Parámetros
 ///
 /// * `result`: Resultado del procesamiento de la orden.
 /// * `index`: Índice de la orden procesada.
+/// * `order_id`: Identificador global de la orden procesada.
 #[derive(Message)]
 #[rtype(result = "Result<(), String>")]
 pub struct ReturnedOrderFromShop {
-    pub result: Result<(), String>,
+    pub result: Result<(), OrderError>,
     pub index: u32,
+    pub order_id: OrderId,
 }
 
 impl Handler<ReturnedOrderFromShop> for ScreenOrderManager {
     type Result = Result<(), String>;
 
     fn handle(&mut self, msg: ReturnedOrderFromShop, ctx: &mut Self::Context) -> Self::Result {
-        println!("[ScreenOrderManager] ReturnedOrderFromShop: #{}", msg.index);
+        clocked_println!(
+            "[ScreenOrderManager] ReturnedOrderFromShop: #{} ({})",
+            msg.index, msg.order_id
+        );
+        let connected = self.screen_connection.is_some();
+        let working_order = match self.get_sent_order(msg.index as usize, msg.order_id) {
+            Some(working_order) => working_order,
+            None => {
+                clocked_println!(
+                    "[ScreenOrderManager] The received order ({}) is not in my records",
+                    msg.order_id
+                );
+                return Ok(());
+            }
+        };
+        if !working_order.is_pending() {
+            return Ok(());
+        }
         match msg.result {
             Ok(_) => {
-                self.validate_completed_order(&msg.index.to_string());
+                working_order.succesfull_order();
+            }
+            Err(error)
+                if error.is_retryable() && working_order.get_attempts() <= MAX_ORDER_RETRIES =>
+            {
+                if !connected {
+                    // AddScreenRobotConnection reenvía las órdenes pendientes
+                    clocked_println!(
+                        "[ScreenOrderManager] Order #{} ({}) failed: {}. Will retry once reconnected",
+                        msg.index, msg.order_id, error
+                    );
+                    return Ok(());
+                }
+                clocked_println!(
+                    "[ScreenOrderManager] Order #{} ({}) failed: {}. Retrying...",
+                    msg.index, msg.order_id, error
+                );
+                ctx.notify_later(
+                    RetryOrder {
+                        index: msg.index as usize,
+                        order_id: msg.order_id,
+                    },
+                    Duration::from_millis(ORDER_RETRY_DELAY),
+                );
+                return Ok(());
             }
-            Err(_) => {
-                self.validate_rejected_order(&msg.index.to_string());
+            Err(error) => {
+                working_order.unsuccesfull_order(&error);
             }
         }
         if self.get_current_order() < self.total_order_count{
@@ -215,7 +300,89 @@ impl Handler<ReturnedOrderFromShop> for ScreenOrderManager {
     }
 }
 
-/// Mensaje para añadir una conexión `ScreenRobotConnection` al `ScreenOrderManager`.
+/// Mensaje para volver a enviar una orden que falló por un error transitorio.
+///
+/// # Parámetros
+///
+/// * `index`: Índice de la orden.
+/// * `order_id`: Identificador global de la orden, que se mantiene entre reintentos.
+#[derive(Message)]
+#[rtype(result = "()")]
+struct RetryOrder {
+    index: usize,
+    order_id: OrderId,
+}
+
+impl Handler<RetryOrder> for ScreenOrderManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: RetryOrder, ctx: &mut Self::Context) {
+        let working_order = match self.sent_orders.get_mut(&msg.index) {
+            Some(working_order)
+                if working_order.get_order_id() == msg.order_id && working_order.is_pending() =>
+            {
+                working_order
+            }
+            _ => return,
+        };
+        match &self.screen_connection {
+            Some(screen_connection) => {
+                Self::dispatch_order(screen_connection, msg.index, working_order, ctx);
+                clocked_println!(
+                    "[ScreenOrderManager] SendOrder #{} ({}), attempt {}",
+                    msg.index,
+                    msg.order_id,
+                    working_order.get_attempts()
+                );
+            }
+            // sin conexión la orden queda pendiente y se reenvía al reconectarse
+            None => clocked_println!(
+                "[ScreenOrderManager] Order #{} ({}) will be sent once reconnected",
+                msg.index,
+                msg.order_id
+            ),
+        }
+    }
+}
+
+/// Mensaje programado al enviar una orden. Si la orden sigue pendiente y no fue reenviada,
+/// se cierra con `OrderError::Timeout`. Sin conexión con un robot no se cierra, ya que se
+/// reenvía (con un nuevo timeout) al reconectarse.
+///
+/// # Parámetros
+///
+/// * `index`: Índice de la orden.
+/// * `order_id`: Identificador global de la orden.
+/// * `attempt`: Número de envío al que corresponde el timeout.
+#[derive(Message)]
+#[rtype(result = "()")]
+struct OrderTimedOut {
+    index: usize,
+    order_id: OrderId,
+    attempt: usize,
+}
+
+impl Handler<OrderTimedOut> for ScreenOrderManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: OrderTimedOut, ctx: &mut Self::Context) {
+        if self.screen_connection.is_none() {
+            return;
+        }
+        if let Some(working_order) = self.get_sent_order(msg.index, msg.order_id) {
+            if working_order.is_pending() && working_order.get_attempts() == msg.attempt {
+                ctx.notify(ReturnedOrderFromShop {
+                    result: Err(OrderError::Timeout),
+                    index: msg.index as u32,
+                    order_id: msg.order_id,
+                });
+            }
+        }
+    }
+}
+
+/// Mensaje para añadir una conexión `ScreenRobotConnection` al `ScreenOrderManager`. Las
+/// órdenes que quedaron pendientes al perder la conexión anterior se reenvían por esta.
 ///
 /// # Parámetros
 ///
@@ -231,16 +398,25 @@ pub struct AddScreenRobotConnection {
 impl Handler<AddScreenRobotConnection> for ScreenOrderManager {
     type Result = Result<(), String>;
 
-    fn handle(&mut self, msg: AddScreenRobotConnection, _: &mut Context<Self>) -> Self::Result {
+    fn handle(&mut self, msg: AddScreenRobotConnection, ctx: &mut Context<Self>) -> Self::Result {
         self.screen_connection = Some(msg.addr_screen_robot_connection.clone());
         self.currently_connected_port = Some(msg.port);
-        let message = serde_json::to_string(&Request::ScreenToRobotAskLeader {})
-            .map_err(|error| error.to_string())?;
         msg.addr_screen_robot_connection
             .try_send(SendMessageThroughSocket {
-                message_to_send_through_socket: message,
+                message_to_send_through_socket: Request::ScreenToRobotAskLeader {},
             })
-            .map_err(|error| error.to_string())
+            .map_err(|error| error.to_string())?;
+        for (index, working_order) in self.sent_orders.iter_mut() {
+            if working_order.is_pending() {
+                Self::dispatch_order(&msg.addr_screen_robot_connection, *index, working_order, ctx);
+                clocked_println!(
+                    "[ScreenOrderManager] SendOrder #{} ({}) again after reconnecting",
+                    index,
+                    working_order.get_order_id()
+                );
+            }
+        }
+        Ok(())
     }
 }
 
@@ -266,3 +442,63 @@ impl Handler<LeaderMessage> for ScreenOrderManager {
         Err("Current port not set, cannot connect to leader.".to_owned())
     }
 }
+
+/// Handler de los eventos de la conexión con el robot. Las respuestas recibidas se reenvían
+/// como `ReturnedOrderFromShop` o `LeaderMessage` para su procesamiento.
+impl Handler<ConnectionEvent<Response, Request>> for ScreenOrderManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: ConnectionEvent<Response, Request>, ctx: &mut Context<Self>) {
+        let result = match msg.event {
+            SocketEvent::Received(Response::RobotToScreenResult {
+                order_id,
+                index,
+                result,
+            }) => ctx
+                .address()
+                .try_send(ReturnedOrderFromShop {
+                    result,
+                    index: index as u32,
+                    order_id,
+                })
+                .map_err(|error| error.to_string()),
+            SocketEvent::Received(Response::RobotToScreenLeaderPort { leader_port }) => ctx
+                .address()
+                .try_send(LeaderMessage {
+                    leader_server_id: leader_port,
+                })
+                .map_err(|error| error.to_string()),
+            SocketEvent::Malformed(error) => {
+                clocked_println!(
+                    "[ScreenOrderManager] Discarding malformed message from robot: {}",
+                    error
+                );
+                Ok(())
+            }
+            SocketEvent::WriteFailed(error) => {
+                clocked_println!(
+                    "[ScreenOrderManager] Error sending message to robot {}: {}",
+                    msg.peer.node_id, error
+                );
+                Ok(())
+            }
+            SocketEvent::Disconnected(_) => {
+                clocked_println!(
+                    "[ScreenOrderManager] Connection with robot {} closed",
+                    msg.peer.node_id
+                );
+                // las órdenes pendientes se mantienen y se reenvían en `AddScreenRobotConnection`
+                if self.screen_connection.as_ref() == Some(&msg.connection) {
+                    self.screen_connection = None;
+                }
+                Ok(())
+            }
+        };
+        if let Err(error) = result {
+            clocked_println!(
+                "[ScreenOrderManager] Error receiving message from robot: {}",
+                error
+            );
+        }
+    }
+}
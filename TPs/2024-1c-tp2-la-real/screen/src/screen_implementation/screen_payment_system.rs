@@ -1,6 +1,6 @@
 use rand::Rng;
 
-use super::screen_constants::PAYMENT_ACCEPTANCE_RATE;
+use common::config::ScreensConfig;
 
 /// `ScreenPaymentSystem` simula el sistema de captura de pagos con una tasa de aceptación configurable.
 pub struct ScreenPaymentSystem {
@@ -21,9 +21,16 @@ impl ScreenPaymentSystem {
     ///
     /// Una nueva instancia de `ScreenPaymentSystem` con una tasa de aceptación del 95%.
     pub fn new() -> Self {
-        ScreenPaymentSystem {
-            acceptance_rate: PAYMENT_ACCEPTANCE_RATE,
-        }
+        Self::with_acceptance_rate(ScreensConfig::default().payment_acceptance_rate)
+    }
+
+    /// Crea una nueva instancia de `ScreenPaymentSystem` con la tasa de aceptación indicada.
+    ///
+    /// # Argumentos
+    ///
+    /// * `acceptance_rate` - Probabilidad, entre 0 y 1, de que se acepte un pago.
+    pub fn with_acceptance_rate(acceptance_rate: f32) -> Self {
+        ScreenPaymentSystem { acceptance_rate }
     }
 
     /// Establece la tasa de aceptación a 0%, utilizado únicamente para pruebas.
@@ -64,7 +71,7 @@ mod tests {
                 break;
             }
         }
-        assert_eq!(one_accepted, true);
+        assert!(one_accepted);
     }
 
     /// Prueba para verificar que `attempt_payment_capture` rechaza todos los pagos cuando la tasa de aceptación se establece en 0%.
@@ -73,7 +80,7 @@ mod tests {
         let mut payment_system = ScreenPaymentSystem::new();
         payment_system._test_set_deny_all();
         let result = payment_system.attempt_payment_capture();
-        assert_eq!(result, false);
+        assert!(!result);
     }
 
     /// Prueba para verificar que `attempt_payment_capture` rechaza al menos un pago en 10,000 intentos con la tasa de aceptación por defecto.
@@ -88,6 +95,6 @@ mod tests {
                 break;
             }
         }
-        assert_eq!(one_denied, true);
+        assert!(one_denied);
     }
 }
@@ -1,17 +1,24 @@
-use actix::{Actor, Addr, AsyncContext};
-use std::sync::Arc;
+use std::time::Duration;
+
+use actix::Addr;
+use common::clocked_println;
+use common::communication::handshake::{initiate_handshake, Handshake, NodeKind};
+use common::config::{Config, HeartbeatConfig};
+use common::discovery::Discovery;
 use tokio::{
-    io::{split, AsyncBufReadExt, BufReader},
+    io::{split, BufReader},
     net::TcpStream,
-    sync::Mutex,
     task::JoinHandle,
+    time::timeout,
 };
-use tokio_stream::wrappers::LinesStream;
 
-use super::{
-    screen_constants::{CONNECTION_HOST, INITIAL_PORT, LEADER_ELECTION_TIME, MAX_PORT}, screen_order_manager::{AddScreenRobotConnection, ScreenOrderManager, StartSendingOrders}, screen_to_robot_connection::*
+use super::screen_order_manager::{
+    AddScreenRobotConnection, ScreenOrderManager, ScreenRobotConnection, StartSendingOrders,
 };
 
+/// Tiempo máximo para conectarse a un robot y otro tanto para completar el handshake.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// `ScreenConnector` se encarga de crear la conexión a los robots y crear el actor
 /// que maneja la conexión y lanzarlo.
 pub struct ScreenConnector {}
@@ -23,6 +30,8 @@ impl ScreenConnector {
     /// # Argumentos
     ///
     /// * `connection_handler_addr`: La dirección (`Addr`) del manejador de órdenes (`ScreenOrderManager`).
+    /// * `handshake`: Handshake con el que la pantalla se presenta ante los robots.
+    /// * `config`: Configuración del cluster, con el modo de descubrimiento de los robots.
     ///
     /// # Retornos
     ///
@@ -32,13 +41,32 @@ impl ScreenConnector {
     /// Puede devolver un error si no se puede establecer la conexión TCP.
     pub fn handle_connection_with_robot(
         connection_handler_addr: Addr<ScreenOrderManager>,
+        handshake: Handshake,
+        config: Config,
     ) -> JoinHandle<Result<(), String>> {
+        let discovery = Discovery::new(&config);
         actix::spawn(async move {
             loop {
-                for curr_port in INITIAL_PORT..MAX_PORT + 1 {
-                    Self::connect_to_robot(curr_port, connection_handler_addr.clone()).await?
+                match discovery.screen_addresses().await {
+                    Ok(addresses) => {
+                        for addr in addresses {
+                            Self::connect_to_robot(
+                                &addr,
+                                connection_handler_addr.clone(),
+                                handshake,
+                                config.heartbeat,
+                            )
+                            .await?
+                        }
+                    }
+                    Err(error) => {
+                        clocked_println!("[ScreenConnector] Couldn't discover robots: {}", error)
+                    }
                 }
-                tokio::time::sleep(std::time::Duration::from_millis(LEADER_ELECTION_TIME)).await;
+                tokio::time::sleep(std::time::Duration::from_millis(
+                    config.screens.leader_election_time,
+                ))
+                .await;
             }
         })
     }
@@ -48,44 +76,77 @@ impl ScreenConnector {
     ///
     /// # Argumentos
     ///
-    /// * `port`: El puerto con el cual intentar la conexión TCP.
+    /// * `addr`: La dirección `host:port` con la cual intentar la conexión TCP.
     /// * `connection_handler_addr`: La dirección (`Addr`) del manejador de órdenes (`ScreenOrderManager`).
+    /// * `handshake`: Handshake con el que la pantalla se presenta ante los robots.
+    /// * `heartbeat`: Configuración de los heartbeats de la conexión.
     ///
     /// # Retornos
     ///
     /// Devuelve un `Result` con `()` si la conexión se establece correctamente, o un `String` en caso de falla.
     ///
-    /// Puede devolver un error si no se puede establecer la conexión TCP.
+    /// Si la conexión o el handshake no terminan en `CONNECT_TIMEOUT`, se saltea el robot.
     async fn connect_to_robot(
-        port: usize,
+        addr: &str,
         connection_handler_addr: Addr<ScreenOrderManager>,
+        handshake: Handshake,
+        heartbeat: HeartbeatConfig,
     ) -> Result<(), String> {
-        let addr = format!("{}:{}", CONNECTION_HOST, port);
-        if let Ok(stream) = TcpStream::connect(addr.clone()).await {
-            let (reader, writer) = split(stream);
+        let stream = match timeout(CONNECT_TIMEOUT, TcpStream::connect(addr)).await {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(_)) => return Ok(()),
+            Err(_) => {
+                clocked_println!("[ScreenConnector] Timed out connecting to {}", addr);
+                return Ok(());
+            }
+        };
+        let peer_addr = stream.peer_addr().map_err(|error| error.to_string())?;
+        let (reader, mut writer) = split(stream);
+        let mut reader = BufReader::new(reader);
 
-            let screen_to_robot_connection = ScreenRobotConnection::create(|ctx| {
-                ctx.add_stream(LinesStream::new(BufReader::new(reader).lines()));
+        let handshake_result = timeout(
+            CONNECT_TIMEOUT,
+            initiate_handshake(&mut reader, &mut writer, &handshake, &[NodeKind::Robot]),
+        )
+        .await;
+        let (robot, codec) = match handshake_result {
+            Ok(Ok(connection)) => connection,
+            Ok(Err(error)) => {
+                clocked_println!("[ScreenConnector] Couldn't connect to {}: {}", addr, error);
+                return Ok(());
+            }
+            Err(_) => {
+                clocked_println!("[ScreenConnector] Timed out in the handshake with {}", addr);
+                return Ok(());
+            }
+        };
+        clocked_println!(
+            "[ScreenConnector] Connected to robot {} using {}",
+            robot.node_id,
+            codec.kind()
+        );
 
-                ScreenRobotConnection::new(
-                    Arc::new(Mutex::new(writer)),
-                    connection_handler_addr.clone(),
-                )
-            });
+        let screen_to_robot_connection = ScreenRobotConnection::new(
+            writer,
+            codec,
+            handshake.node_id,
+            robot,
+            peer_addr,
+            connection_handler_addr.clone().recipient(),
+        )
+        .with_heartbeat(heartbeat)
+        .start_with_reader(reader);
 
-            connection_handler_addr
-                .try_send(AddScreenRobotConnection {
-                    addr_screen_robot_connection: screen_to_robot_connection.clone(),
-                    port,
-                })
-                .map_err(|error| error.to_string())?;
+        connection_handler_addr
+            .try_send(AddScreenRobotConnection {
+                addr_screen_robot_connection: screen_to_robot_connection.clone(),
+                port: peer_addr.port() as usize,
+            })
+            .map_err(|error| error.to_string())?;
 
-            connection_handler_addr
-                .try_send(StartSendingOrders {})
-                .map_err(|error| error.to_string())?;
-            Ok(())
-        } else {
-            Ok(())
-        }
+        connection_handler_addr
+            .try_send(StartSendingOrders {})
+            .map_err(|error| error.to_string())?;
+        Ok(())
     }
 }
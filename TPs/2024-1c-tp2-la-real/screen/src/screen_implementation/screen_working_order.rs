@@ -1,11 +1,16 @@
+use common::clocked_println;
+use common::communication::order_error::OrderError;
+use common::communication::order_id::OrderId;
 use common::communication::screen_robot_communication::Request;
 
 /// Modela una orden individual, con su índice, estado y detalles.
 /// Permite conocer su estado, serializa la información para la comunicación
 /// y maneja el resultado en función del estado recibido de los robots.
 pub struct ScreenWorkingOrder {
+    order_id: OrderId,
     order: common::communication::screen_robot_communication::Request,
     state: OrderState,
+    attempts: usize,
 }
 
 /// Enumerates the possible states of a `ScreenWorkingOrder`.
@@ -17,7 +22,8 @@ pub enum OrderState {
 }
 
 impl ScreenWorkingOrder {
-    /// Crea una nueva `ScreenWorkingOrder` con el índice, tamaño y sabores especificados.
+    /// Crea una nueva `ScreenWorkingOrder` con el índice, tamaño y sabores especificados,
+    /// generando el `OrderId` con el que se la identifica en todo el sistema.
     ///
     /// # Argumentos
     ///
@@ -35,16 +41,54 @@ impl ScreenWorkingOrder {
             "CUARTO" => 250,
             _ => 0,
         };
+        let order_id = OrderId::new();
         ScreenWorkingOrder {
+            order_id,
             order: Request::ScreenToRobotOrder {
+                order_id,
                 index: index.to_owned(),
                 flavors: flavours.to_owned(),
                 size: grams,
             },
             state: OrderState::Pending,
+            attempts: 0,
         }
     }
 
+    /// Devuelve el identificador global de la orden.
+    pub fn get_order_id(&self) -> OrderId {
+        self.order_id
+    }
+
+    /// Indica si la orden sigue esperando su resultado.
+    pub fn is_pending(&self) -> bool {
+        matches!(self.state, OrderState::Pending)
+    }
+
+    /// Registra un nuevo envío de la orden al robot.
+    ///
+    /// # Retornos
+    ///
+    /// Devuelve la cantidad de envíos realizados, incluyendo este.
+    pub fn new_attempt(&mut self) -> usize {
+        self.attempts += 1;
+        self.attempts
+    }
+
+    /// Devuelve la cantidad de veces que la orden fue enviada al robot.
+    pub fn get_attempts(&self) -> usize {
+        self.attempts
+    }
+
+    /// Devuelve el pedido tipado que se envía al robot.
+    ///
+    /// # Retornos
+    ///
+    /// Devuelve una copia del `Request` de la orden.
+    pub fn get_order(&self) -> Request {
+        self.order.clone()
+    }
+
     /// Serializa la información de la orden en un formato que el robot puede entender.
     ///
     /// # Retornos
@@ -63,28 +107,36 @@ impl ScreenWorkingOrder {
         self.state = OrderState::Completed;
         match &self.order {
             Request::ScreenToRobotOrder {
+                order_id,
                 index,
                 flavors: _,
                 size: _,
             } => {
-                println!("Order #{} was succesfully finished!", index);
+                clocked_println!("Order #{} ({}) was succesfully finished!", index, order_id);
             }
             Request::ScreenToRobotAskLeader {} => {}
         }
     }
 
     /// Marca una orden como fallida.
-    pub fn unsuccesfull_order(&mut self) {
+    ///
+    /// # Argumentos
+    ///
+    /// * `error` - Motivo por el que la orden no se pudo completar.
+    pub fn unsuccesfull_order(&mut self, error: &OrderError) {
         self.state = OrderState::Failed;
         match &self.order {
             Request::ScreenToRobotOrder {
+                order_id,
                 index,
                 flavors: _,
                 size: _,
             } => {
-                println!(
-                    "Order #{} couldn't be finished, it won't be charged.",
-                    index
+                clocked_println!(
+                    "Order #{} ({}) couldn't be finished: {}. It won't be charged.",
+                    index,
+                    order_id,
+                    error
                 );
             }
             Request::ScreenToRobotAskLeader {} => {}
@@ -94,6 +146,7 @@ impl ScreenWorkingOrder {
     pub fn valid(&self) -> bool {
         match &self.order {
             Request::ScreenToRobotOrder {
+                order_id: _,
                 index: _,
                 flavors: _,
                 size,
@@ -114,17 +167,15 @@ mod tests {
             String::from("KILO"),
             vec![String::from("Chocolate"), String::from("Vanilla")],
         );
-        let grams;
-        match working_order.order {
+        let grams = match working_order.order {
             Request::ScreenToRobotOrder {
+                order_id: _,
                 index: _,
                 flavors: _,
                 size,
-            } => {
-                grams = size;
-            }
-            Request::ScreenToRobotAskLeader {} => grams = 0,
-        }
+            } => size,
+            Request::ScreenToRobotAskLeader {} => 0,
+        };
         assert_eq!(grams, 1000);
     }
 
@@ -135,17 +186,15 @@ mod tests {
             String::from("MEDIO"),
             vec![String::from("Frutilla"), String::from("Menta")],
         );
-        let grams;
-        match working_order.order {
+        let grams = match working_order.order {
             Request::ScreenToRobotOrder {
+                order_id: _,
                 index: _,
                 flavors: _,
                 size,
-            } => {
-                grams = size;
-            }
-            _ => grams = 25000, //hara que el test falle
-        }
+            } => size,
+            _ => 25000, //hara que el test falle
+        };
         assert_eq!(grams, 500);
     }
 
@@ -156,17 +205,15 @@ mod tests {
             String::from("CUARTO"),
             vec![String::from("Dulce de leche"), String::from("Menta")],
         );
-        let grams;
-        match working_order.order {
+        let grams = match working_order.order {
             Request::ScreenToRobotOrder {
+                order_id: _,
                 index: _,
                 flavors: _,
                 size,
-            } => {
-                grams = size;
-            }
-            _ => grams = 25000, //hara que el test falle
-        }
+            } => size,
+            _ => 25000, //hara que el test falle
+        };
         assert_eq!(grams, 250);
     }
 
@@ -177,17 +224,15 @@ mod tests {
             String::from("CUCURUCHO"),
             vec![String::from("Limon"), String::from("Pistacho")],
         );
-        let grams;
-        match working_order.order {
+        let grams = match working_order.order {
             Request::ScreenToRobotOrder {
+                order_id: _,
                 index: _,
                 flavors: _,
                 size,
-            } => {
-                grams = size;
-            }
-            _ => grams = 25000,
-        }
+            } => size,
+            _ => 25000,
+        };
         assert_eq!(grams, 0);
     }
 
@@ -200,6 +245,7 @@ mod tests {
         );
         let mut expected = String::new();
         if let Ok(serialized) = serde_json::to_string(&Request::ScreenToRobotOrder {
+            order_id: order.get_order_id(),
             index: 5,
             flavors: vec![String::from("Chocolate"), String::from("Vanilla")],
             size: 1000,
@@ -208,4 +254,18 @@ mod tests {
         }
         assert_eq!(order.get_serialized_order_information(), expected);
     }
+
+    #[test]
+    fn test_order_stays_pending_until_closed() {
+        let mut order =
+            ScreenWorkingOrder::new(6, String::from("KILO"), vec![String::from("Chocolate")]);
+        assert!(order.is_pending());
+        assert_eq!(order.new_attempt(), 1);
+        assert_eq!(order.new_attempt(), 2);
+        assert!(order.is_pending());
+
+        order.unsuccesfull_order(&OrderError::Timeout);
+        assert!(!order.is_pending());
+        assert_eq!(order.get_attempts(), 2);
+    }
 }
@@ -0,0 +1,30 @@
+use std::{error::Error, fs};
+
+use merge::{merge, parse_log, render};
+
+pub mod merge;
+
+pub fn start() -> Result<(), Box<dyn Error>> {
+    let mut paths: Vec<String> = std::env::args().collect();
+    paths.remove(0); // no necesito el nombre del programa
+
+    if paths.is_empty() {
+        return Err("You need to pass the log files of the nodes to merge".into());
+    }
+
+    let mut logs = Vec::new();
+    for (source, path) in paths.iter().enumerate() {
+        let contents = fs::read_to_string(path)
+            .map_err(|error| format!("Couldn't read log file {}: {}", path, error))?;
+        let (entries, skipped) = parse_log(source, &contents);
+        if skipped > 0 {
+            eprintln!("Skipped {} lines without a clock in {}", skipped, path);
+        }
+        logs.push(entries);
+    }
+
+    for line in render(&merge(logs)) {
+        println!("{}", line);
+    }
+    Ok(())
+}
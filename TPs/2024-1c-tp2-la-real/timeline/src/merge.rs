@@ -0,0 +1,225 @@
+//! Mezcla de los logs de varios nodos en una única línea de tiempo.
+//!
+//! Cada línea de log empieza con el `LogStamp` del nodo que la escribió. Ordenar las líneas
+//! por su reloj de Lamport da un orden consistente con la causalidad: si un evento causó a
+//! otro, aparece antes. Los empates se resuelven por nodo y, dentro de un nodo, por el orden
+//! en el que se escribieron. Si los nodos llevan relojes vectoriales, se marcan además las
+//! líneas concurrentes con la anterior, que podrían haber ocurrido en cualquier orden.
+
+use std::cmp::Ordering;
+
+use common::clock::LogStamp;
+
+/// Marca de las líneas concurrentes con la anterior de la línea de tiempo.
+pub const CONCURRENT_MARK: &str = "~ ";
+
+/// Línea de log de un nodo, junto con las líneas sin marca que la siguen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub stamp: LogStamp,
+    pub text: String,
+    /// Posición del archivo de log entre los recibidos.
+    pub source: usize,
+    /// Posición de la línea dentro de su archivo.
+    pub line: usize,
+}
+
+/// Separa las entradas de un archivo de log. Las líneas sin marca se agregan a la entrada
+/// anterior, como las de un mensaje de varias líneas.
+///
+/// # Argumentos
+///
+/// * `source` - Posición del archivo entre los recibidos.
+/// * `contents` - Contenido del archivo.
+///
+/// # Retorna
+///
+/// Las entradas del archivo y la cantidad de líneas descartadas por no tener una entrada
+/// anterior a la que agregarse.
+pub fn parse_log(source: usize, contents: &str) -> (Vec<LogEntry>, usize) {
+    let mut entries: Vec<LogEntry> = Vec::new();
+    let mut skipped = 0;
+
+    for (line, text) in contents.lines().enumerate() {
+        let text = strip_ansi(text);
+        match (LogStamp::parse(&text), entries.last_mut()) {
+            (Some((stamp, rest)), _) => entries.push(LogEntry {
+                stamp,
+                text: rest.to_string(),
+                source,
+                line,
+            }),
+            (None, Some(previous)) => {
+                previous.text.push('\n');
+                previous.text.push_str(&text);
+            }
+            (None, None) => skipped += 1,
+        }
+    }
+    (entries, skipped)
+}
+
+/// Mezcla las entradas de todos los logs en una línea de tiempo consistente con la causalidad.
+///
+/// # Argumentos
+///
+/// * `logs` - Entradas de cada archivo de log.
+pub fn merge(logs: Vec<Vec<LogEntry>>) -> Vec<LogEntry> {
+    let mut timeline: Vec<LogEntry> = logs.into_iter().flatten().collect();
+    timeline.sort_by(|a, b| {
+        a.stamp
+            .timestamp
+            .lamport
+            .cmp(&b.stamp.timestamp.lamport)
+            .then_with(|| a.stamp.node.cmp(&b.stamp.node))
+            .then_with(|| (a.source, a.line).cmp(&(b.source, b.line)))
+    });
+    timeline
+}
+
+/// Arma las líneas de salida de una línea de tiempo. Si las entradas traen relojes
+/// vectoriales, cada línea empieza con `CONCURRENT_MARK` si es concurrente con la anterior.
+///
+/// # Argumentos
+///
+/// * `timeline` - Entradas ya mezcladas.
+pub fn render(timeline: &[LogEntry]) -> Vec<String> {
+    let with_vectors = timeline
+        .iter()
+        .any(|entry| entry.stamp.timestamp.vector.is_some());
+
+    let mut previous: Option<&LogEntry> = None;
+    let mut lines = Vec::new();
+    for entry in timeline {
+        let mark = match previous {
+            _ if !with_vectors => "",
+            Some(previous) if concurrent(previous, entry) => CONCURRENT_MARK,
+            _ => "  ",
+        };
+        lines.push(format!("{}{} {}", mark, entry.stamp, entry.text));
+        previous = Some(entry);
+    }
+    lines
+}
+
+/// Dos líneas de nodos distintos son concurrentes si ninguna conoce a la otra. Con relojes
+/// iguales tampoco: ninguno de los dos nodos había registrado eventos propios que el otro viera.
+fn concurrent(a: &LogEntry, b: &LogEntry) -> bool {
+    match (&a.stamp.timestamp.vector, &b.stamp.timestamp.vector) {
+        (Some(a_vector), Some(b_vector)) if a.stamp.node != b.stamp.node => !matches!(
+            a_vector.partial_cmp(b_vector),
+            Some(Ordering::Less | Ordering::Greater)
+        ),
+        _ => false,
+    }
+}
+
+/// Quita los códigos de color ANSI de una línea, como los que agrega `tracing` en una terminal.
+fn strip_ansi(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use common::clock::NodeClock;
+
+    use super::*;
+
+    fn log(clock: &NodeClock, text: &str) -> String {
+        format!("{} {}\n", clock.stamp(), text)
+    }
+
+    #[test]
+    fn test_merge_orders_messages_after_their_causes() {
+        let screen = NodeClock::new("screen-42", false);
+        let robot = NodeClock::new("robot-10000", false);
+        for _ in 0..5 {
+            robot.send();
+        }
+
+        let mut robot_log = log(&robot, "Robot started");
+        let mut screen_log = log(&screen, "Screen started");
+        let order = screen.send();
+        screen_log += &log(&screen, "Order sent");
+        robot.receive(&order);
+        robot_log += &log(&robot, "Order received");
+        let result = robot.send();
+        robot_log += &log(&robot, "Order finished");
+        screen.receive(&result);
+        screen_log += &log(&screen, "Result received");
+
+        let (robot_entries, _) = parse_log(0, &robot_log);
+        let (screen_entries, _) = parse_log(1, &screen_log);
+        let texts: Vec<String> = merge(vec![robot_entries, screen_entries])
+            .into_iter()
+            .map(|entry| entry.text)
+            .collect();
+
+        assert_eq!(
+            texts,
+            vec![
+                "Screen started",
+                "Order sent",
+                "Robot started",
+                "Order received",
+                "Order finished",
+                "Result received"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unstamped_lines_are_continuations_or_skipped() {
+        let clock = NodeClock::new("shop-9999", false);
+        let contents = format!(
+            "Usage: shop\n\u{1b}[2m{}\u{1b}[0m  INFO shop: Stock:\nvanilla 10\n",
+            clock.stamp()
+        );
+
+        let (entries, skipped) = parse_log(0, &contents);
+
+        assert_eq!(skipped, 1);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].text, " INFO shop: Stock:\nvanilla 10");
+    }
+
+    #[test]
+    fn test_concurrent_entries_are_marked() {
+        let screen = NodeClock::new("screen-42", true);
+        let robot = NodeClock::new("robot-10000", true);
+        let screen_log = log(&screen, "Screen started");
+        let robot_log = log(&robot, "Robot started");
+        screen.send();
+        let screen_log = screen_log + &log(&screen, "Order sent");
+        robot.send();
+        let robot_log = robot_log + &log(&robot, "Election started");
+        robot.receive(&screen.now());
+        let robot_log = robot_log + &log(&robot, "Order received");
+
+        let timeline = merge(vec![
+            parse_log(0, &screen_log).0,
+            parse_log(1, &robot_log).0,
+        ]);
+        let lines = render(&timeline);
+
+        let marked: Vec<bool> = lines
+            .iter()
+            .map(|line| line.starts_with(CONCURRENT_MARK))
+            .collect();
+        assert_eq!(marked, vec![false, true, false, true, false]);
+        assert!(timeline[2].stamp.timestamp.vector < timeline[4].stamp.timestamp.vector);
+    }
+}